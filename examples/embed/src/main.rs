@@ -6,6 +6,7 @@ fn main() {
     example_runcode();
     example_runfile();
     example_variables();
+    example_owned();
 }
 
 fn example_runcode() {
@@ -37,9 +38,9 @@ fn example_variables() {
 
     let state = ring_state_init();
 
-    // Rust -> Ring: Set variable via runcode
-    let rust_value = 42.5;
-    ring_state_runcode_str(state, &format!("rust_number = {}", rust_value));
+    // Rust -> Ring: Create the variable directly, no source code involved
+    let var = ring_state_newvar_str(state, "rust_number");
+    RingValue::Double(42.5).set_variable(var);
     ring_state_runcode_str(state, r#"? "   rust_number = " + rust_number"#);
 
     // Ring computes something
@@ -54,3 +55,47 @@ fn example_variables() {
 
     ring_state_delete(state);
 }
+
+fn example_owned() {
+    println!("\n4. Owned runtime (Ring)\n");
+
+    // The state is deleted when `ring` goes out of scope
+    let mut ring = Ring::new();
+    ring.run(r#"total = 0 for i = 1 to 10 total += i next"#)
+        .expect("script failed");
+
+    let total: f64 = ring.get("total").expect("total is a number");
+    println!("   total = {}", total);
+
+    // Values are stored directly, so quotes in strings need no escaping
+    ring.set("greeting", r#"say "hi""#).expect("bad name");
+    ring.set("scores", vec![90, 85, 77]).expect("bad name");
+    ring.run(r#"? "   " + greeting + " / " + len(scores) + " scores""#)
+        .expect("script failed");
+
+    ring.run(r#"func scale n, factor return n * factor"#)
+        .expect("script failed");
+    let scaled: f64 = ring.call("scale", (21, 2)).expect("call failed");
+    println!("   scale(21, 2) = {}", scaled);
+
+    // Rust closures can capture state and are dropped with `ring`
+    let rate = 0.2;
+    ring.register_fn("with_tax", move |call| {
+        let amount: f64 = call.arg(1)?;
+        Ok::<_, ArgError>(amount * (1.0 + rate))
+    })
+    .expect("with_tax is a valid name");
+    ring.run(r#"? "   with_tax(100) = " + with_tax(100)"#)
+        .expect("script failed");
+
+    // Runtime errors are returned instead of terminating the process
+    match ring.run(r#"x = 1 / 0"#) {
+        Ok(()) => println!("   no error"),
+        Err(e) => println!("   caught: {}", e),
+    }
+
+    // Output can be returned instead of printed
+    let (result, output) = ring.run_captured(r#"see "captured!""#);
+    result.expect("script failed");
+    println!("   output = {:?}", String::from_utf8_lossy(&output));
+}
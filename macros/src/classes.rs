@@ -0,0 +1,593 @@
+//! Ring class wrappers for the structs of a `ring_extension!`.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+use std::path::PathBuf;
+
+use syn::ext::IdentExt;
+use syn::{FnArg, ImplItem, ImplItemFn, Item, ItemImpl, ItemStruct, Pat, Type, Visibility};
+
+use crate::{
+    FIELD_OPTIONS, FN_OPTIONS, PARAM_OPTIONS, RingOptions, doc_lines, is_option_type,
+    is_variadic_type, option_reference, ring_options,
+};
+
+/// Ring keywords, which cannot name methods or parameters.
+const RING_KEYWORDS: &[&str] = &[
+    "again",
+    "and",
+    "but",
+    "bye",
+    "call",
+    "case",
+    "catch",
+    "class",
+    "def",
+    "do",
+    "done",
+    "else",
+    "elseif",
+    "end",
+    "exit",
+    "for",
+    "foreach",
+    "from",
+    "func",
+    "function",
+    "get",
+    "give",
+    "if",
+    "import",
+    "in",
+    "load",
+    "loop",
+    "new",
+    "next",
+    "not",
+    "off",
+    "ok",
+    "on",
+    "or",
+    "other",
+    "package",
+    "private",
+    "put",
+    "return",
+    "see",
+    "step",
+    "switch",
+    "to",
+    "try",
+    "while",
+    "endfunc",
+    "endclass",
+    "endpackage",
+    "endif",
+    "endfor",
+    "endwhile",
+    "endswitch",
+    "endtry",
+    "endfunction",
+    "break",
+    "continue",
+    "this",
+    "self",
+    "super",
+    "main",
+    "init",
+    "operator",
+    "bracestart",
+    "braceexpreval",
+    "bracenewline",
+    "braceerror",
+    "braceend",
+    "ringvm_see",
+    "ringvm_give",
+    "ringvm_errorhandler",
+    "changeringkeyword",
+    "changeringoperator",
+    "loadsyntax",
+];
+
+/// Where the `classes:` option of `ring_extension!` writes the class file.
+pub(crate) enum ClassFile {
+    /// A file name inside `OUT_DIR`.
+    OutDir(String),
+    /// A path relative to the crate root, or an absolute path.
+    Path(String),
+}
+
+impl ClassFile {
+    fn resolve(&self) -> Result<PathBuf, String> {
+        match self {
+            ClassFile::OutDir(name) => std::env::var_os("OUT_DIR")
+                .map(|dir| PathBuf::from(dir).join(name))
+                .ok_or_else(|| {
+                    "`classes` writes to OUT_DIR, which is only set for crates with a build \
+                     script; add a build.rs or use `classes_path`"
+                        .to_string()
+                }),
+            ClassFile::Path(path) => {
+                let root = std::env::var_os("CARGO_MANIFEST_DIR").unwrap_or_default();
+                Ok(PathBuf::from(root).join(path))
+            }
+        }
+    }
+
+    /// Write `code`, leaving the file untouched when it is already up to date.
+    pub(crate) fn write(&self, code: &str) -> Result<(), String> {
+        let path = self.resolve()?;
+        if std::fs::read_to_string(&path).is_ok_and(|old| old == code) {
+            return Ok(());
+        }
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| format!("cannot create {}: {}", dir.display(), e))?;
+        }
+        std::fs::write(&path, code).map_err(|e| format!("cannot write {}: {}", path.display(), e))
+    }
+}
+
+/// A name Ring code can use for a method or parameter: keywords get a trailing `_`.
+fn ring_safe_name(name: &str) -> String {
+    if RING_KEYWORDS.contains(&name.to_lowercase().as_str()) {
+        format!("{}_", name)
+    } else {
+        name.to_string()
+    }
+}
+
+/// `value` becomes `Value`, for `getValue`/`setValue`.
+fn capitalize(name: &str) -> String {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// The extension struct a parameter of type `ty` (or a reference to it) takes, if any.
+fn takes_struct(ty: &Type, structs: &HashSet<String>) -> bool {
+    match ty {
+        Type::Reference(r) => takes_struct(&r.elem, structs),
+        Type::Path(p) => match option_reference(ty) {
+            Some(r) => takes_struct(&r.elem, structs),
+            None => p
+                .path
+                .get_ident()
+                .is_some_and(|ident| structs.contains(&ident.to_string())),
+        },
+        _ => false,
+    }
+}
+
+/// How a Ring method passes a parameter on to Rust.
+#[derive(Clone, Copy, PartialEq)]
+enum ParamKind {
+    Required,
+    /// A trailing `Option<T>` or `#[ring(default = expr)]` parameter, left out when `NULL`.
+    Optional,
+    /// A `RingArgs` or `&[RingValue]` parameter, which Ring code passes as a list.
+    Variadic,
+}
+
+/// A parameter of a method, with the name Ring code uses for it.
+struct Param {
+    name: String,
+    takes_struct: bool,
+    kind: ParamKind,
+}
+
+/// The typed parameters of a method.
+fn method_params(inputs: impl Iterator<Item = FnArg>, structs: &HashSet<String>) -> Vec<Param> {
+    let mut params: Vec<Param> = inputs
+        .filter_map(|arg| match arg {
+            FnArg::Typed(pat) => match &*pat.pat {
+                Pat::Ident(ident) => {
+                    let has_default = ring_options(&pat.attrs, PARAM_OPTIONS)
+                        .is_ok_and(|options| options.default.is_some());
+                    let kind = if is_variadic_type(&pat.ty) {
+                        ParamKind::Variadic
+                    } else if has_default || is_option_type(&pat.ty) {
+                        ParamKind::Optional
+                    } else {
+                        ParamKind::Required
+                    };
+                    Some(Param {
+                        name: ring_safe_name(&ident.ident.unraw().to_string()),
+                        takes_struct: takes_struct(&pat.ty, structs),
+                        kind,
+                    })
+                }
+                _ => None,
+            },
+            FnArg::Receiver(_) => None,
+        })
+        .collect();
+    // As in the flat API, only a run of optional parameters at the end can be left out
+    let mut required = false;
+    for param in params.iter_mut().rev() {
+        match param.kind {
+            ParamKind::Variadic => {}
+            ParamKind::Required => required = true,
+            ParamKind::Optional if required => param.kind = ParamKind::Required,
+            ParamKind::Optional => {}
+        }
+    }
+    params
+}
+
+/// Replace class instances passed for struct parameters with their pointers.
+fn unwrap_objects(code: &mut String, params: &[Param]) {
+    for param in params.iter().filter(|param| param.takes_struct) {
+        let _ = writeln!(
+            code,
+            "\t\tif isObject({0}) {0} = {0}.objectPointer() ok",
+            param.name
+        );
+    }
+}
+
+/// The call to `function`, starting with `pObject` when `with_self`, and passing the first
+/// `optional` optional parameters. A variadic list goes to the function's `_list` variant.
+fn call(function: &str, with_self: bool, params: &[Param], optional: usize) -> String {
+    let names = |kind| {
+        params
+            .iter()
+            .filter(move |param| param.kind == kind)
+            .map(|param| param.name.as_str())
+    };
+    let mut args: Vec<&str> = Vec::new();
+    if with_self {
+        args.push("pObject");
+    }
+    args.extend(names(ParamKind::Variadic));
+    args.extend(names(ParamKind::Required));
+    args.extend(names(ParamKind::Optional).take(optional));
+    let suffix = if names(ParamKind::Variadic).next().is_some() {
+        "_list"
+    } else {
+        ""
+    };
+    format!("{}{}({})", function, suffix, args.join(", "))
+}
+
+/// Write `statement` followed by the call to `function`. Ring functions take a fixed number of
+/// parameters, so optional ones are declared too, and those left `NULL` at the end are not
+/// passed, letting Rust apply `None` or the default.
+fn write_call(
+    code: &mut String,
+    statement: &str,
+    function: &str,
+    with_self: bool,
+    params: &[Param],
+) {
+    let optional: Vec<&str> = params
+        .iter()
+        .filter(|param| param.kind == ParamKind::Optional)
+        .map(|param| param.name.as_str())
+        .collect();
+    if optional.is_empty() {
+        let _ = writeln!(
+            code,
+            "\t\t{}{}",
+            statement,
+            call(function, with_self, params, 0)
+        );
+        return;
+    }
+    for given in 0..optional.len() {
+        let nulls: Vec<String> = optional[given..]
+            .iter()
+            .map(|name| format!("isNull({})", name))
+            .collect();
+        let keyword = if given == 0 { "if" } else { "but" };
+        let _ = writeln!(code, "\t\t{} {}", keyword, nulls.join(" and "));
+        let _ = writeln!(
+            code,
+            "\t\t\t{}{}",
+            statement,
+            call(function, with_self, params, given)
+        );
+    }
+    let _ = writeln!(code, "\t\telse");
+    let _ = writeln!(
+        code,
+        "\t\t\t{}{}",
+        statement,
+        call(function, with_self, params, optional.len())
+    );
+    let _ = writeln!(code, "\t\tok");
+}
+
+fn params_list(params: &[Param]) -> String {
+    let names: Vec<&str> = params.iter().map(|param| param.name.as_str()).collect();
+    if names.is_empty() {
+        String::new()
+    } else {
+        format!(" {}", names.join(", "))
+    }
+}
+
+/// `# ` comments for the `///` docs of an item.
+fn write_docs(code: &mut String, attrs: &[syn::Attribute], indent: &str) {
+    for line in doc_lines(attrs) {
+        if line.is_empty() {
+            let _ = writeln!(code, "{}#", indent);
+        } else {
+            let _ = writeln!(code, "{}# {}", indent, line);
+        }
+    }
+}
+
+/// Generate a Ring source file with one class per struct.
+///
+/// Each class holds the pointer in `pObject`, creates it in `init` with the struct's `_new`,
+/// declares the public fields as attributes backed by `getX`/`setX` (so `obj.x` and
+/// `obj.x = 1` call into Rust), forwards the `&self` methods, and releases the object in `delete`.
+/// The functions wrapped are the ones the macro registers, so the two always agree.
+///
+/// `init` and the methods take optional and variadic parameters as the flat functions do, within
+/// what Ring allows: every parameter is declared, trailing optional ones given as `NULL` are left
+/// out, and the variadic part is a list whose items become separate arguments.
+pub(crate) fn generate_ring_classes(
+    items: &[Item],
+    extension_prefix: &str,
+    item_options: &HashMap<String, RingOptions>,
+    impl_methods: &HashSet<(String, String)>,
+) -> String {
+    let structs: HashSet<String> = items
+        .iter()
+        .filter_map(|item| match item {
+            Item::Struct(s) => Some(s.ident.to_string()),
+            _ => None,
+        })
+        .collect();
+
+    let mut code = String::new();
+    code.push_str("# Generated by ring_extension!\n# Do not edit manually\n");
+    for item in items {
+        let Item::Struct(s) = item else { continue };
+        let options = &item_options[&s.ident.to_string()];
+        if options.skip {
+            continue;
+        }
+        let impls: Vec<&ItemImpl> = items
+            .iter()
+            .filter_map(|item| match item {
+                Item::Impl(i) if impl_self_name(i).as_deref() == Some(&s.ident.to_string()) => {
+                    Some(i)
+                }
+                _ => None,
+            })
+            .collect();
+        let base = format!(
+            "{}{}",
+            options.prefix(extension_prefix),
+            options.ring_name(s.ident.to_string().to_lowercase())
+        );
+        generate_class(&mut code, s, &impls, &base, impl_methods, &structs);
+    }
+    code
+}
+
+fn impl_self_name(i: &ItemImpl) -> Option<String> {
+    match &*i.self_ty {
+        Type::Path(p) => p.path.segments.last().map(|seg| seg.ident.to_string()),
+        _ => None,
+    }
+}
+
+/// A method registered for the struct, with its Ring name and function.
+struct Method<'a> {
+    item: &'a ImplItemFn,
+    name: String,
+    function: String,
+}
+
+fn generate_class(
+    code: &mut String,
+    s: &ItemStruct,
+    impls: &[&ItemImpl],
+    base: &str,
+    impl_methods: &HashSet<(String, String)>,
+    structs: &HashSet<String>,
+) {
+    let name = s.ident.to_string();
+
+    let methods: Vec<Method> = impls
+        .iter()
+        .flat_map(|i| &i.items)
+        .filter_map(|item| match item {
+            ImplItem::Fn(method) if matches!(method.vis, Visibility::Public(_)) => {
+                let options = ring_options(&method.attrs, FN_OPTIONS).ok()?;
+                let name = options.ring_name(method.sig.ident.unraw().to_string());
+                (!options.skip).then(|| Method {
+                    item: method,
+                    function: format!("{}_{}", base, name),
+                    name,
+                })
+            }
+            _ => None,
+        })
+        .collect();
+    let function_of = |rust_name: &str| {
+        methods
+            .iter()
+            .find(|m| m.item.sig.ident == rust_name)
+            .map(|m| m.function.clone())
+    };
+
+    // Public fields become attributes, read and written through the functions `process_struct`
+    // generates, or the methods that replace them
+    let mut fields = Vec::new();
+    if let syn::Fields::Named(named) = &s.fields {
+        for field in &named.named {
+            let Ok(options) = ring_options(&field.attrs, FIELD_OPTIONS) else {
+                continue;
+            };
+            if !matches!(field.vis, Visibility::Public(_)) || options.skip {
+                continue;
+            }
+            let field_name = field.ident.as_ref().unwrap().unraw().to_string();
+            let ring_name = options.ring_name(field_name.clone());
+            let has = |method: &str| impl_methods.contains(&(name.clone(), method.to_string()));
+            let getter = if has(&format!("get_{}", field_name)) {
+                function_of(&format!("get_{}", field_name))
+            } else if has(&field_name) {
+                function_of(&field_name)
+            } else {
+                Some(format!("{}_get_{}", base, ring_name))
+            };
+            let setter = if options.readonly {
+                None
+            } else if has(&format!("set_{}", field_name)) {
+                function_of(&format!("set_{}", field_name))
+            } else {
+                Some(format!("{}_set_{}", base, ring_name))
+            };
+            fields.push((field, ring_safe_name(&ring_name), getter, setter));
+        }
+    }
+
+    let _ = writeln!(code);
+    write_docs(code, &s.attrs, "");
+    let _ = writeln!(code, "Class {}\n", name);
+    let _ = writeln!(code, "\tpObject");
+    for (_, field, _, _) in &fields {
+        let _ = writeln!(code, "\t{}", field);
+    }
+
+    let constructor = methods.iter().find(|m| m.item.sig.ident == "new");
+    let params = constructor
+        .map(|m| method_params(m.item.sig.inputs.iter().cloned(), structs))
+        .unwrap_or_default();
+    let new_function = constructor.map_or_else(|| format!("{}_new", base), |m| m.function.clone());
+    let _ = writeln!(code);
+    if let Some(constructor) = constructor {
+        write_docs(code, &constructor.item.attrs, "\t");
+    }
+    let _ = writeln!(code, "\tFunc init{}", params_list(&params));
+    unwrap_objects(code, &params);
+    write_call(code, "pObject = ", &new_function, false, &params);
+    let _ = writeln!(code, "\t\treturn self");
+
+    let _ = writeln!(code, "\n\tFunc delete");
+    let _ = writeln!(code, "\t\t{}_delete(pObject)", base);
+
+    let _ = writeln!(code, "\n\tFunc objectPointer");
+    let _ = writeln!(code, "\t\treturn pObject");
+
+    for (field, ring_name, getter, setter) in &fields {
+        let _ = writeln!(code);
+        write_docs(code, &field.attrs, "\t");
+        let _ = writeln!(code, "\tFunc get{}", capitalize(ring_name));
+        match getter {
+            Some(getter) => {
+                let _ = writeln!(code, "\t\treturn {}(pObject)", getter);
+            }
+            None => {
+                let _ = writeln!(code, "\t\traise(\"{} is not readable\")", ring_name);
+            }
+        }
+        let _ = writeln!(code, "\n\tFunc set{} value", capitalize(ring_name));
+        match setter {
+            Some(setter) => {
+                let _ = writeln!(code, "\t\t{}(pObject, value)", setter);
+            }
+            None => {
+                let _ = writeln!(code, "\t\traise(\"{} is read-only\")", ring_name);
+            }
+        }
+    }
+
+    for method in methods.iter().filter(|m| m.item.sig.ident != "new") {
+        let with_self = matches!(method.item.sig.inputs.first(), Some(FnArg::Receiver(_)));
+        // Static methods stay plain functions, as in the flat API
+        if !with_self {
+            continue;
+        }
+        let params = method_params(method.item.sig.inputs.iter().cloned(), structs);
+        let _ = writeln!(code);
+        write_docs(code, &method.item.attrs, "\t");
+        let _ = writeln!(
+            code,
+            "\tFunc {}{}",
+            ring_safe_name(&method.name),
+            params_list(&params)
+        );
+        unwrap_objects(code, &params);
+        write_call(code, "return ", &method.function, true, &params);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Generate the class file for `items` as `ring_extension!` does with the `mylib` prefix.
+    fn classes_for(items: Vec<Item>) -> String {
+        let mut item_options = HashMap::new();
+        let mut impl_methods = HashSet::new();
+        for item in &items {
+            match item {
+                Item::Struct(s) => {
+                    let options = ring_options(&s.attrs, crate::STRUCT_OPTIONS).unwrap();
+                    item_options.insert(s.ident.to_string(), options);
+                }
+                Item::Impl(i) => {
+                    for item in &i.items {
+                        if let ImplItem::Fn(method) = item {
+                            let name = (impl_self_name(i).unwrap(), method.sig.ident.to_string());
+                            impl_methods.insert(name);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        generate_ring_classes(&items, "mylib_", &item_options, &impl_methods)
+    }
+
+    /// Verify methods leave out trailing optional parameters given as `NULL` and pass a variadic
+    /// list to the `_list` function, before the named parameters
+    #[test]
+    fn test_classes_optional_and_variadic() {
+        let code = classes_for(vec![
+            syn::parse_quote! {
+                pub struct Logger {
+                    pub level: i32,
+                }
+            },
+            syn::parse_quote! {
+                impl Logger {
+                    pub fn pad(
+                        &self,
+                        text: &str,
+                        #[ring(default = 10)] width: usize,
+                        fill: Option<String>,
+                    ) -> String {
+                        String::new()
+                    }
+
+                    pub fn log(&self, level: &str, values: RingArgs) {}
+                }
+            },
+        ]);
+        assert!(code.contains(
+            "\tFunc pad text, width, fill\n\
+             \t\tif isNull(width) and isNull(fill)\n\
+             \t\t\treturn mylib_logger_pad(pObject, text)\n\
+             \t\tbut isNull(fill)\n\
+             \t\t\treturn mylib_logger_pad(pObject, text, width)\n\
+             \t\telse\n\
+             \t\t\treturn mylib_logger_pad(pObject, text, width, fill)\n\
+             \t\tok\n"
+        ));
+        assert!(code.contains(
+            "\tFunc log level, values\n\
+             \t\treturn mylib_logger_log_list(pObject, values, level)\n"
+        ));
+        assert!(code.contains("\tFunc init\n\t\tpObject = mylib_logger_new()\n"));
+    }
+}
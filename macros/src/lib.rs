@@ -74,6 +74,159 @@
 //! | `pub value: i64` field | `mylib_counter_get_value(ptr)`, `mylib_counter_set_value(ptr, v)` |
 //! | `impl Counter { pub fn new() }` | Replaces default `_new` with custom constructor |
 //! | `pub fn increment(&mut self)` | `mylib_counter_increment(ptr)` |
+//! | `pub enum Color { Red, DarkRed }` | `mylib_color_red()`, `mylib_color_dark_red()` (enums without fields) |
+//!
+//! ## Object Lifetime
+//!
+//! Constructors return managed pointers: Ring's garbage collector frees the object once no
+//! variable refers to it, so calling `_delete` is optional. `_delete` sets the pointer it is given
+//! to NULL, so a second `_delete` does nothing and other generated functions report an invalid
+//! pointer; the object itself is freed by the garbage collector once no other copy of the pointer
+//! is left. Mark a struct `#[ring(unmanaged)]` to return plain pointers that `_delete` frees right
+//! away. It still sets the pointer to NULL, but copies of it made earlier are left dangling.
+//!
+//! ## Ring Classes
+//!
+//! Add `classes: "mylib.ring";` to write a Ring file with one class per struct to `OUT_DIR` (the
+//! crate needs a `build.rs`, which can be empty), or `classes_path: "ring/mylib.ring";` to write it
+//! relative to the crate root. The file is rewritten when the bindings change:
+//!
+//! ```rust,ignore
+//! ring_extension! {
+//!     prefix: "mylib";
+//!     classes_path: "ring/mylib.ring";
+//!
+//!     #[derive(Default)]
+//!     pub struct Counter {
+//!         pub value: i64,
+//!     }
+//!
+//!     impl Counter {
+//!         pub fn increment(&mut self) {
+//!             self.value += 1;
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! Each class keeps the pointer in `pObject`, creates it in `init`, exposes public fields as
+//! attributes through `getX`/`setX` methods and forwards `&self` methods. Static methods stay plain
+//! functions, and struct values returned by methods are pointers. Ring keywords used as method or
+//! parameter names get a trailing `_`.
+//!
+//! ```ring
+//! loadlib("libmylib.so")
+//! load "ring/mylib.ring"
+//!
+//! c = new Counter
+//! c.increment()
+//! c.value = c.value + 10
+//! ? c.value       # 11
+//! c.delete()
+//! ```
+//!
+//! ## Attributes
+//!
+//! `#[ring(...)]` changes what an item exports. Use it to keep the names of an existing Ring API:
+//!
+//! | Attribute | On | Effect |
+//! |-----------|----|--------|
+//! | `name = "..."` | struct, enum | Replaces the lowercased type name in its functions |
+//! | `name = "..."` | field, method, function | Replaces the Rust name in its Ring functions |
+//! | `prefix = "..."` | struct, enum | Replaces the extension prefix for its functions (`""` for none) |
+//! | `skip` | any item | Leaves the item as plain Rust, exporting nothing |
+//! | `readonly` / `getter_only` | field | Generates the getter only |
+//! | `managed` / `unmanaged` | struct | See [Object Lifetime](#object-lifetime) |
+//! | `default = expr` | parameter | Makes the parameter optional, see [Optional Parameters](#optional-parameters) |
+//!
+//! ```rust,ignore
+//! ring_extension! {
+//!     prefix: "mylib";
+//!
+//!     #[derive(Default)]
+//!     #[ring(prefix = "", name = "hnd")]
+//!     pub struct Handle {
+//!         #[ring(readonly)]
+//!         pub id: u32,           // hnd_get_id(h)
+//!         #[ring(name = "label")]
+//!         pub title: String,     // hnd_get_label(h), hnd_set_label(h, v)
+//!         #[ring(skip)]
+//!         pub cache: Vec<u8>,
+//!     }
+//!
+//!     impl Handle {
+//!         #[ring(name = "open")]
+//!         pub fn new(id: u32) -> Self {  // hnd_open(id)
+//!             Handle { id, ..Default::default() }
+//!         }
+//!     }
+//!
+//!     #[ring(name = "sum")]
+//!     pub fn add(a: i32, b: i32) -> i32 {  // mylib_sum(a, b)
+//!         a + b
+//!     }
+//! }
+//! ```
+//!
+//! Names must be lowercase letters, digits and underscores. Generated Ring classes follow the same
+//! options, and carry the items' `///` comments.
+//!
+//! ## Optional Parameters
+//!
+//! Trailing parameters of type `Option<T>` or marked `#[ring(default = expr)]` may be left out by
+//! Ring callers. Missing `Option` parameters are `None`, and the others take their default:
+//!
+//! ```rust,ignore
+//! ring_extension! {
+//!     prefix: "mylib";
+//!
+//!     pub fn pad(text: &str, #[ring(default = 10)] width: usize, fill: Option<String>) -> String {
+//!         let fill = fill.unwrap_or_else(|| " ".to_string());
+//!         format!("{}{}", fill.repeat(width.saturating_sub(text.len())), text)
+//!     }
+//! }
+//! ```
+//!
+//! ```ring
+//! ? mylib_pad("7")            # "         7"
+//! ? mylib_pad("7", 3)         # "  7"
+//! ? mylib_pad("7", 3, "0")    # "007"
+//! ```
+//!
+//! The wrapper checks the count with `ring_check_paracount_range!`. A parameter with a default must
+//! only be followed by optional parameters. Ring functions take a fixed number of parameters, so
+//! methods of generated Ring classes declare every parameter, and leave out trailing optional
+//! ones given as `NULL`: `obj.pad("7", NULL, NULL)` calls `pad("7")` with the defaults.
+//!
+//! ## Variadic Functions
+//!
+//! A last parameter of type `RingArgs` (or `&[RingValue]`) takes every argument after the named
+//! ones, so Ring code can pass any number of values of mixed types:
+//!
+//! ```rust,ignore
+//! ring_extension! {
+//!     prefix: "mylib";
+//!
+//!     pub fn total(args: RingArgs) -> Result<f64, ArgError> {
+//!         (0..args.len()).map(|i| args.get_as::<f64>(i)).sum()
+//!     }
+//!
+//!     pub fn log(level: &str, values: &[RingValue]) {
+//!         eprintln!("[{}] {:?}", level, values);
+//!     }
+//! }
+//! ```
+//!
+//! ```ring
+//! ? mylib_total(1, 2, 3.5)      # 6.5
+//! mylib_log("info", "x", [1, 2])
+//! ```
+//!
+//! Only the named parameters are counted. Constructors and methods with a variadic parameter
+//! also get a `_list` function taking the variadic arguments as one list before the named ones,
+//! such as `mylib_logger_log_list(ptr, values, level)`. Generated Ring classes call it, so their
+//! methods take the variadic part as a list: `logger.log("info", ["x", [1, 2]])` passes `"x"` and
+//! `[1, 2]` as separate arguments.
 //!
 //! ## Ring Usage
 //!
@@ -91,10 +244,18 @@
 //!
 //! ## Supported Types
 //!
+//! Parameters, return values and field accessors are converted with the `FromRing` and `IntoRing`
+//! traits from `ring-lang-rs`, so any type implementing them works, including your own types with
+//! `#[derive(IntoRing, FromRing)]`. Structs declared inside `ring_extension!` get implementations
+//! that pass them to Ring as C pointers (`FromRing` needs `#[derive(Clone)]`); like constructor
+//! results, these pointers are freed by Ring's garbage collector unless the struct is
+//! `#[ring(unmanaged)]`. Each struct also gets a `RingType` implementation, and its pointers are
+//! tagged with `ring_type_name`, so a pointer to another type with the same name is rejected.
+//!
 //! ### Return Types
 //!
-//! | Rust Type | Ring Representation |
-//! |-----------|---------------------|
+//! | Type | Ring Representation |
+//! |------|---------------------|
 //! | `i8`, `i16`, `i32`, `i64`, `u8`, `u16`, `u32`, `u64`, `f32`, `f64` | Number |
 //! | `bool` | Number (1 or 0) |
 //! | `String`, `&str` | String |
@@ -105,12 +266,15 @@
 //! | `(A, B)`, `(A, B, C)` | List (tuple as list) |
 //! | `Box<T>` | Unwrapped inner value |
 //! | `HashMap<K, V>` | List of `[key, value]` pairs |
-//! | Custom structs | C pointer |
+//! | Structs in `ring_extension!` | C pointer |
+//! | `#[derive(IntoRing)]` structs | List of `[name, value]` pairs |
+//! | Enums without fields | Number (the discriminant) |
+//! | Enums with fields | List `[variant, payload...]` |
 //!
 //! ### Parameter Types
 //!
-//! | Rust Type | Ring Input |
-//! |-----------|------------|
+//! | Type | Ring Input |
+//! |------|------------|
 //! | `i8`, `i16`, `i32`, `i64`, `u8`, `u16`, `u32`, `u64`, `f32`, `f64` | Number |
 //! | `bool` | Number (non-zero = true) |
 //! | `&str`, `String` | String |
@@ -118,56 +282,75 @@
 //! | `&mut T` (mutable struct reference) | C pointer |
 //! | `Vec<T>` | List |
 //! | `&[T]` (slice) | List |
-//! | `Option<T>` | Value or empty string for None |
-//! | Custom structs | C pointer |
+//! | `Option<T>` | Value or empty string for None, or left out when trailing |
+//! | `Option<&str>`, `Option<&T>`, `Option<&[T]>` | As `Option<T>`, borrowed like the plain reference |
+//! | `(A, B)`, `HashMap<K, V>` | List |
+//! | `RingArgs`, `&[RingValue]` (last parameter) | All remaining arguments |
+//! | Structs in `ring_extension!` | C pointer |
+//! | `#[derive(FromRing)]` structs | List of `[name, value]` pairs or object |
+//! | Enums without fields | Number or variant name (`"dark_red"`, `"DarkRed"`) |
+//! | Enums with fields | List `[variant, payload...]`, or the name alone for a variant without fields |
 //!
 //! ### Field Types (Getters/Setters)
 //!
-//! | Rust Type | Get Returns | Set Accepts |
-//! |-----------|-------------|-------------|
-//! | Primitives | Number | Number |
-//! | `String` | String | String |
-//! | `Vec<T>` | List | List |
-//! | `Option<T>` | Value or empty string | Value or empty string |
-//! | Struct | C pointer | C pointer |
+//! Getters return `IntoRing::into_ring(field.clone())` and setters accept anything `FromRing` reads
+//! for the field's type, so the tables above apply to fields too.
+
+mod classes;
 
+use classes::ClassFile;
 use proc_macro::TokenStream;
 use proc_macro2::TokenStream as TokenStream2;
 use quote::{format_ident, quote};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use syn::ext::IdentExt;
 use syn::parse::{Parse, ParseStream};
 use syn::{
-    FnArg, Ident, ImplItem, ImplItemFn, Item, ItemFn, ItemImpl, ItemStruct, Pat, ReturnType, Token,
-    Type, Visibility, parse_macro_input,
+    Data, DeriveInput, Fields, FnArg, Ident, ImplItem, ImplItemFn, Item, ItemEnum, ItemFn,
+    ItemImpl, ItemStruct, Pat, ReturnType, Token, Type, Visibility, parse_macro_input,
 };
 
 struct RingExtension {
     prefix: Option<String>,
+    classes: Option<ClassFile>,
     items: Vec<Item>,
 }
 
 impl Parse for RingExtension {
     fn parse(input: ParseStream) -> syn::Result<Self> {
         let mut prefix = None;
+        let mut classes = None;
         let mut items = Vec::new();
 
         while !input.is_empty() {
             if input.peek(Ident) {
                 let ident: Ident = input.parse()?;
+                if ident != "prefix" && ident != "classes" && ident != "classes_path" {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "expected 'prefix', 'classes', 'classes_path' or item",
+                    ));
+                }
+                let _: Token![:] = input.parse()?;
+                let lit: syn::LitStr = input.parse()?;
+                let _: Token![;] = input.parse()?;
                 if ident == "prefix" {
-                    let _: Token![:] = input.parse()?;
-                    let lit: syn::LitStr = input.parse()?;
-                    let _: Token![;] = input.parse()?;
                     prefix = Some(lit.value());
-                    continue;
+                } else if ident == "classes" {
+                    classes = Some(ClassFile::OutDir(lit.value()));
                 } else {
-                    return Err(syn::Error::new(ident.span(), "expected 'prefix' or item"));
+                    classes = Some(ClassFile::Path(lit.value()));
                 }
+                continue;
             }
             items.push(input.parse()?);
         }
 
-        Ok(RingExtension { prefix, items })
+        Ok(RingExtension {
+            prefix,
+            classes,
+            items,
+        })
     }
 }
 
@@ -175,8 +358,26 @@ impl Parse for RingExtension {
 #[proc_macro]
 pub fn ring_extension(input: TokenStream) -> TokenStream {
     let module = parse_macro_input!(input as RingExtension);
+    let result = expand_extension(&module).and_then(|expansion| {
+        if let (Some(file), Some(code)) = (&module.classes, &expansion.classes) {
+            file.write(code)
+                .map_err(|message| syn::Error::new(proc_macro2::Span::call_site(), message))?;
+        }
+        Ok(expansion.tokens)
+    });
+    result.unwrap_or_else(|e| e.to_compile_error()).into()
+}
+
+/// The output of `ring_extension!`.
+struct Expansion {
+    /// The items as written, their wrappers and the `ring_libinit!` registering them.
+    tokens: TokenStream2,
+    /// The Ring class file, generated when the `classes` option is set.
+    classes: Option<String>,
+}
 
-    let prefix = module.prefix.unwrap_or_default();
+fn expand_extension(module: &RingExtension) -> syn::Result<Expansion> {
+    let prefix = module.prefix.clone().unwrap_or_default();
     let prefix_underscore = if prefix.is_empty() {
         String::new()
     } else {
@@ -185,13 +386,25 @@ pub fn ring_extension(input: TokenStream) -> TokenStream {
 
     let mut structs_with_custom_new: HashSet<String> = HashSet::new();
     let mut impl_methods: HashSet<(String, String)> = HashSet::new();
+    let mut item_options: HashMap<String, RingOptions> = HashMap::new();
 
     for item in &module.items {
+        let options = match item {
+            Item::Struct(s) => Some((&s.ident, ring_options(&s.attrs, STRUCT_OPTIONS))),
+            Item::Enum(e) => Some((&e.ident, ring_options(&e.attrs, ENUM_OPTIONS))),
+            _ => None,
+        };
+        if let Some((ident, options)) = options {
+            item_options.insert(ident.to_string(), options?);
+        }
         if let Item::Impl(i) = item {
             if let Type::Path(p) = &*i.self_ty {
                 let struct_name = p.path.segments.last().unwrap().ident.to_string();
                 for impl_item in &i.items {
                     if let ImplItem::Fn(method) = impl_item {
+                        if ring_options(&method.attrs, FN_OPTIONS).is_ok_and(|o| o.skip) {
+                            continue;
+                        }
                         let method_name = method.sig.ident.to_string();
                         if method_name == "new" {
                             structs_with_custom_new.insert(struct_name.clone());
@@ -207,34 +420,40 @@ pub fn ring_extension(input: TokenStream) -> TokenStream {
     let mut generated_code = Vec::new();
     let mut registrations: Vec<(String, syn::Ident)> = Vec::new();
 
-    for item in module.items {
-        match item {
+    for item in &module.items {
+        let processed = match item {
             Item::Struct(s) => {
                 let has_custom_new = structs_with_custom_new.contains(&s.ident.to_string());
-                let (orig, generated, regs) =
-                    process_struct(&s, &prefix_underscore, has_custom_new, &impl_methods);
-                original_items.push(orig);
-                generated_code.push(generated);
-                registrations.extend(regs);
-            }
-            Item::Impl(i) => {
-                let (orig, generated, regs) = process_impl(&i, &prefix_underscore);
-                original_items.push(orig);
-                generated_code.push(generated);
-                registrations.extend(regs);
-            }
-            Item::Fn(f) => {
-                let (orig, generated, regs) = process_function(&f, &prefix_underscore);
-                original_items.push(orig);
-                generated_code.push(generated);
-                registrations.extend(regs);
-            }
-            other => {
-                original_items.push(quote! { #other });
-            }
-        }
+                process_struct(
+                    s,
+                    &item_options[&s.ident.to_string()],
+                    &prefix_underscore,
+                    has_custom_new,
+                    &impl_methods,
+                )
+            }
+            Item::Impl(i) => process_impl(i, &prefix_underscore, &item_options),
+            Item::Enum(e) => {
+                process_enum(e, &item_options[&e.ident.to_string()], &prefix_underscore)
+            }
+            Item::Fn(f) => process_function(f, &prefix_underscore),
+            other => Ok((quote! { #other }, quote! {}, vec![])),
+        };
+        let (orig, generated, regs) = processed?;
+        original_items.push(orig);
+        generated_code.push(generated);
+        registrations.extend(regs);
     }
 
+    let classes = module.classes.as_ref().map(|_| {
+        classes::generate_ring_classes(
+            &module.items,
+            &prefix_underscore,
+            &item_options,
+            &impl_methods,
+        )
+    });
+
     let libinit_entries: Vec<_> = registrations
         .iter()
         .map(|(name, fn_ident)| {
@@ -242,7 +461,7 @@ pub fn ring_extension(input: TokenStream) -> TokenStream {
         })
         .collect();
 
-    let expanded = quote! {
+    let tokens = quote! {
         #(#original_items)*
         #(#generated_code)*
 
@@ -251,19 +470,209 @@ pub fn ring_extension(input: TokenStream) -> TokenStream {
         }
     };
 
-    expanded.into()
+    Ok(Expansion { tokens, classes })
+}
+
+/// An item as written, the code generated for it, and the Ring functions to register.
+type Processed = (TokenStream2, TokenStream2, Vec<(String, syn::Ident)>);
+
+/// Options set on an item with `#[ring(...)]`.
+#[derive(Clone)]
+struct RingOptions {
+    /// Constructors return pointers that Ring's garbage collector frees.
+    managed: bool,
+    /// Ring name replacing the item's own (lowercased for structs and enums).
+    name: Option<String>,
+    /// Prefix replacing the extension's for a struct's or enum's functions.
+    prefix: Option<String>,
+    /// The item is left as plain Rust, with nothing exported to Ring.
+    skip: bool,
+    /// Fields get a getter but no setter.
+    readonly: bool,
+    /// Value of a parameter the caller leaves out.
+    default: Option<syn::Expr>,
+}
+
+const STRUCT_OPTIONS: &[&str] = &["managed", "unmanaged", "name", "prefix", "skip"];
+const ENUM_OPTIONS: &[&str] = &["name", "prefix", "skip"];
+const FIELD_OPTIONS: &[&str] = &["name", "skip", "readonly", "getter_only"];
+const FN_OPTIONS: &[&str] = &["name", "skip"];
+const PARAM_OPTIONS: &[&str] = &["default"];
+
+/// Parse the `#[ring(...)]` attributes of an item, accepting only the options in `allowed`.
+fn ring_options(attrs: &[syn::Attribute], allowed: &[&str]) -> syn::Result<RingOptions> {
+    let mut options = RingOptions {
+        managed: true,
+        name: None,
+        prefix: None,
+        skip: false,
+        readonly: false,
+        default: None,
+    };
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("ring")) {
+        attr.parse_nested_meta(|meta| {
+            let key = meta
+                .path
+                .get_ident()
+                .map(|ident| ident.to_string())
+                .unwrap_or_default();
+            if !allowed.contains(&key.as_str()) {
+                let expected: Vec<String> = allowed.iter().map(|o| format!("`{}`", o)).collect();
+                let expected = match expected.split_last() {
+                    Some((last, rest)) if !rest.is_empty() => {
+                        format!("{} or {}", rest.join(", "), last)
+                    }
+                    _ => expected.join(""),
+                };
+                return Err(meta.error(format!("expected {}", expected)));
+            }
+            match key.as_str() {
+                "managed" => options.managed = true,
+                "unmanaged" => options.managed = false,
+                "skip" => options.skip = true,
+                "readonly" | "getter_only" => options.readonly = true,
+                "default" => options.default = Some(meta.value()?.parse()?),
+                "name" | "prefix" => {
+                    let lit: syn::LitStr = meta.value()?.parse()?;
+                    let value = lit.value();
+                    let valid = value
+                        .bytes()
+                        .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'_')
+                        && !value.starts_with(|c: char| c.is_ascii_digit());
+                    if !valid || (key == "name" && value.is_empty()) {
+                        return Err(syn::Error::new(
+                            lit.span(),
+                            "Ring names may only use lowercase letters, digits and underscores, \
+                             and may not start with a digit",
+                        ));
+                    }
+                    if key == "name" {
+                        options.name = Some(value);
+                    } else {
+                        options.prefix = Some(value);
+                    }
+                }
+                _ => unreachable!(),
+            }
+            Ok(())
+        })?;
+    }
+    Ok(options)
+}
+
+impl RingOptions {
+    /// The prefix for a struct's or enum's functions, such as `mylib_`.
+    fn prefix(&self, extension_prefix: &str) -> String {
+        match self.prefix.as_deref() {
+            Some("") => String::new(),
+            Some(prefix) => format!("{}_", prefix),
+            None => extension_prefix.to_string(),
+        }
+    }
+
+    /// The Ring name of an item, or `default` without a `name` option.
+    fn ring_name(&self, default: impl Into<String>) -> String {
+        self.name.clone().unwrap_or_else(|| default.into())
+    }
+}
+
+/// Remove the `#[ring(...)]` attributes only the macro understands from an item as written.
+fn strip_ring_attrs(attrs: &mut Vec<syn::Attribute>) {
+    attrs.retain(|attr| !attr.path().is_ident("ring"));
+}
+
+/// Remove the `#[ring(...)]` attributes of a function's parameters.
+fn strip_param_attrs(sig: &mut syn::Signature) {
+    for arg in &mut sig.inputs {
+        if let FnArg::Typed(pat) = arg {
+            strip_ring_attrs(&mut pat.attrs);
+        }
+    }
+}
+
+/// A struct without its `#[ring(...)]` attributes, including those on fields.
+fn strip_struct(s: &ItemStruct) -> ItemStruct {
+    let mut stripped = s.clone();
+    strip_ring_attrs(&mut stripped.attrs);
+    for field in stripped.fields.iter_mut() {
+        strip_ring_attrs(&mut field.attrs);
+    }
+    stripped
+}
+
+/// An impl block without the `#[ring(...)]` attributes on its methods.
+fn strip_impl(i: &ItemImpl) -> ItemImpl {
+    let mut stripped = i.clone();
+    for item in &mut stripped.items {
+        if let ImplItem::Fn(method) = item {
+            strip_ring_attrs(&mut method.attrs);
+            strip_param_attrs(&mut method.sig);
+        }
+    }
+    stripped
+}
+
+/// The `///` comments on an item, one line each.
+fn doc_lines(attrs: &[syn::Attribute]) -> Vec<String> {
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta {
+            syn::Meta::NameValue(nv) => match &nv.value {
+                syn::Expr::Lit(syn::ExprLit {
+                    lit: syn::Lit::Str(s),
+                    ..
+                }) => Some(s.value()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .flat_map(|doc| {
+            doc.lines()
+                .map(|line| {
+                    line.strip_prefix(' ')
+                        .unwrap_or(line)
+                        .trim_end()
+                        .to_string()
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// The code returning a new `obj: Box<Struct>` from a constructor.
+fn ret_new_object(type_const: &TokenStream2, free_fn: Option<&syn::Ident>) -> TokenStream2 {
+    match free_fn {
+        Some(free_fn) => quote! {
+            ring_ret_managed_cpointer!(p, Box::into_raw(obj), #type_const, #free_fn);
+        },
+        None => quote! {
+            ring_ret_cpointer!(p, Box::into_raw(obj), #type_const);
+        },
+    }
+}
+
+fn free_fn_name(prefix: &str, struct_name_lower: &str) -> syn::Ident {
+    format_ident!("ring_{}{}_gc_free", prefix, struct_name_lower)
 }
 
 fn process_struct(
     s: &ItemStruct,
-    prefix: &str,
+    options: &RingOptions,
+    extension_prefix: &str,
     has_custom_new: bool,
     impl_methods: &HashSet<(String, String)>,
-) -> (TokenStream2, TokenStream2, Vec<(String, syn::Ident)>) {
+) -> syn::Result<Processed> {
+    let stripped = strip_struct(s);
+    if options.skip {
+        return Ok((quote! { #stripped }, quote! {}, vec![]));
+    }
+
     let struct_name = &s.ident;
-    let struct_name_lower = struct_name.to_string().to_lowercase();
-    let type_const = format_ident!("{}_TYPE", struct_name.to_string().to_uppercase());
-    let type_const_str = format!("{}\0", struct_name);
+    let prefix = &options.prefix(extension_prefix);
+    let struct_name_lower = options.ring_name(struct_name.to_string().to_lowercase());
+    let managed = options.managed;
+    let type_const = struct_type_const(struct_name);
 
     let mut regs = Vec::new();
 
@@ -271,6 +680,9 @@ fn process_struct(
     let delete_ring_name = format!("{}{}_delete", prefix, struct_name_lower);
     regs.push((delete_ring_name, delete_fn_name.clone()));
 
+    let free_fn = free_fn_name(prefix, &struct_name_lower);
+    let ret_new = ret_new_object(&type_const, managed.then_some(&free_fn));
+
     let new_code = if !has_custom_new {
         let new_fn_name = format_ident!("ring_{}{}_new", prefix, struct_name_lower);
         let new_ring_name = format!("{}{}_new", prefix, struct_name_lower);
@@ -280,7 +692,7 @@ fn process_struct(
             ring_func!(#new_fn_name, |p| {
                 ring_check_paracount!(p, 0);
                 let obj = Box::new(#struct_name::default());
-                ring_ret_cpointer!(p, Box::into_raw(obj), #type_const);
+                #ret_new
             });
         }
     } else {
@@ -296,8 +708,14 @@ fn process_struct(
                 continue;
             }
 
+            let field_options = ring_options(&field.attrs, FIELD_OPTIONS)?;
+            if field_options.skip {
+                continue;
+            }
+
             let field_name = field.ident.as_ref().unwrap();
             let field_name_str = field_name.to_string();
+            let field_ring_name = field_options.ring_name(field_name.unraw().to_string());
             let field_type = &field.ty;
 
             let getter_method = format!("get_{}", field_name_str);
@@ -306,25 +724,32 @@ fn process_struct(
             if !impl_methods.contains(&(struct_name_str.clone(), getter_method.clone()))
                 && !impl_methods.contains(&(struct_name_str.clone(), field_name_str.clone()))
             {
-                let getter_fn =
-                    format_ident!("ring_{}{}_get_{}", prefix, struct_name_lower, field_name);
-                let getter_name = format!("{}{}_get_{}", prefix, struct_name_lower, field_name);
+                let getter_fn = format_ident!(
+                    "ring_{}{}_get_{}",
+                    prefix,
+                    struct_name_lower,
+                    field_ring_name
+                );
+                let getter_name =
+                    format!("{}{}_get_{}", prefix, struct_name_lower, field_ring_name);
                 regs.push((getter_name, getter_fn.clone()));
 
-                let getter_code = generate_field_getter(
-                    &getter_fn,
-                    struct_name,
-                    &type_const,
-                    field_name,
-                    field_type,
-                );
+                let getter_code =
+                    generate_field_getter(&getter_fn, struct_name, &type_const, field_name);
                 accessors.push(getter_code);
             }
 
-            if !impl_methods.contains(&(struct_name_str.clone(), setter_method)) {
-                let setter_fn =
-                    format_ident!("ring_{}{}_set_{}", prefix, struct_name_lower, field_name);
-                let setter_name = format!("{}{}_set_{}", prefix, struct_name_lower, field_name);
+            if !field_options.readonly
+                && !impl_methods.contains(&(struct_name_str.clone(), setter_method))
+            {
+                let setter_fn = format_ident!(
+                    "ring_{}{}_set_{}",
+                    prefix,
+                    struct_name_lower,
+                    field_ring_name
+                );
+                let setter_name =
+                    format!("{}{}_set_{}", prefix, struct_name_lower, field_ring_name);
                 regs.push((setter_name, setter_fn.clone()));
 
                 let setter_code = generate_field_setter(
@@ -339,10 +764,46 @@ fn process_struct(
         }
     }
 
-    let original = quote! { #s };
+    let original = quote! { #stripped };
+    let conversions = generate_struct_conversions(s, managed.then_some(&free_fn));
+
+    // Delete nulls the argument's pointer first, so a second delete sees nothing. A managed
+    // object is left to the GC, since other copies of the pointer may still use it.
+    let (free_code, delete_code) = if managed {
+        (
+            quote! {
+                extern "C" fn #free_fn(_state: *mut std::ffi::c_void, ptr: *mut std::ffi::c_void) {
+                    if !ptr.is_null() {
+                        unsafe { let _ = Box::from_raw(ptr as *mut #struct_name); }
+                    }
+                }
+            },
+            quote! {
+                if !ptr.is_null() {
+                    ring_api_setnullpointer(p, 1);
+                }
+            },
+        )
+    } else {
+        (
+            quote! {},
+            quote! {
+                if !ptr.is_null() {
+                    ring_api_setnullpointer(p, 1);
+                    unsafe { let _ = Box::from_raw(ptr as *mut #struct_name); }
+                }
+            },
+        )
+    };
 
     let generated = quote! {
-        const #type_const: &[u8] = #type_const_str.as_bytes();
+        impl RingType for #struct_name {
+            const TYPE_NAME: &'static str = #struct_name_str;
+        }
+
+        #conversions
+
+        #free_code
 
         #new_code
 
@@ -350,51 +811,138 @@ fn process_struct(
             ring_check_paracount!(p, 1);
             ring_check_cpointer!(p, 1);
             let ptr = ring_get_cpointer!(p, 1, #type_const);
-            if !ptr.is_null() {
-                unsafe { let _ = Box::from_raw(ptr as *mut #struct_name); }
-            }
+            #delete_code
         });
 
         #(#accessors)*
     };
 
-    (original, generated, regs)
+    Ok((original, generated, regs))
+}
+
+/// Conversions for an enum, plus a getter per variant of a C-like enum, such as
+/// `mylib_color_red()` for `Color::Red`.
+fn process_enum(
+    e: &ItemEnum,
+    options: &RingOptions,
+    extension_prefix: &str,
+) -> syn::Result<Processed> {
+    let mut stripped = e.clone();
+    strip_ring_attrs(&mut stripped.attrs);
+    let original = quote! { #stripped };
+    if options.skip {
+        return Ok((original, quote! {}, vec![]));
+    }
+
+    let enum_name = &e.ident;
+    let prefix = &options.prefix(extension_prefix);
+    let enum_name_lower = options.ring_name(enum_name.to_string().to_lowercase());
+    let data = syn::DataEnum {
+        enum_token: e.enum_token,
+        brace_token: e.brace_token,
+        variants: e.variants.clone(),
+    };
+
+    let into_ring = if has_derive(&e.attrs, "IntoRing") {
+        quote! {}
+    } else {
+        enum_into_ring(enum_name, &e.generics, &data)
+    };
+    let from_ring = if has_derive(&e.attrs, "FromRing") {
+        quote! {}
+    } else {
+        enum_from_ring(enum_name, &e.generics, &data)
+    };
+
+    let mut regs = Vec::new();
+    let mut getters = Vec::new();
+    if is_c_like(&data) {
+        for variant in &data.variants {
+            let ident = &variant.ident;
+            let tag = variant_tag(ident);
+            let fn_name = format_ident!("ring_{}{}_{}", prefix, enum_name_lower, tag);
+            let ring_name = format!("{}{}_{}", prefix, enum_name_lower, tag);
+            regs.push((ring_name, fn_name.clone()));
+            getters.push(quote! {
+                ring_func!(#fn_name, |p| {
+                    ring_check_paracount!(p, 0);
+                    ring_ret_number!(p, #enum_name::#ident as i64);
+                });
+            });
+        }
+    }
+
+    let generated = quote! {
+        #into_ring
+        #from_ring
+        #(#getters)*
+    };
+
+    Ok((original, generated, regs))
 }
 
 fn process_impl(
     i: &ItemImpl,
-    prefix: &str,
-) -> (TokenStream2, TokenStream2, Vec<(String, syn::Ident)>) {
+    extension_prefix: &str,
+    item_options: &HashMap<String, RingOptions>,
+) -> syn::Result<Processed> {
+    let stripped = strip_impl(i);
     let struct_name = match &*i.self_ty {
         Type::Path(p) => p.path.segments.last().unwrap().ident.clone(),
-        _ => return (quote! { #i }, quote! {}, vec![]),
+        _ => return Ok((quote! { #stripped }, quote! {}, vec![])),
     };
 
-    let struct_name_lower = struct_name.to_string().to_lowercase();
-    let type_const = format_ident!("{}_TYPE", struct_name.to_string().to_uppercase());
+    let options = item_options.get(&struct_name.to_string());
+    if options.is_some_and(|o| o.skip) {
+        return Ok((quote! { #stripped }, quote! {}, vec![]));
+    }
+    let prefix = &options.map_or_else(
+        || extension_prefix.to_string(),
+        |o| o.prefix(extension_prefix),
+    );
+    let struct_name_lower = match options {
+        Some(o) => o.ring_name(struct_name.to_string().to_lowercase()),
+        None => struct_name.to_string().to_lowercase(),
+    };
+    let type_const = struct_type_const(&struct_name);
 
     let mut regs = Vec::new();
     let mut method_wrappers = Vec::new();
 
     for item in &i.items {
         if let ImplItem::Fn(method) = item {
-            if !matches!(method.vis, Visibility::Public(_)) {
+            let method_options = ring_options(&method.attrs, FN_OPTIONS)?;
+            if !matches!(method.vis, Visibility::Public(_)) || method_options.skip {
                 continue;
             }
 
             let method_name = &method.sig.ident;
             let method_name_str = method_name.to_string();
+            let ring_method = method_options.ring_name(method_name.unraw().to_string());
+
+            // Generated classes call a variadic constructor or method through a second
+            // function that takes the variadic arguments as a list
+            let lists: &[bool] = if is_variadic(&method.sig) {
+                &[false, true]
+            } else {
+                &[false]
+            };
 
             if method_name_str == "new" {
-                let (code, name, fn_ident) = generate_custom_new(
-                    &struct_name,
-                    &struct_name_lower,
-                    &type_const,
-                    method,
-                    prefix,
-                );
-                method_wrappers.push(code);
-                regs.push((name, fn_ident));
+                let managed = options.is_none_or(|o| o.managed);
+                let free_fn = free_fn_name(prefix, &struct_name_lower);
+                for &list in lists {
+                    let (code, name, fn_ident) = generate_custom_new(
+                        &struct_name,
+                        &type_const,
+                        method,
+                        wrapper_name(prefix, &struct_name_lower, &ring_method, list),
+                        managed.then_some(&free_fn),
+                        list,
+                    )?;
+                    method_wrappers.push(code);
+                    regs.push((name, fn_ident));
+                }
                 continue;
             }
 
@@ -405,190 +953,111 @@ fn process_impl(
                 .any(|arg| matches!(arg, FnArg::Receiver(_)));
 
             if has_self {
-                let (code, name, fn_ident) = generate_method(
-                    &struct_name,
-                    &struct_name_lower,
-                    &type_const,
-                    method,
-                    prefix,
-                );
-                method_wrappers.push(code);
-                regs.push((name, fn_ident));
+                for &list in lists {
+                    let (code, name, fn_ident) = generate_method(
+                        &struct_name,
+                        &struct_name_lower,
+                        &type_const,
+                        method,
+                        &ring_method,
+                        prefix,
+                        list,
+                    )?;
+                    method_wrappers.push(code);
+                    regs.push((name, fn_ident));
+                }
             } else {
                 let (code, name, fn_ident) = generate_static_method(
                     &struct_name,
                     &struct_name_lower,
                     &type_const,
                     method,
+                    &ring_method,
                     prefix,
-                );
+                )?;
                 method_wrappers.push(code);
                 regs.push((name, fn_ident));
             }
         }
     }
 
-    let original = quote! { #i };
+    let original = quote! { #stripped };
     let generated = quote! { #(#method_wrappers)* };
 
-    (original, generated, regs)
+    Ok((original, generated, regs))
 }
 
-fn process_function(
-    f: &ItemFn,
-    prefix: &str,
-) -> (TokenStream2, TokenStream2, Vec<(String, syn::Ident)>) {
-    let fn_name = &f.sig.ident;
-    let ring_fn_name = format_ident!("ring_{}{}", prefix, fn_name);
-    let ring_name = format!("{}{}", prefix, fn_name);
-
-    let params: Vec<_> = f
-        .sig
-        .inputs
-        .iter()
-        .filter_map(|arg| {
-            if let FnArg::Typed(pat) = arg {
-                let name = if let Pat::Ident(ident) = &*pat.pat {
-                    ident.ident.clone()
-                } else {
-                    return None;
-                };
-                Some((name, (*pat.ty).clone()))
-            } else {
-                None
-            }
-        })
-        .collect();
-
-    let param_count = params.len();
-    let mut checks = Vec::new();
-    let mut gets = Vec::new();
-    let mut args = Vec::new();
-
-    for (i, (name, ty)) in params.iter().enumerate() {
-        let idx = (i + 1) as i32;
-        let binding = generate_param_binding(name, ty, idx);
-        checks.push(binding.check);
-        gets.push(binding.get);
-        args.push(binding.arg);
+fn process_function(f: &ItemFn, prefix: &str) -> syn::Result<Processed> {
+    let options = ring_options(&f.attrs, FN_OPTIONS)?;
+    let mut stripped = f.clone();
+    strip_ring_attrs(&mut stripped.attrs);
+    strip_param_attrs(&mut stripped.sig);
+    if options.skip {
+        return Ok((quote! { #stripped }, quote! {}, vec![]));
     }
 
-    let param_count_i32 = param_count as i32;
+    let fn_name = &f.sig.ident;
+    let ring_name = format!(
+        "{}{}",
+        prefix,
+        options.ring_name(fn_name.unraw().to_string())
+    );
+    let ring_fn_name = format_ident!("ring_{}", ring_name);
+
+    let Params {
+        count_check,
+        checks,
+        gets,
+        args,
+    } = generate_params(&f.sig, 1, false)?;
     let return_code = generate_return_code(&f.sig.output, quote! { #fn_name(#(#args),*) });
 
-    let original = quote! { #f };
+    let original = quote! { #stripped };
     let generated = quote! {
         ring_func!(#ring_fn_name, |p| {
-            ring_check_paracount!(p, #param_count_i32);
+            #count_check
             #(#checks)*
             #(#gets)*
             #return_code
         });
     };
 
-    (original, generated, vec![(ring_name, ring_fn_name)])
+    Ok((original, generated, vec![(ring_name, ring_fn_name)]))
 }
 
 fn generate_field_getter(
     fn_name: &syn::Ident,
     struct_name: &syn::Ident,
-    type_const: &syn::Ident,
+    type_const: &TokenStream2,
     field_name: &syn::Ident,
-    field_type: &Type,
 ) -> TokenStream2 {
-    let type_str = quote!(#field_type).to_string();
-    let return_expr = if is_number_type(&type_str) {
-        quote! { ring_ret_number!(p, obj.#field_name as f64); }
-    } else if is_string_type(&type_str) {
-        quote! { ring_ret_string!(p, &obj.#field_name); }
-    } else if type_str == "bool" {
-        quote! { ring_ret_number!(p, if obj.#field_name { 1.0 } else { 0.0 }); }
-    } else if is_vec_type(&type_str) {
-        let inner = extract_vec_inner(&type_str).unwrap_or_default();
-        if is_number_type(&inner) {
-            quote! {
-                let __list = ring_new_list!(p);
-                for __item in &obj.#field_name {
-                    ring_list_adddouble(__list, *__item as f64);
-                }
-                ring_ret_list!(p, __list);
-            }
-        } else if is_string_type(&inner) {
-            quote! {
-                let __list = ring_new_list!(p);
-                for __item in &obj.#field_name {
-                    ring_list_addstring_str(__list, &__item);
-                }
-                ring_ret_list!(p, __list);
-            }
-        } else if is_struct_type(&inner) {
-            let inner_type_const = struct_type_const(&extract_struct_name(&inner));
-            quote! {
-                let __list = ring_new_list!(p);
-                for __item in &obj.#field_name {
-                    let __ptr = Box::into_raw(Box::new(__item.clone()));
-                    ring_list_addcpointer(__list, __ptr as *mut std::ffi::c_void, #inner_type_const);
-                }
-                ring_ret_list!(p, __list);
+    quote! {
+        ring_func!(#fn_name, |p| {
+            ring_check_paracount!(p, 1);
+            ring_check_cpointer!(p, 1);
+            if let Some(obj) = ring_get_pointer!(p, 1, #struct_name, #type_const) {
+                ring_ret_value!(p, obj.#field_name.clone());
+            } else {
+                ring_error!(p, concat!("Invalid ", stringify!(#struct_name), " pointer"));
             }
-        } else {
-            quote! {
-                let __list = ring_new_list!(p);
-                for __item in &obj.#field_name {
-                    ring_list_adddouble(__list, *__item as f64);
-                }
-                ring_ret_list!(p, __list);
-            }
-        }
-    } else if is_option_type(&type_str) {
-        let inner = extract_option_inner(&type_str).unwrap_or_default();
-        if is_number_type(&inner) {
-            quote! {
-                match &obj.#field_name {
-                    Some(__val) => ring_ret_number!(p, *__val as f64),
-                    None => {},
-                }
-            }
-        } else if is_string_type(&inner) {
-            quote! {
-                match &obj.#field_name {
-                    Some(__val) => ring_ret_string!(p, __val),
-                    None => {},
-                }
-            }
-        } else if is_struct_type(&inner) {
-            let inner_type_const = struct_type_const(&extract_struct_name(&inner));
-            quote! {
-                match &obj.#field_name {
-                    Some(__val) => {
-                        ring_ret_cpointer!(p, Box::into_raw(Box::new(__val.clone())), #inner_type_const);
-                    }
-                    None => {},
-                }
-            }
-        } else {
-            quote! {
-                match &obj.#field_name {
-                    Some(__val) => ring_ret_number!(p, *__val as f64),
-                    None => {},
-                }
-            }
-        }
-    } else if is_struct_type(&type_str) {
-        let field_type_const = struct_type_const(&extract_struct_name(&type_str));
-        quote! {
-            ring_ret_cpointer!(p, Box::into_raw(Box::new(obj.#field_name.clone())), #field_type_const);
-        }
-    } else {
-        quote! { ring_ret_number!(p, obj.#field_name as f64); }
-    };
+        });
+    }
+}
 
+fn generate_field_setter(
+    fn_name: &syn::Ident,
+    struct_name: &syn::Ident,
+    type_const: &TokenStream2,
+    field_name: &syn::Ident,
+    field_type: &Type,
+) -> TokenStream2 {
     quote! {
         ring_func!(#fn_name, |p| {
-            ring_check_paracount!(p, 1);
+            ring_check_paracount!(p, 2);
             ring_check_cpointer!(p, 1);
             if let Some(obj) = ring_get_pointer!(p, 1, #struct_name, #type_const) {
-                #return_expr
+                let __value: #field_type = ring_get_value!(p, 2);
+                obj.#field_name = __value;
             } else {
                 ring_error!(p, concat!("Invalid ", stringify!(#struct_name), " pointer"));
             }
@@ -596,403 +1065,148 @@ fn generate_field_getter(
     }
 }
 
-fn generate_field_setter(
-    fn_name: &syn::Ident,
-    struct_name: &syn::Ident,
-    type_const: &syn::Ident,
-    field_name: &syn::Ident,
-    field_type: &Type,
-) -> TokenStream2 {
-    let type_str = quote!(#field_type).to_string();
+/// `IntoRing`/`FromRing` for a struct declared inside `ring_extension!`: values cross into Ring
+/// as a boxed C pointer tagged with the struct's `ring_type_name`. A managed struct's pointer is freed
+/// by Ring's garbage collector with `free_fn`, like the pointers its constructor returns.
+///
+/// `FromRing` clones the pointee, so it is only generated for structs that derive `Clone`. A
+/// struct that derives either trait itself keeps its own conversion.
+fn generate_struct_conversions(s: &ItemStruct, free_fn: Option<&syn::Ident>) -> TokenStream2 {
+    let struct_name = &s.ident;
+    let free_func = match free_fn {
+        // The pointer was just boxed here, so the value is its only owner.
+        Some(free_fn) => quote! { Some(unsafe { RingFreeFunc::new(#free_fn) }) },
+        None => quote! { None },
+    };
 
-    if is_number_type(&type_str) {
-        let cast = get_number_cast(&type_str);
-        quote! {
-            ring_func!(#fn_name, |p| {
-                ring_check_paracount!(p, 2);
-                ring_check_cpointer!(p, 1);
-                ring_check_number!(p, 2);
-                if let Some(obj) = ring_get_pointer!(p, 1, #struct_name, #type_const) {
-                    obj.#field_name = ring_get_number!(p, 2) as #cast;
-                } else {
-                    ring_error!(p, concat!("Invalid ", stringify!(#struct_name), " pointer"));
-                }
-            });
-        }
-    } else if is_string_type(&type_str) {
-        quote! {
-            ring_func!(#fn_name, |p| {
-                ring_check_paracount!(p, 2);
-                ring_check_cpointer!(p, 1);
-                ring_check_string!(p, 2);
-                if let Some(obj) = ring_get_pointer!(p, 1, #struct_name, #type_const) {
-                    obj.#field_name = ring_get_string!(p, 2).to_string();
-                } else {
-                    ring_error!(p, concat!("Invalid ", stringify!(#struct_name), " pointer"));
-                }
-            });
-        }
-    } else if type_str == "bool" {
+    let into_ring = if has_derive(&s.attrs, "IntoRing") {
+        quote! {}
+    } else {
         quote! {
-            ring_func!(#fn_name, |p| {
-                ring_check_paracount!(p, 2);
-                ring_check_cpointer!(p, 1);
-                ring_check_number!(p, 2);
-                if let Some(obj) = ring_get_pointer!(p, 1, #struct_name, #type_const) {
-                    obj.#field_name = ring_get_number!(p, 2) != 0.0;
-                } else {
-                    ring_error!(p, concat!("Invalid ", stringify!(#struct_name), " pointer"));
-                }
-            });
-        }
-    } else if is_vec_type(&type_str) {
-        let inner = extract_vec_inner(&type_str).unwrap_or_default();
-        if is_number_type(&inner) {
-            let cast = get_number_cast(&inner);
-            quote! {
-                ring_func!(#fn_name, |p| {
-                    ring_check_paracount!(p, 2);
-                    ring_check_cpointer!(p, 1);
-                    ring_check_list!(p, 2);
-                    if let Some(obj) = ring_get_pointer!(p, 1, #struct_name, #type_const) {
-                        let __list = ring_get_list!(p, 2);
-                        let __size = ring_list_getsize(__list);
-                        let mut __vec = Vec::with_capacity(__size as usize);
-                        for __i in 1..=__size {
-                            if ring_list_isnumber(__list, __i) {
-                                __vec.push(ring_list_getdouble(__list, __i) as #cast);
-                            }
-                        }
-                        obj.#field_name = __vec;
-                    } else {
-                        ring_error!(p, concat!("Invalid ", stringify!(#struct_name), " pointer"));
-                    }
-                });
-            }
-        } else if is_string_type(&inner) {
-            quote! {
-                ring_func!(#fn_name, |p| {
-                    ring_check_paracount!(p, 2);
-                    ring_check_cpointer!(p, 1);
-                    ring_check_list!(p, 2);
-                    if let Some(obj) = ring_get_pointer!(p, 1, #struct_name, #type_const) {
-                        let __list = ring_get_list!(p, 2);
-                        let __size = ring_list_getsize(__list);
-                        let mut __vec = Vec::with_capacity(__size as usize);
-                        for __i in 1..=__size {
-                            if ring_list_isstring(__list, __i) {
-                                let __cstr = ring_list_getstring(__list, __i);
-                                let __s = unsafe { std::ffi::CStr::from_ptr(__cstr).to_string_lossy().into_owned() };
-                                __vec.push(__s);
-                            }
-                        }
-                        obj.#field_name = __vec;
-                    } else {
-                        ring_error!(p, concat!("Invalid ", stringify!(#struct_name), " pointer"));
+            impl IntoRing for #struct_name {
+                fn into_ring(self) -> RingValue {
+                    RingValue::CPointer {
+                        type_name: ring_type_name::<#struct_name>().to_string_lossy().into_owned(),
+                        ptr: Box::into_raw(Box::new(self)) as *mut std::ffi::c_void,
+                        free_func: #free_func,
                     }
-                });
-            }
-        } else if is_struct_type(&inner) {
-            let inner_struct_name = extract_struct_name(&inner);
-            let inner_struct_ident = format_ident!("{}", inner_struct_name);
-            quote! {
-                ring_func!(#fn_name, |p| {
-                    ring_check_paracount!(p, 2);
-                    ring_check_cpointer!(p, 1);
-                    ring_check_list!(p, 2);
-                    if let Some(obj) = ring_get_pointer!(p, 1, #struct_name, #type_const) {
-                        let __list = ring_get_list!(p, 2);
-                        let __size = ring_list_getsize(__list);
-                        let mut __vec = Vec::with_capacity(__size as usize);
-                        for __i in 1..=__size {
-                            let __ptr = if ring_list_ispointer(__list, __i) {
-                                ring_list_getpointer(__list, __i)
-                            } else if ring_list_islist(__list, __i) {
-                                let __inner_list = ring_list_getlist(__list, __i);
-                                if ring_list_ispointer(__inner_list, 1) {
-                                    ring_list_getpointer(__inner_list, 1)
-                                } else {
-                                    std::ptr::null_mut()
-                                }
-                            } else {
-                                std::ptr::null_mut()
-                            };
-                            if !__ptr.is_null() {
-                                let __item = unsafe { &*(__ptr as *const #inner_struct_ident) };
-                                __vec.push(__item.clone());
-                            }
-                        }
-                        obj.#field_name = __vec;
-                    } else {
-                        ring_error!(p, concat!("Invalid ", stringify!(#struct_name), " pointer"));
-                    }
-                });
-            }
-        } else {
-            let cast = get_number_cast(&inner);
-            quote! {
-                ring_func!(#fn_name, |p| {
-                    ring_check_paracount!(p, 2);
-                    ring_check_cpointer!(p, 1);
-                    ring_check_list!(p, 2);
-                    if let Some(obj) = ring_get_pointer!(p, 1, #struct_name, #type_const) {
-                        let __list = ring_get_list!(p, 2);
-                        let __size = ring_list_getsize(__list);
-                        let mut __vec = Vec::with_capacity(__size as usize);
-                        for __i in 1..=__size {
-                            if ring_list_isnumber(__list, __i) {
-                                __vec.push(ring_list_getdouble(__list, __i) as #cast);
-                            }
-                        }
-                        obj.#field_name = __vec;
-                    } else {
-                        ring_error!(p, concat!("Invalid ", stringify!(#struct_name), " pointer"));
-                    }
-                });
-            }
-        }
-    } else if is_option_type(&type_str) {
-        let inner = extract_option_inner(&type_str).unwrap_or_default();
-        if is_number_type(&inner) {
-            let cast = get_number_cast(&inner);
-            quote! {
-                ring_func!(#fn_name, |p| {
-                    ring_check_paracount!(p, 2);
-                    ring_check_cpointer!(p, 1);
-                    if let Some(obj) = ring_get_pointer!(p, 1, #struct_name, #type_const) {
-                        if ring_api_isstring(p, 2) {
-                            let __s = ring_get_string!(p, 2);
-                            if __s.is_empty() {
-                                obj.#field_name = None;
-                            } else {
-                                ring_error!(p, "Expected number or empty string for Option");
-                            }
-                        } else if ring_api_isnumber(p, 2) {
-                            obj.#field_name = Some(ring_get_number!(p, 2) as #cast);
-                        } else {
-                            ring_error!(p, "Expected number or empty string for Option");
-                        }
-                    } else {
-                        ring_error!(p, concat!("Invalid ", stringify!(#struct_name), " pointer"));
-                    }
-                });
-            }
-        } else if is_string_type(&inner) {
-            quote! {
-                ring_func!(#fn_name, |p| {
-                    ring_check_paracount!(p, 2);
-                    ring_check_cpointer!(p, 1);
-                    ring_check_string!(p, 2);
-                    if let Some(obj) = ring_get_pointer!(p, 1, #struct_name, #type_const) {
-                        let __s = ring_get_string!(p, 2);
-                        if __s.is_empty() {
-                            obj.#field_name = None;
-                        } else {
-                            obj.#field_name = Some(__s.to_string());
-                        }
-                    } else {
-                        ring_error!(p, concat!("Invalid ", stringify!(#struct_name), " pointer"));
-                    }
-                });
-            }
-        } else if is_struct_type(&inner) {
-            let inner_struct_name = extract_struct_name(&inner);
-            let inner_struct_ident = format_ident!("{}", inner_struct_name);
-            let inner_type_const = struct_type_const(&inner_struct_name);
-            quote! {
-                ring_func!(#fn_name, |p| {
-                    ring_check_paracount!(p, 2);
-                    ring_check_cpointer!(p, 1);
-                    if let Some(obj) = ring_get_pointer!(p, 1, #struct_name, #type_const) {
-                        if ring_api_isstring(p, 2) {
-                            let __s = ring_get_string!(p, 2);
-                            if __s.is_empty() {
-                                obj.#field_name = None;
-                            } else {
-                                ring_error!(p, "Expected cpointer or empty string for Option<Struct>");
-                            }
-                        } else if ring_api_ispointer(p, 2) {
-                            let __ptr = ring_get_cpointer!(p, 2, #inner_type_const);
-                            if __ptr.is_null() {
-                                obj.#field_name = None;
-                            } else {
-                                let __val = unsafe { &*(__ptr as *const #inner_struct_ident) };
-                                obj.#field_name = Some(__val.clone());
-                            }
-                        } else {
-                            ring_error!(p, "Expected cpointer or empty string for Option<Struct>");
-                        }
-                    } else {
-                        ring_error!(p, concat!("Invalid ", stringify!(#struct_name), " pointer"));
-                    }
-                });
-            }
-        } else {
-            let cast = get_number_cast(&inner);
-            quote! {
-                ring_func!(#fn_name, |p| {
-                    ring_check_paracount!(p, 2);
-                    ring_check_cpointer!(p, 1);
-                    if let Some(obj) = ring_get_pointer!(p, 1, #struct_name, #type_const) {
-                        if ring_api_isstring(p, 2) {
-                            let __s = ring_get_string!(p, 2);
-                            if __s.is_empty() {
-                                obj.#field_name = None;
-                            } else {
-                                ring_error!(p, "Expected number or empty string for Option");
-                            }
-                        } else if ring_api_isnumber(p, 2) {
-                            obj.#field_name = Some(ring_get_number!(p, 2) as #cast);
-                        } else {
-                            ring_error!(p, "Expected number or empty string for Option");
-                        }
-                    } else {
-                        ring_error!(p, concat!("Invalid ", stringify!(#struct_name), " pointer"));
-                    }
-                });
+                }
             }
         }
-    } else if is_struct_type(&type_str) {
-        let field_struct_name = extract_struct_name(&type_str);
-        let field_struct_ident = format_ident!("{}", field_struct_name);
-        let field_type_const = struct_type_const(&field_struct_name);
+    };
+
+    let from_ring = if has_derive(&s.attrs, "FromRing") || !has_derive(&s.attrs, "Clone") {
+        quote! {}
+    } else {
         quote! {
-            ring_func!(#fn_name, |p| {
-                ring_check_paracount!(p, 2);
-                ring_check_cpointer!(p, 1);
-                ring_check_cpointer!(p, 2);
-                if let Some(obj) = ring_get_pointer!(p, 1, #struct_name, #type_const) {
-                    if let Some(val) = ring_get_pointer!(p, 2, #field_struct_ident, #field_type_const) {
-                        obj.#field_name = val.clone();
-                    } else {
-                        ring_error!(p, concat!("Invalid ", #field_struct_name, " pointer"));
+            impl FromRing for #struct_name {
+                fn from_ring(value: RingValue) -> Result<Self, RingError> {
+                    match value {
+                        RingValue::CPointer { ref type_name, ptr, .. }
+                            if type_name.as_bytes() == ring_type_name::<#struct_name>().to_bytes()
+                                && !ptr.is_null() =>
+                        {
+                            Ok(unsafe { (*(ptr as *const #struct_name)).clone() })
+                        }
+                        other => Err(ring_type_mismatch::<#struct_name>(&other)),
                     }
-                } else {
-                    ring_error!(p, concat!("Invalid ", stringify!(#struct_name), " pointer"));
                 }
-            });
+            }
         }
-    } else {
-        quote! {
-            ring_func!(#fn_name, |p| {
-                ring_check_paracount!(p, 2);
-                ring_check_cpointer!(p, 1);
-                ring_check_number!(p, 2);
-                if let Some(obj) = ring_get_pointer!(p, 1, #struct_name, #type_const) {
-                    obj.#field_name = ring_get_number!(p, 2) as _;
-                } else {
-                    ring_error!(p, concat!("Invalid ", stringify!(#struct_name), " pointer"));
+    };
+
+    quote! {
+        #into_ring
+        #from_ring
+    }
+}
+
+fn has_derive(attrs: &[syn::Attribute], name: &str) -> bool {
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("derive"))
+        .any(|attr| {
+            let mut found = false;
+            let _ = attr.parse_nested_meta(|meta| {
+                if meta
+                    .path
+                    .segments
+                    .last()
+                    .is_some_and(|seg| seg.ident == name)
+                {
+                    found = true;
                 }
+                Ok(())
             });
-        }
-    }
+            found
+        })
 }
 
+/// A constructor registered as `ring_name`. Its objects are freed with `free_fn` when managed.
 fn generate_custom_new(
     struct_name: &syn::Ident,
-    struct_name_lower: &str,
-    type_const: &syn::Ident,
+    type_const: &TokenStream2,
     method: &ImplItemFn,
-    prefix: &str,
-) -> (TokenStream2, String, syn::Ident) {
-    let fn_name = format_ident!("ring_{}{}_new", prefix, struct_name_lower);
-    let ring_name = format!("{}{}_new", prefix, struct_name_lower);
-
-    let params: Vec<_> = method
-        .sig
-        .inputs
-        .iter()
-        .filter_map(|arg| {
-            if let FnArg::Typed(pat) = arg {
-                let name = if let Pat::Ident(ident) = &*pat.pat {
-                    ident.ident.clone()
-                } else {
-                    return None;
-                };
-                Some((name, (*pat.ty).clone()))
-            } else {
-                None
-            }
-        })
-        .collect();
-
-    let param_count = params.len() as i32;
-    let mut checks = Vec::new();
-    let mut gets = Vec::new();
-    let mut args = Vec::new();
-
-    for (i, (name, ty)) in params.iter().enumerate() {
-        let idx = (i + 1) as i32;
-        let binding = generate_param_binding(name, ty, idx);
-        checks.push(binding.check);
-        gets.push(binding.get);
-        args.push(binding.arg);
-    }
+    ring_name: String,
+    free_fn: Option<&syn::Ident>,
+    list: bool,
+) -> syn::Result<(TokenStream2, String, syn::Ident)> {
+    let fn_name = format_ident!("ring_{}", ring_name);
+    let ret_new = ret_new_object(type_const, free_fn);
+
+    let Params {
+        count_check,
+        checks,
+        gets,
+        args,
+    } = generate_params(&method.sig, 1, list)?;
 
     let code = quote! {
         ring_func!(#fn_name, |p| {
-            ring_check_paracount!(p, #param_count);
+            #count_check
             #(#checks)*
             #(#gets)*
             let obj = Box::new(#struct_name::new(#(#args),*));
-            ring_ret_cpointer!(p, Box::into_raw(obj), #type_const);
+            #ret_new
         });
     };
 
-    (code, ring_name, fn_name)
+    Ok((code, ring_name, fn_name))
+}
+
+/// The Ring name of a struct's function, with `_list` for the variant that takes the variadic
+/// arguments as a list.
+fn wrapper_name(prefix: &str, struct_name_lower: &str, ring_method: &str, list: bool) -> String {
+    let suffix = if list { "_list" } else { "" };
+    format!("{}{}_{}{}", prefix, struct_name_lower, ring_method, suffix)
 }
 
 fn generate_method(
     struct_name: &syn::Ident,
     struct_name_lower: &str,
-    type_const: &syn::Ident,
+    type_const: &TokenStream2,
     method: &ImplItemFn,
+    ring_method: &str,
     prefix: &str,
-) -> (TokenStream2, String, syn::Ident) {
+    list: bool,
+) -> syn::Result<(TokenStream2, String, syn::Ident)> {
     let method_name = &method.sig.ident;
-    let fn_name = format_ident!("ring_{}{}_{}", prefix, struct_name_lower, method_name);
-    let ring_name = format!("{}{}_{}", prefix, struct_name_lower, method_name);
+    let ring_name = wrapper_name(prefix, struct_name_lower, ring_method, list);
+    let fn_name = format_ident!("ring_{}", ring_name);
 
-    let params: Vec<_> = method
-        .sig
-        .inputs
-        .iter()
-        .filter_map(|arg| {
-            if let FnArg::Typed(pat) = arg {
-                let name = if let Pat::Ident(ident) = &*pat.pat {
-                    ident.ident.clone()
-                } else {
-                    return None;
-                };
-                Some((name, (*pat.ty).clone()))
-            } else {
-                None
-            }
-        })
-        .collect();
-
-    let param_count = (params.len() + 1) as i32;
-    let mut checks = Vec::new();
-    let mut gets = Vec::new();
-    let mut args = Vec::new();
+    let Params {
+        count_check,
+        checks,
+        gets,
+        args,
+    } = generate_params(&method.sig, 2, list)?;
 
-    for (i, (name, ty)) in params.iter().enumerate() {
-        let idx = (i + 2) as i32;
-        let binding = generate_param_binding(name, ty, idx);
-        checks.push(binding.check);
-        gets.push(binding.get);
-        args.push(binding.arg);
-    }
-
-    let return_code = generate_return_code_with_context(
-        &method.sig.output,
-        quote! { obj.#method_name(#(#args),*) },
-        Some(struct_name),
-    );
+    let return_code =
+        generate_return_code(&method.sig.output, quote! { obj.#method_name(#(#args),*) });
 
     let code = quote! {
         ring_func!(#fn_name, |p| {
-            ring_check_paracount!(p, #param_count);
+            #count_check
             ring_check_cpointer!(p, 1);
             #(#checks)*
             if let Some(obj) = ring_get_pointer!(p, 1, #struct_name, #type_const) {
@@ -1004,1161 +1218,778 @@ fn generate_method(
         });
     };
 
-    (code, ring_name, fn_name)
+    Ok((code, ring_name, fn_name))
 }
 
 fn generate_static_method(
     struct_name: &syn::Ident,
     struct_name_lower: &str,
-    _type_const: &syn::Ident,
+    _type_const: &TokenStream2,
     method: &ImplItemFn,
+    ring_method: &str,
     prefix: &str,
-) -> (TokenStream2, String, syn::Ident) {
+) -> syn::Result<(TokenStream2, String, syn::Ident)> {
     let method_name = &method.sig.ident;
-    let fn_name = format_ident!("ring_{}{}_{}", prefix, struct_name_lower, method_name);
-    let ring_name = format!("{}{}_{}", prefix, struct_name_lower, method_name);
-
-    let params: Vec<_> = method
-        .sig
-        .inputs
-        .iter()
-        .filter_map(|arg| {
-            if let FnArg::Typed(pat) = arg {
-                let name = if let Pat::Ident(ident) = &*pat.pat {
-                    ident.ident.clone()
-                } else {
-                    return None;
-                };
-                Some((name, (*pat.ty).clone()))
-            } else {
-                None
-            }
-        })
-        .collect();
-
-    let param_count = params.len() as i32;
-    let mut checks = Vec::new();
-    let mut gets = Vec::new();
-    let mut args = Vec::new();
+    let ring_name = format!("{}{}_{}", prefix, struct_name_lower, ring_method);
+    let fn_name = format_ident!("ring_{}", ring_name);
 
-    for (i, (name, ty)) in params.iter().enumerate() {
-        let idx = (i + 1) as i32;
-        let binding = generate_param_binding(name, ty, idx);
-        checks.push(binding.check);
-        gets.push(binding.get);
-        args.push(binding.arg);
-    }
+    let Params {
+        count_check,
+        checks,
+        gets,
+        args,
+    } = generate_params(&method.sig, 1, false)?;
 
-    let return_code = generate_return_code_with_context(
+    let return_code = generate_return_code(
         &method.sig.output,
         quote! { #struct_name::#method_name(#(#args),*) },
-        Some(struct_name),
     );
 
     let code = quote! {
         ring_func!(#fn_name, |p| {
-            ring_check_paracount!(p, #param_count);
+            #count_check
             #(#checks)*
             #(#gets)*
-            #return_code
-        });
-    };
-
-    (code, ring_name, fn_name)
-}
-
-fn generate_return_code(output: &ReturnType, call: TokenStream2) -> TokenStream2 {
-    generate_return_code_with_context(output, call, None)
-}
-
-fn generate_return_code_with_context(
-    output: &ReturnType,
-    call: TokenStream2,
-    self_struct: Option<&syn::Ident>,
-) -> TokenStream2 {
-    match output {
-        ReturnType::Default => quote! { #call; },
-        ReturnType::Type(_, ty) => {
-            let type_str = quote!(#ty).to_string();
-            generate_return_for_type(&type_str, call, self_struct)
-        }
-    }
-}
-
-fn generate_return_for_type(
-    type_str: &str,
-    call: TokenStream2,
-    self_struct: Option<&syn::Ident>,
-) -> TokenStream2 {
-    let type_str = type_str.trim();
-
-    if is_number_type(type_str) {
-        quote! {
-            let __result = #call;
-            ring_ret_number!(p, __result as f64);
-        }
-    } else if is_string_type(type_str) {
-        quote! {
-            let __result = #call;
-            ring_ret_string!(p, &__result);
-        }
-    } else if type_str == "bool" {
-        quote! {
-            let __result = #call;
-            ring_ret_number!(p, if __result { 1.0 } else { 0.0 });
-        }
-    } else if is_vec_type(type_str) {
-        generate_vec_return(type_str, call)
-    } else if is_option_type(type_str) {
-        generate_option_return(type_str, call)
-    } else if is_result_type(type_str) {
-        generate_result_return(type_str, call)
-    } else if is_tuple_type(type_str) {
-        generate_tuple_return(type_str, call)
-    } else if is_box_type(type_str) {
-        generate_box_return(type_str, call)
-    } else if is_hashmap_type(type_str) {
-        generate_hashmap_return(type_str, call)
-    } else if type_str == "Self" {
-        if let Some(struct_name) = self_struct {
-            let type_const = struct_type_const(&struct_name.to_string());
-            quote! {
-                let __result = #call;
-                ring_ret_cpointer!(p, Box::into_raw(Box::new(__result)), #type_const);
-            }
-        } else {
-            quote! {
-                let __result = #call;
-                ring_ret_number!(p, __result as f64);
-            }
-        }
-    } else if is_struct_type(type_str) {
-        let struct_name = extract_struct_name(type_str);
-        let type_const = struct_type_const(&struct_name);
-        quote! {
-            let __result = #call;
-            ring_ret_cpointer!(p, Box::into_raw(Box::new(__result)), #type_const);
-        }
-    } else {
-        quote! {
-            let __result = #call;
-            ring_ret_number!(p, __result as f64);
-        }
-    }
-}
-
-fn generate_vec_return(type_str: &str, call: TokenStream2) -> TokenStream2 {
-    let inner = extract_vec_inner(type_str).unwrap_or_default();
-
-    if is_number_type(&inner) {
-        quote! {
-            let __result = #call;
-            let __list = ring_new_list!(p);
-            for __item in __result {
-                ring_list_adddouble(__list, __item as f64);
-            }
-            ring_ret_list!(p, __list);
-        }
-    } else if is_string_type(&inner) {
-        quote! {
-            let __result = #call;
-            let __list = ring_new_list!(p);
-            for __item in __result {
-                ring_list_addstring_str(__list, &__item);
-            }
-            ring_ret_list!(p, __list);
-        }
-    } else if is_option_type(&inner) {
-        let opt_inner = extract_option_inner(&inner).unwrap_or_default();
-        if is_number_type(&opt_inner) {
-            quote! {
-                let __result = #call;
-                let __list = ring_new_list!(p);
-                for __item in __result {
-                    match __item {
-                        Some(__val) => ring_list_adddouble(__list, __val as f64),
-                        None => ring_list_newitem(__list),
-                    }
-                }
-                ring_ret_list!(p, __list);
-            }
-        } else if is_string_type(&opt_inner) {
-            quote! {
-                let __result = #call;
-                let __list = ring_new_list!(p);
-                for __item in __result {
-                    match __item {
-                        Some(__val) => ring_list_addstring_str(__list, &__val),
-                        None => ring_list_newitem(__list),
-                    }
-                }
-                ring_ret_list!(p, __list);
-            }
-        } else if is_struct_type(&opt_inner) {
-            let type_const = struct_type_const(&extract_struct_name(&opt_inner));
-            quote! {
-                let __result = #call;
-                let __list = ring_new_list!(p);
-                for __item in __result {
-                    match __item {
-                        Some(__val) => {
-                            let __ptr = Box::into_raw(Box::new(__val));
-                            ring_list_addcpointer(__list, __ptr as *mut std::ffi::c_void, #type_const);
-                        }
-                        None => ring_list_newitem(__list),
-                    }
-                }
-                ring_ret_list!(p, __list);
-            }
-        } else {
-            quote! {
-                let __result = #call;
-                let __list = ring_new_list!(p);
-                for __item in __result {
-                    match __item {
-                        Some(__val) => ring_list_adddouble(__list, __val as f64),
-                        None => ring_list_newitem(__list),
-                    }
-                }
-                ring_ret_list!(p, __list);
-            }
-        }
-    } else if is_vec_type(&inner) {
-        let inner_inner = extract_vec_inner(&inner).unwrap_or_default();
-        let add_inner_item = if is_number_type(&inner_inner) {
-            quote! { ring_list_adddouble(__inner_list, __inner_item as f64); }
-        } else if is_string_type(&inner_inner) {
-            quote! { ring_list_addstring_str(__inner_list, &__inner_item); }
-        } else if is_struct_type(&inner_inner) {
-            let type_const = struct_type_const(&extract_struct_name(&inner_inner));
-            quote! {
-                let __ptr = Box::into_raw(Box::new(__inner_item));
-                ring_list_addcpointer(__inner_list, __ptr as *mut std::ffi::c_void, #type_const);
-            }
-        } else {
-            quote! { ring_list_adddouble(__inner_list, __inner_item as f64); }
-        };
-
-        quote! {
-            let __result = #call;
-            let __list = ring_new_list!(p);
-            for __item in __result {
-                let __inner_list = ring_list_newlist(__list);
-                for __inner_item in __item {
-                    #add_inner_item
-                }
-            }
-            ring_ret_list!(p, __list);
-        }
-    } else if is_struct_type(&inner) {
-        let type_const = struct_type_const(&extract_struct_name(&inner));
-        quote! {
-            let __result = #call;
-            let __list = ring_new_list!(p);
-            for __item in __result {
-                let __ptr = Box::into_raw(Box::new(__item));
-                ring_list_addcpointer(__list, __ptr as *mut std::ffi::c_void, #type_const);
-            }
-            ring_ret_list!(p, __list);
-        }
-    } else {
-        quote! {
-            let __result = #call;
-            let __list = ring_new_list!(p);
-            for __item in __result {
-                ring_list_adddouble(__list, __item as f64);
-            }
-            ring_ret_list!(p, __list);
-        }
-    }
-}
-
-fn generate_option_return(type_str: &str, call: TokenStream2) -> TokenStream2 {
-    let inner = extract_option_inner(type_str).unwrap_or_default();
-
-    if is_number_type(&inner) {
-        quote! {
-            let __result = #call;
-            match __result {
-                Some(__val) => ring_ret_number!(p, __val as f64),
-                None => {},
-            }
-        }
-    } else if is_string_type(&inner) {
-        quote! {
-            let __result = #call;
-            match __result {
-                Some(__val) => ring_ret_string!(p, &__val),
-                None => {},
-            }
-        }
-    } else if is_vec_type(&inner) {
-        let vec_inner = extract_vec_inner(&inner).unwrap_or_default();
-        if is_number_type(&vec_inner) {
-            quote! {
-                let __result = #call;
-                match __result {
-                    Some(__vec) => {
-                        let __list = ring_new_list!(p);
-                        for __item in __vec {
-                            ring_list_adddouble(__list, __item as f64);
-                        }
-                        ring_ret_list!(p, __list);
-                    }
-                    None => {},
-                }
-            }
-        } else if is_string_type(&vec_inner) {
-            quote! {
-                let __result = #call;
-                match __result {
-                    Some(__vec) => {
-                        let __list = ring_new_list!(p);
-                        for __item in __vec {
-                            ring_list_addstring_str(__list, &__item);
-                        }
-                        ring_ret_list!(p, __list);
-                    }
-                    None => {},
-                }
-            }
-        } else if is_struct_type(&vec_inner) {
-            let type_const = struct_type_const(&extract_struct_name(&vec_inner));
-            quote! {
-                let __result = #call;
-                match __result {
-                    Some(__vec) => {
-                        let __list = ring_new_list!(p);
-                        for __item in __vec {
-                            let __ptr = Box::into_raw(Box::new(__item));
-                            ring_list_addcpointer(__list, __ptr as *mut std::ffi::c_void, #type_const);
-                        }
-                        ring_ret_list!(p, __list);
-                    }
-                    None => {},
-                }
-            }
-        } else {
-            quote! {
-                let __result = #call;
-                match __result {
-                    Some(__vec) => {
-                        let __list = ring_new_list!(p);
-                        for __item in __vec {
-                            ring_list_adddouble(__list, __item as f64);
-                        }
-                        ring_ret_list!(p, __list);
-                    }
-                    None => {},
-                }
-            }
-        }
-    } else if is_struct_type(&inner) {
-        let type_const = struct_type_const(&extract_struct_name(&inner));
-        quote! {
-            let __result = #call;
-            match __result {
-                Some(__val) => {
-                    ring_ret_cpointer!(p, Box::into_raw(Box::new(__val)), #type_const);
-                }
-                None => {},
-            }
-        }
-    } else {
-        quote! {
-            let __result = #call;
-            match __result {
-                Some(__val) => ring_ret_number!(p, __val as f64),
-                None => {},
-            }
-        }
-    }
-}
-
-fn generate_result_return(type_str: &str, call: TokenStream2) -> TokenStream2 {
-    let ok_type = extract_result_ok(type_str).unwrap_or_default();
-
-    if ok_type == "()" || ok_type.is_empty() {
-        quote! {
-            let __result = #call;
-            match __result {
-                Ok(_) => {}
-                Err(__e) => ring_error!(p, &format!("{}", __e)),
-            }
-        }
-    } else if is_number_type(&ok_type) {
-        quote! {
-            let __result = #call;
-            match __result {
-                Ok(__val) => ring_ret_number!(p, __val as f64),
-                Err(__e) => ring_error!(p, &format!("{}", __e)),
-            }
-        }
-    } else if is_string_type(&ok_type) {
-        quote! {
-            let __result = #call;
-            match __result {
-                Ok(__val) => ring_ret_string!(p, &__val),
-                Err(__e) => ring_error!(p, &format!("{}", __e)),
-            }
-        }
-    } else if is_vec_type(&ok_type) {
-        let vec_inner = extract_vec_inner(&ok_type).unwrap_or_default();
-        if is_number_type(&vec_inner) {
-            quote! {
-                let __result = #call;
-                match __result {
-                    Ok(__vec) => {
-                        let __list = ring_new_list!(p);
-                        for __item in __vec {
-                            ring_list_adddouble(__list, __item as f64);
-                        }
-                        ring_ret_list!(p, __list);
-                    }
-                    Err(__e) => ring_error!(p, &format!("{}", __e)),
-                }
-            }
-        } else if is_string_type(&vec_inner) {
-            quote! {
-                let __result = #call;
-                match __result {
-                    Ok(__vec) => {
-                        let __list = ring_new_list!(p);
-                        for __item in __vec {
-                            ring_list_addstring_str(__list, &__item);
-                        }
-                        ring_ret_list!(p, __list);
-                    }
-                    Err(__e) => ring_error!(p, &format!("{}", __e)),
-                }
-            }
-        } else if is_struct_type(&vec_inner) {
-            let type_const = struct_type_const(&extract_struct_name(&vec_inner));
-            quote! {
-                let __result = #call;
-                match __result {
-                    Ok(__vec) => {
-                        let __list = ring_new_list!(p);
-                        for __item in __vec {
-                            let __ptr = Box::into_raw(Box::new(__item));
-                            ring_list_addcpointer(__list, __ptr as *mut std::ffi::c_void, #type_const);
-                        }
-                        ring_ret_list!(p, __list);
-                    }
-                    Err(__e) => ring_error!(p, &format!("{}", __e)),
-                }
-            }
-        } else {
-            quote! {
-                let __result = #call;
-                match __result {
-                    Ok(__vec) => {
-                        let __list = ring_new_list!(p);
-                        for __item in __vec {
-                            ring_list_adddouble(__list, __item as f64);
-                        }
-                        ring_ret_list!(p, __list);
-                    }
-                    Err(__e) => ring_error!(p, &format!("{}", __e)),
-                }
-            }
-        }
-    } else if is_struct_type(&ok_type) {
-        let type_const = struct_type_const(&extract_struct_name(&ok_type));
-        quote! {
-            let __result = #call;
-            match __result {
-                Ok(__val) => {
-                    ring_ret_cpointer!(p, Box::into_raw(Box::new(__val)), #type_const);
-                }
-                Err(__e) => ring_error!(p, &format!("{}", __e)),
-            }
-        }
-    } else {
-        quote! {
-            let __result = #call;
-            match __result {
-                Ok(__val) => ring_ret_number!(p, __val as f64),
-                Err(__e) => ring_error!(p, &format!("{}", __e)),
-            }
-        }
-    }
-}
-
-fn generate_tuple_return(type_str: &str, call: TokenStream2) -> TokenStream2 {
-    let elements = extract_tuple_elements(type_str);
-
-    if elements.is_empty() {
-        return quote! { #call; };
-    }
-
-    let mut add_statements = Vec::new();
-
-    for (i, elem_type) in elements.iter().enumerate() {
-        let idx = syn::Index::from(i);
-
-        let add_stmt = if is_number_type(elem_type) {
-            quote! { ring_list_adddouble(__list, __result.#idx as f64); }
-        } else if is_string_type(elem_type) {
-            quote! { ring_list_addstring_str(__list, &__result.#idx); }
-        } else if elem_type == "bool" {
-            quote! { ring_list_adddouble(__list, if __result.#idx { 1.0 } else { 0.0 }); }
-        } else if is_struct_type(elem_type) {
-            let type_const = struct_type_const(&extract_struct_name(elem_type));
-            quote! {
-                let __ptr = Box::into_raw(Box::new(__result.#idx));
-                ring_list_addcpointer(__list, __ptr as *mut std::ffi::c_void, #type_const);
-            }
-        } else {
-            quote! { ring_list_adddouble(__list, __result.#idx as f64); }
-        };
-
-        add_statements.push(add_stmt);
-    }
-
-    quote! {
-        let __result = #call;
-        let __list = ring_new_list!(p);
-        #(#add_statements)*
-        ring_ret_list!(p, __list);
-    }
-}
-
-fn generate_box_return(type_str: &str, call: TokenStream2) -> TokenStream2 {
-    let inner = extract_box_inner(type_str).unwrap_or_default();
-
-    if is_number_type(&inner) {
-        quote! {
-            let __result = #call;
-            ring_ret_number!(p, *__result as f64);
-        }
-    } else if is_string_type(&inner) {
-        quote! {
-            let __result = #call;
-            ring_ret_string!(p, &*__result);
-        }
-    } else if inner == "bool" {
-        quote! {
-            let __result = #call;
-            ring_ret_number!(p, if *__result { 1.0 } else { 0.0 });
-        }
-    } else if is_struct_type(&inner) {
-        let type_const = struct_type_const(&extract_struct_name(&inner));
-        quote! {
-            let __result = #call;
-            ring_ret_cpointer!(p, Box::into_raw(__result), #type_const);
-        }
-    } else {
-        quote! {
-            let __result = #call;
-            ring_ret_number!(p, *__result as f64);
-        }
-    }
-}
-
-fn generate_hashmap_return(type_str: &str, call: TokenStream2) -> TokenStream2 {
-    let (key_type, value_type) = extract_hashmap_kv(type_str).unwrap_or_default();
-
-    let add_key = if is_number_type(&key_type) {
-        quote! { ring_list_adddouble(__pair, __k as f64); }
-    } else if is_string_type(&key_type) {
-        quote! { ring_list_addstring_str(__pair, &__k); }
-    } else {
-        quote! { ring_list_addstring_str(__pair, &format!("{:?}", __k)); }
-    };
-
-    let add_value = if is_number_type(&value_type) {
-        quote! { ring_list_adddouble(__pair, __v as f64); }
-    } else if is_string_type(&value_type) {
-        quote! { ring_list_addstring_str(__pair, &__v); }
-    } else if value_type == "bool" {
-        quote! { ring_list_adddouble(__pair, if __v { 1.0 } else { 0.0 }); }
-    } else if is_struct_type(&value_type) {
-        let type_const = struct_type_const(&extract_struct_name(&value_type));
-        quote! {
-            let __ptr = Box::into_raw(Box::new(__v));
-            ring_list_addcpointer(__pair, __ptr as *mut std::ffi::c_void, #type_const);
-        }
-    } else {
-        quote! { ring_list_adddouble(__pair, __v as f64); }
-    };
-
-    quote! {
-        let __result = #call;
-        let __list = ring_new_list!(p);
-        for (__k, __v) in __result {
-            let __pair = ring_list_newlist(__list);
-            #add_key
-            #add_value
-        }
-        ring_ret_list!(p, __list);
-    }
-}
-
-fn is_number_type(ty: &str) -> bool {
-    let ty = ty.trim();
-    matches!(
-        ty,
-        "i8" | "i16"
-            | "i32"
-            | "i64"
-            | "i128"
-            | "isize"
-            | "u8"
-            | "u16"
-            | "u32"
-            | "u64"
-            | "u128"
-            | "usize"
-            | "f32"
-            | "f64"
-    )
-}
+            #return_code
+        });
+    };
 
-fn is_string_type(ty: &str) -> bool {
-    let ty = ty.trim();
-    ty == "String" || ty == "& str" || ty.contains("str")
+    Ok((code, ring_name, fn_name))
 }
 
-fn get_number_cast(ty: &str) -> TokenStream2 {
-    let ty = ty.trim();
-    match ty {
-        "i8" => quote!(i8),
-        "i16" => quote!(i16),
-        "i32" => quote!(i32),
-        "i64" => quote!(i64),
-        "i128" => quote!(i128),
-        "isize" => quote!(isize),
-        "u8" => quote!(u8),
-        "u16" => quote!(u16),
-        "u32" => quote!(u32),
-        "u64" => quote!(u64),
-        "u128" => quote!(u128),
-        "usize" => quote!(usize),
-        "f32" => quote!(f32),
-        "f64" => quote!(f64),
-        _ => quote!(f64),
+fn generate_return_code(output: &ReturnType, call: TokenStream2) -> TokenStream2 {
+    match output {
+        ReturnType::Default => quote! { #call; },
+        ReturnType::Type(_, ty) if is_unit_type(ty) => quote! { #call; },
+        ReturnType::Type(_, ty) if is_result_type(ty) => quote! {
+            match #call {
+                Ok(__value) => ring_ret_value!(p, __value),
+                Err(__e) => ring_error!(p, &format!("{}", __e)),
+            }
+        },
+        ReturnType::Type(..) => quote! {
+            let __result = #call;
+            ring_ret_value!(p, __result);
+        },
     }
 }
 
-fn is_vec_type(ty: &str) -> bool {
-    let ty = ty.trim();
-    ty.starts_with("Vec <") || ty.starts_with("Vec<")
+fn is_unit_type(ty: &Type) -> bool {
+    matches!(ty, Type::Tuple(t) if t.elems.is_empty())
 }
 
-fn is_option_type(ty: &str) -> bool {
-    let ty = ty.trim();
-    ty.starts_with("Option <") || ty.starts_with("Option<")
+fn is_result_type(ty: &Type) -> bool {
+    match ty {
+        Type::Path(p) => p
+            .path
+            .segments
+            .last()
+            .is_some_and(|seg| seg.ident == "Result"),
+        _ => false,
+    }
 }
 
-fn is_result_type(ty: &str) -> bool {
-    let ty = ty.trim();
-    ty.starts_with("Result <") || ty.starts_with("Result<")
+/// Whether the last parameter of `sig` collects the remaining arguments.
+fn is_variadic(sig: &syn::Signature) -> bool {
+    matches!(sig.inputs.last(), Some(FnArg::Typed(pat)) if is_variadic_type(&pat.ty))
 }
 
-fn is_tuple_type(ty: &str) -> bool {
-    let ty = ty.trim();
-    ty.starts_with('(') && ty.ends_with(')') && ty.contains(',')
+/// `RingArgs` or `&[RingValue]`, which collect the remaining arguments.
+fn is_variadic_type(ty: &Type) -> bool {
+    let last_ident = |ty: &Type| match ty {
+        Type::Path(p) => p.path.segments.last().map(|seg| seg.ident.to_string()),
+        _ => None,
+    };
+    match ty {
+        Type::Reference(r) => {
+            matches!(&*r.elem, Type::Slice(s) if last_ident(&s.elem).as_deref() == Some("RingValue"))
+        }
+        other => last_ident(other).as_deref() == Some("RingArgs"),
+    }
 }
 
-fn is_box_type(ty: &str) -> bool {
-    let ty = ty.trim();
-    ty.starts_with("Box <") || ty.starts_with("Box<")
+fn is_option_type(ty: &Type) -> bool {
+    match ty {
+        Type::Path(p) => p
+            .path
+            .segments
+            .last()
+            .is_some_and(|seg| seg.ident == "Option"),
+        _ => false,
+    }
 }
 
-fn is_hashmap_type(ty: &str) -> bool {
-    let ty = ty.trim();
-    ty.starts_with("HashMap <")
-        || ty.starts_with("HashMap<")
-        || ty.starts_with("std :: collections :: HashMap")
-        || ty.contains("HashMap <")
-        || ty.contains("HashMap<")
+/// The reference inside an `Option<&T>` type.
+fn option_reference(ty: &Type) -> Option<&syn::TypeReference> {
+    let Type::Path(p) = ty else { return None };
+    let seg = p.path.segments.last().filter(|seg| seg.ident == "Option")?;
+    let syn::PathArguments::AngleBracketed(args) = &seg.arguments else {
+        return None;
+    };
+    match args.args.first() {
+        Some(syn::GenericArgument::Type(Type::Reference(r))) => Some(r),
+        _ => None,
+    }
 }
 
-fn is_slice_type(ty: &str) -> bool {
-    let ty = ty.trim();
-    ty.starts_with("& [") || ty.starts_with("&[")
+fn is_str_type(ty: &Type) -> bool {
+    matches!(ty, Type::Path(p) if p.path.is_ident("str"))
 }
 
-fn is_struct_type(ty: &str) -> bool {
-    let ty = ty.trim();
-    let ty = ty.trim_start_matches('&').trim_start_matches("mut ").trim();
-
-    if is_vec_type(ty)
-        || is_option_type(ty)
-        || is_result_type(ty)
-        || is_tuple_type(ty)
-        || is_box_type(ty)
-        || is_hashmap_type(ty)
-    {
-        return false;
-    }
-
-    if is_number_type(ty) || is_string_type(ty) || ty == "bool" || ty == "()" {
-        return false;
+/// The struct named by `ty` if it looks like one of the extension's own structs, which Ring code
+/// holds as C pointers.
+fn struct_ident(ty: &Type) -> Option<&syn::Ident> {
+    let Type::Path(p) = ty else { return None };
+    if p.qself.is_some() || p.path.segments.len() != 1 {
+        return None;
     }
+    let seg = &p.path.segments[0];
+    let name = seg.ident.to_string();
+    let is_struct = seg.arguments.is_none()
+        && name != "String"
+        && name != "Self"
+        && name.starts_with(|c: char| c.is_ascii_uppercase());
+    is_struct.then_some(&seg.ident)
+}
 
-    ty.chars()
-        .next()
-        .map(|c| c.is_ascii_uppercase())
-        .unwrap_or(false)
+/// The pointer type name of one of the extension's structs, as a NUL-terminated `&[u8]`.
+fn struct_type_const(struct_name: &syn::Ident) -> TokenStream2 {
+    quote! { ring_type_name::<#struct_name>().to_bytes_with_nul() }
 }
 
-fn is_struct_ref(ty: &str) -> bool {
-    let ty = ty.trim();
-    ty.starts_with('&') && is_struct_type(ty)
+/// The parameter handling of a wrapper, with the Rust parameters starting at Ring parameter
+/// `first` (after the object pointer of a method).
+struct Params {
+    count_check: TokenStream2,
+    checks: Vec<TokenStream2>,
+    gets: Vec<TokenStream2>,
+    args: Vec<TokenStream2>,
 }
 
-fn extract_vec_inner(ty: &str) -> Option<String> {
-    let ty = ty.trim();
-    if ty.starts_with("Vec <") {
-        Some(ty[5..ty.len() - 1].trim().to_string())
-    } else if ty.starts_with("Vec<") {
-        Some(ty[4..ty.len() - 1].trim().to_string())
-    } else {
-        None
+/// Bind the parameters of `sig`. Trailing parameters with `#[ring(default = expr)]` or an
+/// `Option<T>` type may be left out by the caller, and take the default or `None`. A last
+/// parameter of type `RingArgs` or `&[RingValue]` takes all remaining arguments, or with `list`
+/// the items of a list passed at `first`, ahead of the named parameters.
+fn generate_params(sig: &syn::Signature, first: i32, list: bool) -> syn::Result<Params> {
+    let mut params = Vec::new();
+    let mut variadic = None;
+    for arg in &sig.inputs {
+        if let FnArg::Typed(pat) = arg {
+            let options = ring_options(&pat.attrs, PARAM_OPTIONS)?;
+            let ident = match &*pat.pat {
+                Pat::Ident(ident) if ident.by_ref.is_none() && ident.subpat.is_none() => ident,
+                other => {
+                    return Err(syn::Error::new_spanned(
+                        other,
+                        "exported function parameters must be plain names, such as `x: i32`",
+                    ));
+                }
+            };
+            if let Some((name, _)) = &variadic {
+                return Err(syn::Error::new_spanned(
+                    name,
+                    "a `RingArgs` parameter must be the last one",
+                ));
+            }
+            if is_variadic_type(&pat.ty) {
+                variadic = Some((ident.ident.clone(), (*pat.ty).clone()));
+            } else {
+                params.push((ident.ident.clone(), (*pat.ty).clone(), options.default));
+            }
+        }
     }
-}
 
-fn extract_option_inner(ty: &str) -> Option<String> {
-    let ty = ty.trim();
-    if ty.starts_with("Option <") {
-        Some(ty[8..ty.len() - 1].trim().to_string())
-    } else if ty.starts_with("Option<") {
-        Some(ty[7..ty.len() - 1].trim().to_string())
-    } else {
-        None
+    // Only a run of optional parameters at the end can be left out
+    let mut required = params.len();
+    while required > 0 {
+        let (_, ty, default) = &params[required - 1];
+        if default.is_none() && !is_option_type(ty) {
+            break;
+        }
+        required -= 1;
+    }
+    if let Some((name, _, _)) = params[..required].iter().find(|(_, _, d)| d.is_some()) {
+        return Err(syn::Error::new(
+            name.span(),
+            "a parameter with a default must be followed only by optional parameters",
+        ));
     }
-}
 
-fn extract_result_ok(ty: &str) -> Option<String> {
-    let ty = ty.trim();
-    let inner = if ty.starts_with("Result <") {
-        &ty[8..ty.len() - 1]
-    } else if ty.starts_with("Result<") {
-        &ty[7..ty.len() - 1]
+    let list = list && variadic.is_some();
+    let named = if list { first + 1 } else { first };
+    let min = named - 1 + required as i32;
+    let max = named - 1 + params.len() as i32;
+    let count_check = if variadic.is_some() && !list {
+        if min > 0 {
+            quote! { ring_check_paracount_min!(p, #min); }
+        } else {
+            quote! {}
+        }
+    } else if min == max {
+        quote! { ring_check_paracount!(p, #max); }
     } else {
-        return None;
+        quote! { ring_check_paracount_range!(p, #min, #max); }
     };
 
-    let mut depth = 0;
-    for (i, c) in inner.char_indices() {
-        match c {
-            '<' => depth += 1,
-            '>' => depth -= 1,
-            ',' if depth == 0 => {
-                return Some(inner[..i].trim().to_string());
-            }
-            _ => {}
+    let mut checks = Vec::new();
+    let mut gets = Vec::new();
+    let mut args = Vec::new();
+    for (i, (name, ty, default)) in params.iter().enumerate() {
+        let idx = named + i as i32;
+        let binding = if i < required {
+            generate_param_binding(name, ty, idx)
+        } else {
+            let fallback = match default {
+                Some(expr) => quote! { #expr },
+                None => quote! { None },
+            };
+            generate_optional_binding(name, ty, idx, fallback)
+        };
+        checks.push(binding.check);
+        gets.push(binding.get);
+        args.push(binding.arg);
+    }
+    if let Some((name, ty)) = variadic {
+        if list {
+            checks.push(quote! { ring_check_list!(p, #first); });
+            gets.push(quote! { let #name = RingArgs::from_list_param(p, #first); });
+        } else {
+            let idx = max + 1;
+            gets.push(quote! { let #name = RingArgs::from_params(p, #idx); });
         }
+        args.push(match ty {
+            Type::Reference(_) => quote! { &#name },
+            _ => quote! { #name },
+        });
     }
-    None
+
+    Ok(Params {
+        count_check,
+        checks,
+        gets,
+        args,
+    })
 }
 
-fn extract_tuple_elements(ty: &str) -> Vec<String> {
-    let ty = ty.trim();
-    if !ty.starts_with('(') || !ty.ends_with(')') {
-        return vec![];
+/// Bind parameter `idx` when the caller passed it, and `fallback` otherwise.
+fn generate_optional_binding(
+    name: &syn::Ident,
+    ty: &Type,
+    idx: i32,
+    fallback: TokenStream2,
+) -> ParamBinding {
+    if let Some(r) = option_reference(ty) {
+        return generate_borrowed_option_binding(name, ty, r, idx, Some(fallback));
     }
 
-    let inner = &ty[1..ty.len() - 1];
-    let mut elements = vec![];
-    let mut depth = 0;
-    let mut start = 0;
-
-    for (i, c) in inner.char_indices() {
-        match c {
-            '<' | '(' => depth += 1,
-            '>' | ')' => depth -= 1,
-            ',' if depth == 0 => {
-                elements.push(inner[start..i].trim().to_string());
-                start = i + 1;
-            }
-            _ => {}
-        }
-    }
+    let passed = quote! { ring_api_paracount(p) >= #idx };
 
-    let last = inner[start..].trim();
-    if !last.is_empty() {
-        elements.push(last.to_string());
+    // A borrowed value read through `FromRing` needs a variable outside the `if` to live in
+    if let Type::Reference(r) = ty {
+        let elem = &*r.elem;
+        if !is_str_type(elem) && struct_ident(elem).is_none() {
+            let owned = match elem {
+                Type::Slice(s) => {
+                    let inner = &s.elem;
+                    quote! { Vec<#inner> }
+                }
+                other => quote! { #other },
+            };
+            let storage = format_ident!("__value_{}", name);
+            let (declare, borrow) = if r.mutability.is_some() {
+                (
+                    quote! { let mut #storage: #owned; },
+                    quote! { &mut #storage },
+                )
+            } else {
+                (quote! { let #storage: #owned; }, quote! { &#storage })
+            };
+            return ParamBinding {
+                check: quote! {},
+                get: quote! {
+                    #declare
+                    let #name: #ty = if #passed {
+                        #storage = ring_get_value!(p, #idx);
+                        #borrow
+                    } else {
+                        #fallback
+                    };
+                },
+                arg: quote! { #name },
+            };
+        }
     }
 
-    elements
-}
-
-fn extract_box_inner(ty: &str) -> Option<String> {
-    let ty = ty.trim();
-    if ty.starts_with("Box <") {
-        Some(ty[5..ty.len() - 1].trim().to_string())
-    } else if ty.starts_with("Box<") {
-        Some(ty[4..ty.len() - 1].trim().to_string())
-    } else {
-        None
+    let ParamBinding { check, get, arg } = generate_param_binding(name, ty, idx);
+    ParamBinding {
+        check: quote! {},
+        get: quote! {
+            let #name: #ty = if #passed {
+                #check
+                #get
+                #arg
+            } else {
+                #fallback
+            };
+        },
+        arg: quote! { #name },
     }
 }
 
-fn extract_hashmap_kv(ty: &str) -> Option<(String, String)> {
-    let ty = ty.trim();
-
-    let hashmap_start = ty.find("HashMap")?;
-    let rest = &ty[hashmap_start + 7..];
-
-    let angle_start = rest.find('<')?;
-    let angle_end = rest.rfind('>')?;
-    let inner = &rest[angle_start + 1..angle_end];
-
-    let mut depth = 0;
-    for (i, c) in inner.char_indices() {
-        match c {
-            '<' => depth += 1,
-            '>' => depth -= 1,
-            ',' if depth == 0 => {
-                let key = inner[..i].trim().to_string();
-                let value = inner[i + 1..].trim().to_string();
-                return Some((key, value));
+/// Bind an `Option<&T>` parameter, which is `None` for Ring's `NULL`. Strings and structs are
+/// borrowed in place; any other `T` is read through `FromRing` into a variable outside the
+/// binding for the reference to point to. Without a `fallback` the parameter is required.
+fn generate_borrowed_option_binding(
+    name: &syn::Ident,
+    ty: &Type,
+    r: &syn::TypeReference,
+    idx: i32,
+    fallback: Option<TokenStream2>,
+) -> ParamBinding {
+    let elem = &*r.elem;
+    let (declare, read) = if is_str_type(elem) || struct_ident(elem).is_some() {
+        let ParamBinding { check, get, arg } =
+            generate_param_binding(name, &Type::Reference(r.clone()), idx);
+        let read = quote! {
+            if ring_api_isstring(p, #idx) && ring_api_getstringsize(p, #idx) == 0 {
+                None
+            } else {
+                #check
+                #get
+                Some(#arg)
             }
-            _ => {}
-        }
-    }
-    None
-}
-
-fn extract_slice_inner(ty: &str) -> Option<String> {
-    let ty = ty.trim();
-    if ty.starts_with("& [") {
-        Some(ty[3..ty.len() - 1].trim().to_string())
-    } else if ty.starts_with("&[") {
-        Some(ty[2..ty.len() - 1].trim().to_string())
+        };
+        (quote! {}, read)
     } else {
-        None
+        let storage = format_ident!("__value_{}", name);
+        let (owned, borrow) = match (elem, r.mutability.is_some()) {
+            (Type::Slice(s), true) => (&*s.elem, quote! { as_deref_mut }),
+            (Type::Slice(s), false) => (&*s.elem, quote! { as_deref }),
+            (other, true) => (other, quote! { as_mut }),
+            (other, false) => (other, quote! { as_ref }),
+        };
+        let owned = match elem {
+            Type::Slice(_) => quote! { Vec<#owned> },
+            _ => quote! { #owned },
+        };
+        let declare = if r.mutability.is_some() {
+            quote! { let mut #storage: Option<#owned>; }
+        } else {
+            quote! { let #storage: Option<#owned>; }
+        };
+        let read = quote! {
+            #storage = ring_get_value!(p, #idx);
+            #storage.#borrow()
+        };
+        (declare, read)
+    };
+    let get = match fallback {
+        Some(fallback) => quote! {
+            #declare
+            let #name: #ty = if ring_api_paracount(p) >= #idx {
+                #read
+            } else {
+                #fallback
+            };
+        },
+        None => quote! {
+            #declare
+            let #name: #ty = { #read };
+        },
+    };
+    ParamBinding {
+        check: quote! {},
+        get,
+        arg: quote! { #name },
     }
 }
 
-fn extract_struct_name(ty: &str) -> String {
-    ty.trim()
-        .trim_start_matches('&')
-        .trim()
-        .trim_start_matches("mut ")
-        .trim()
-        .to_string()
-}
-
-fn struct_type_const(struct_name: &str) -> syn::Ident {
-    format_ident!("{}_TYPE", struct_name.to_uppercase())
-}
-
 struct ParamBinding {
     check: TokenStream2,
     get: TokenStream2,
     arg: TokenStream2,
 }
 
-fn is_mut_struct_ref(ty: &str) -> bool {
-    let ty = ty.trim();
-    ty.starts_with("& mut ") && is_struct_type(&ty[6..])
-}
-
+/// Bind parameter `idx`. Borrowed strings and borrowed structs are read in place; every other
+/// type is converted through `FromRing`.
 fn generate_param_binding(name: &syn::Ident, ty: &Type, idx: i32) -> ParamBinding {
-    let type_str = quote!(#ty).to_string();
-
-    if is_number_type(&type_str) {
-        let cast = get_number_cast(&type_str);
-        ParamBinding {
-            check: quote! { ring_check_number!(p, #idx); },
-            get: quote! { let #name = ring_get_number!(p, #idx) as #cast; },
+    if let Some(r) = option_reference(ty) {
+        return generate_borrowed_option_binding(name, ty, r, idx, None);
+    }
+    let Type::Reference(r) = ty else {
+        return ParamBinding {
+            check: quote! {},
+            get: quote! { let #name: #ty = ring_get_value!(p, #idx); },
             arg: quote! { #name },
-        }
-    } else if is_string_type(&type_str) {
+        };
+    };
+
+    let elem = &*r.elem;
+    let is_mut = r.mutability.is_some();
+
+    if is_str_type(elem) {
         ParamBinding {
             check: quote! { ring_check_string!(p, #idx); },
             get: quote! { let #name = ring_get_string!(p, #idx); },
             arg: quote! { #name },
         }
-    } else if type_str == "bool" {
-        ParamBinding {
-            check: quote! { ring_check_number!(p, #idx); },
-            get: quote! { let #name = ring_get_number!(p, #idx) != 0.0; },
-            arg: quote! { #name },
-        }
-    } else if is_mut_struct_ref(&type_str) {
-        let struct_name_str = extract_struct_name(&type_str);
-        let struct_ident = format_ident!("{}", struct_name_str);
-        let type_const = struct_type_const(&struct_name_str);
-        let ptr_name = format_ident!("__ptr_{}", name);
-        ParamBinding {
-            check: quote! { ring_check_cpointer!(p, #idx); },
-            get: quote! { let #ptr_name = ring_get_cpointer!(p, #idx, #type_const); },
-            arg: quote! { unsafe { &mut *(#ptr_name as *mut #struct_ident) } },
-        }
-    } else if is_struct_ref(&type_str) {
-        let struct_name_str = extract_struct_name(&type_str);
-        let struct_ident = format_ident!("{}", struct_name_str);
-        let type_const = struct_type_const(&struct_name_str);
+    } else if let Some(struct_ident) = struct_ident(elem) {
+        let type_const = struct_type_const(struct_ident);
         let ptr_name = format_ident!("__ptr_{}", name);
+        let arg = if is_mut {
+            quote! { unsafe { &mut *(#ptr_name as *mut #struct_ident) } }
+        } else {
+            quote! { unsafe { &*(#ptr_name as *const #struct_ident) } }
+        };
         ParamBinding {
             check: quote! { ring_check_cpointer!(p, #idx); },
             get: quote! { let #ptr_name = ring_get_cpointer!(p, #idx, #type_const); },
-            arg: quote! { unsafe { &*(#ptr_name as *const #struct_ident) } },
-        }
-    } else if is_vec_type(&type_str) {
-        generate_vec_param_binding(name, &type_str, idx)
-    } else if is_option_type(&type_str) {
-        generate_option_param_binding(name, &type_str, idx)
-    } else if is_slice_type(&type_str) {
-        generate_slice_param_binding(name, &type_str, idx)
-    } else if is_struct_type(&type_str) {
-        let struct_name_str = extract_struct_name(&type_str);
-        let struct_ident = format_ident!("{}", struct_name_str);
-        let type_const = struct_type_const(&struct_name_str);
-        let ptr_name = format_ident!("__ptr_{}", name);
-        ParamBinding {
-            check: quote! { ring_check_cpointer!(p, #idx); },
-            get: quote! {
-                let #ptr_name = ring_get_cpointer!(p, #idx, #type_const);
-                let #name = unsafe { (*( #ptr_name as *const #struct_ident)).clone() };
-            },
-            arg: quote! { #name },
+            arg,
         }
     } else {
+        let owned = match elem {
+            Type::Slice(s) => {
+                let inner = &s.elem;
+                quote! { Vec<#inner> }
+            }
+            other => quote! { #other },
+        };
+        let (binding, arg) = if is_mut {
+            (quote! { mut #name }, quote! { &mut #name })
+        } else {
+            (quote! { #name }, quote! { &#name })
+        };
         ParamBinding {
-            check: quote! { ring_check_number!(p, #idx); },
-            get: quote! { let #name = ring_get_number!(p, #idx) as _; },
-            arg: quote! { #name },
+            check: quote! {},
+            get: quote! { let #binding: #owned = ring_get_value!(p, #idx); },
+            arg,
         }
     }
 }
 
-fn generate_vec_param_binding(name: &syn::Ident, type_str: &str, idx: i32) -> ParamBinding {
-    let inner = extract_vec_inner(type_str).unwrap_or_default();
+/// Derive `IntoRing` for a struct or an enum.
+///
+/// Named fields become a list of `[name, value]` pairs, which Ring code can index with
+/// `list[:name]`. Tuple structs become a plain list of their fields.
+///
+/// Enums whose variants have no fields become their discriminant as a number. Other enums
+/// become a tagged list `[variant, payload...]`, with the variant name in snake case and the
+/// fields in declaration order.
+///
+/// ```rust,ignore
+/// #[derive(IntoRing, FromRing)]
+/// pub struct Point {
+///     pub x: f64,
+///     pub y: f64,
+/// }
+/// ```
+#[proc_macro_derive(IntoRing)]
+pub fn derive_into_ring(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let name = &input.ident;
+
+    let data = match &input.data {
+        Data::Struct(data) => data,
+        Data::Enum(data) => return enum_into_ring(name, &input.generics, data).into(),
+        Data::Union(_) => {
+            return syn::Error::new(name.span(), "IntoRing cannot be derived for unions")
+                .to_compile_error()
+                .into();
+        }
+    };
 
-    if is_number_type(&inner) {
-        let cast = get_number_cast(&inner);
-        ParamBinding {
-            check: quote! { ring_check_list!(p, #idx); },
-            get: quote! {
-                let __list = ring_get_list!(p, #idx);
-                let __size = ring_list_getsize(__list);
-                let mut #name = Vec::with_capacity(__size as usize);
-                for __i in 1..=__size {
-                    if ring_list_isnumber(__list, __i) {
-                        #name.push(ring_list_getdouble(__list, __i) as #cast);
-                    }
+    let values: Vec<_> = match &data.fields {
+        Fields::Named(fields) => fields
+            .named
+            .iter()
+            .map(|f| {
+                let ident = f.ident.as_ref().unwrap();
+                let key = ident.unraw().to_string();
+                quote! {
+                    ::ring_lang_rs::RingValue::List(vec![::ring_lang_rs::RingValue::from(#key), ::ring_lang_rs::IntoRing::into_ring(self.#ident)])
                 }
-            },
-            arg: quote! { #name },
+            })
+            .collect(),
+        Fields::Unnamed(fields) => (0..fields.unnamed.len())
+            .map(|i| {
+                let index = syn::Index::from(i);
+                quote! { ::ring_lang_rs::IntoRing::into_ring(self.#index) }
+            })
+            .collect(),
+        Fields::Unit => Vec::new(),
+    };
+
+    let generics = add_trait_bounds(input.generics.clone(), quote!(::ring_lang_rs::IntoRing));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    quote! {
+        impl #impl_generics ::ring_lang_rs::IntoRing for #name #ty_generics #where_clause {
+            fn into_ring(self) -> ::ring_lang_rs::RingValue {
+                ::ring_lang_rs::RingValue::List(vec![#(#values),*])
+            }
         }
-    } else if is_string_type(&inner) {
-        ParamBinding {
-            check: quote! { ring_check_list!(p, #idx); },
-            get: quote! {
-                let __list = ring_get_list!(p, #idx);
-                let __size = ring_list_getsize(__list);
-                let mut #name = Vec::with_capacity(__size as usize);
-                for __i in 1..=__size {
-                    if ring_list_isstring(__list, __i) {
-                        let __cstr = ring_list_getstring(__list, __i);
-                        let __s = unsafe { std::ffi::CStr::from_ptr(__cstr).to_string_lossy().into_owned() };
-                        #name.push(__s);
-                    }
-                }
-            },
-            arg: quote! { #name },
+    }
+    .into()
+}
+
+/// Derive `FromRing` for a struct or an enum.
+///
+/// Named fields are read from a list of `[name, value]` pairs or from a Ring object's attributes,
+/// ignoring case. Tuple structs are read from a plain list of the same length.
+///
+/// Enums are read from what `IntoRing` produces. Variant names are matched ignoring case, either
+/// as written in Rust or in snake case, and a variant without fields can also be given as a
+/// plain string.
+#[proc_macro_derive(FromRing)]
+pub fn derive_from_ring(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let name = &input.ident;
+
+    let data = match &input.data {
+        Data::Struct(data) => data,
+        Data::Enum(data) => return enum_from_ring(name, &input.generics, data).into(),
+        Data::Union(_) => {
+            return syn::Error::new(name.span(), "FromRing cannot be derived for unions")
+                .to_compile_error()
+                .into();
         }
-    } else if is_struct_type(&inner) {
-        let inner_struct_name = extract_struct_name(&inner);
-        let inner_struct_ident = format_ident!("{}", inner_struct_name);
-        ParamBinding {
-            check: quote! { ring_check_list!(p, #idx); },
-            get: quote! {
-                let __list = ring_get_list!(p, #idx);
-                let __size = ring_list_getsize(__list);
-                let mut #name = Vec::with_capacity(__size as usize);
-                for __i in 1..=__size {
-                    let __ptr = if ring_list_ispointer(__list, __i) {
-                        ring_list_getpointer(__list, __i)
-                    } else if ring_list_islist(__list, __i) {
-                        let __inner_list = ring_list_getlist(__list, __i);
-                        if ring_list_ispointer(__inner_list, 1) {
-                            ring_list_getpointer(__inner_list, 1)
-                        } else {
-                            std::ptr::null_mut()
-                        }
-                    } else {
-                        std::ptr::null_mut()
-                    };
-                    if !__ptr.is_null() {
-                        let __item = unsafe { &*(__ptr as *const #inner_struct_ident) };
-                        #name.push(__item.clone());
-                    }
-                }
-            },
-            arg: quote! { #name },
+    };
+
+    let body = match &data.fields {
+        Fields::Named(fields) => {
+            let inits = fields.named.iter().map(|f| {
+                let ident = f.ident.as_ref().unwrap();
+                let key = ident.unraw().to_string();
+                quote! { #ident: ::ring_lang_rs::FromRing::from_ring(::ring_lang_rs::ring_take_field(&mut __fields, #key))? }
+            });
+            quote! {
+                let mut __fields = ::ring_lang_rs::ring_named_fields::<Self>(value)?;
+                Ok(Self { #(#inits),* })
+            }
         }
-    } else {
-        let cast = get_number_cast(&inner);
-        ParamBinding {
-            check: quote! { ring_check_list!(p, #idx); },
-            get: quote! {
-                let __list = ring_get_list!(p, #idx);
-                let __size = ring_list_getsize(__list);
-                let mut #name = Vec::with_capacity(__size as usize);
-                for __i in 1..=__size {
-                    if ring_list_isnumber(__list, __i) {
-                        #name.push(ring_list_getdouble(__list, __i) as #cast);
-                    }
-                }
-            },
-            arg: quote! { #name },
+        Fields::Unnamed(fields) => {
+            let len = fields.unnamed.len();
+            let inits = (0..len).map(|_| {
+                quote! { ::ring_lang_rs::FromRing::from_ring(__items.next().unwrap_or_default())? }
+            });
+            quote! {
+                let mut __items = match value {
+                    ::ring_lang_rs::RingValue::List(items) if items.len() == #len => items.into_iter(),
+                    other => return Err(::ring_lang_rs::ring_type_mismatch::<Self>(&other)),
+                };
+                Ok(Self(#(#inits),*))
+            }
+        }
+        Fields::Unit => quote! {
+            let _ = value;
+            Ok(Self)
+        },
+    };
+
+    let generics = add_trait_bounds(input.generics.clone(), quote!(::ring_lang_rs::FromRing));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    quote! {
+        impl #impl_generics ::ring_lang_rs::FromRing for #name #ty_generics #where_clause {
+            fn from_ring(value: ::ring_lang_rs::RingValue) -> ::core::result::Result<Self, ::ring_lang_rs::RingError> {
+                #body
+            }
         }
     }
+    .into()
 }
 
-fn generate_option_param_binding(name: &syn::Ident, type_str: &str, idx: i32) -> ParamBinding {
-    let inner = extract_option_inner(type_str).unwrap_or_default();
+/// Whether no variant has fields, so the enum converts to a number.
+fn is_c_like(data: &syn::DataEnum) -> bool {
+    data.variants
+        .iter()
+        .all(|v| matches!(v.fields, Fields::Unit))
+}
 
-    if is_number_type(&inner) {
-        let cast = get_number_cast(&inner);
-        ParamBinding {
-            check: quote! {},
-            get: quote! {
-                let #name = if ring_api_isstring(p, #idx) {
-                    let __s = ring_get_string!(p, #idx);
-                    if __s.is_empty() { None } else { None }
-                } else if ring_api_isnumber(p, #idx) {
-                    Some(ring_get_number!(p, #idx) as #cast)
-                } else {
-                    None
-                };
-            },
-            arg: quote! { #name },
-        }
-    } else if is_string_type(&inner) {
-        ParamBinding {
-            check: quote! {},
-            get: quote! {
-                let #name = if ring_api_isstring(p, #idx) {
-                    let __s = ring_get_string!(p, #idx);
-                    if __s.is_empty() { None } else { Some(__s.to_string()) }
-                } else {
-                    None
-                };
-            },
-            arg: quote! { #name },
+/// The name Ring code uses for a variant: `DarkRed` becomes `dark_red`.
+fn variant_tag(variant: &Ident) -> String {
+    let name = variant.unraw().to_string();
+    let chars: Vec<char> = name.chars().collect();
+    let mut tag = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            let prev = chars[i - 1];
+            let next_lower = chars.get(i + 1).is_some_and(|n| n.is_lowercase());
+            if prev.is_lowercase() || prev.is_ascii_digit() || (prev.is_uppercase() && next_lower) {
+                tag.push('_');
+            }
         }
-    } else if is_struct_type(&inner) {
-        let inner_struct_name = extract_struct_name(&inner);
-        let inner_struct_ident = format_ident!("{}", inner_struct_name);
-        let inner_type_const = struct_type_const(&inner_struct_name);
-        ParamBinding {
-            check: quote! {},
-            get: quote! {
-                let #name = if ring_api_isstring(p, #idx) {
-                    None
-                } else if ring_api_ispointer(p, #idx) {
-                    let __ptr = ring_get_cpointer!(p, #idx, #inner_type_const);
-                    if __ptr.is_null() {
-                        None
-                    } else {
-                        Some(unsafe { (*(__ptr as *const #inner_struct_ident)).clone() })
-                    }
-                } else {
-                    None
-                };
-            },
-            arg: quote! { #name },
+        tag.extend(c.to_lowercase());
+    }
+    tag
+}
+
+/// Bindings `__0, __1, ...` for a variant's fields, and the pattern that binds them.
+fn variant_bindings(variant: &syn::Variant) -> (Vec<Ident>, TokenStream2) {
+    let ident = &variant.ident;
+    let names: Vec<Ident> = (0..variant.fields.len())
+        .map(|i| format_ident!("__{}", i))
+        .collect();
+    let pattern = match &variant.fields {
+        Fields::Named(fields) => {
+            let fields = fields.named.iter().map(|f| f.ident.as_ref().unwrap());
+            quote! { Self::#ident { #(#fields: #names),* } }
         }
+        Fields::Unnamed(_) => quote! { Self::#ident(#(#names),*) },
+        Fields::Unit => quote! { Self::#ident },
+    };
+    (names, pattern)
+}
+
+fn enum_into_ring(name: &Ident, generics: &syn::Generics, data: &syn::DataEnum) -> TokenStream2 {
+    let generics = add_trait_bounds(generics.clone(), quote!(::ring_lang_rs::IntoRing));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let body = if is_c_like(data) {
+        quote! { ::ring_lang_rs::IntoRing::into_ring(self as i64) }
     } else {
-        let cast = get_number_cast(&inner);
-        ParamBinding {
-            check: quote! {},
-            get: quote! {
-                let #name = if ring_api_isstring(p, #idx) {
-                    None
-                } else if ring_api_isnumber(p, #idx) {
-                    Some(ring_get_number!(p, #idx) as #cast)
-                } else {
-                    None
-                };
-            },
-            arg: quote! { #name },
+        let arms = data.variants.iter().map(|variant| {
+            let tag = variant_tag(&variant.ident);
+            let (names, pattern) = variant_bindings(variant);
+            quote! {
+                #pattern => ::ring_lang_rs::RingValue::List(vec![
+                    ::ring_lang_rs::RingValue::from(#tag),
+                    #(::ring_lang_rs::IntoRing::into_ring(#names)),*
+                ])
+            }
+        });
+        quote! {
+            match self {
+                #(#arms),*
+            }
+        }
+    };
+
+    quote! {
+        impl #impl_generics ::ring_lang_rs::IntoRing for #name #ty_generics #where_clause {
+            fn into_ring(self) -> ::ring_lang_rs::RingValue {
+                #body
+            }
         }
     }
 }
 
-fn generate_slice_param_binding(name: &syn::Ident, type_str: &str, idx: i32) -> ParamBinding {
-    let inner = extract_slice_inner(type_str).unwrap_or_default();
-    let vec_name = format_ident!("__{}_vec", name);
+fn enum_from_ring(name: &Ident, generics: &syn::Generics, data: &syn::DataEnum) -> TokenStream2 {
+    let generics = add_trait_bounds(generics.clone(), quote!(::ring_lang_rs::FromRing));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
-    if is_number_type(&inner) {
-        let cast = get_number_cast(&inner);
-        ParamBinding {
-            check: quote! { ring_check_list!(p, #idx); },
-            get: quote! {
-                let __list = ring_get_list!(p, #idx);
-                let __size = ring_list_getsize(__list);
-                let mut #vec_name: Vec<#cast> = Vec::with_capacity(__size as usize);
-                for __i in 1..=__size {
-                    if ring_list_isnumber(__list, __i) {
-                        #vec_name.push(ring_list_getdouble(__list, __i) as #cast);
-                    }
+    let matches_name = |variant: &syn::Variant, value: TokenStream2| {
+        let tag = variant_tag(&variant.ident);
+        let rust_name = variant.ident.unraw().to_string();
+        quote! { (#value.eq_ignore_ascii_case(#tag) || #value.eq_ignore_ascii_case(#rust_name)) }
+    };
+
+    let body = if is_c_like(data) {
+        let by_name = data.variants.iter().map(|variant| {
+            let ident = &variant.ident;
+            let matches = matches_name(variant, quote!(__name));
+            quote! {
+                if #matches {
+                    return Ok(Self::#ident);
                 }
-            },
-            arg: quote! { &#vec_name[..] },
-        }
-    } else if is_string_type(&inner) {
-        ParamBinding {
-            check: quote! { ring_check_list!(p, #idx); },
-            get: quote! {
-                let __list = ring_get_list!(p, #idx);
-                let __size = ring_list_getsize(__list);
-                let mut #vec_name: Vec<String> = Vec::with_capacity(__size as usize);
-                for __i in 1..=__size {
-                    if ring_list_isstring(__list, __i) {
-                        let __cstr = ring_list_getstring(__list, __i);
-                        let __s = unsafe { std::ffi::CStr::from_ptr(__cstr).to_string_lossy().into_owned() };
-                        #vec_name.push(__s);
-                    }
+            }
+        });
+        let by_number = data.variants.iter().map(|variant| {
+            let ident = &variant.ident;
+            quote! {
+                if __number == Self::#ident as i64 {
+                    return Ok(Self::#ident);
                 }
-            },
-            arg: quote! { &#vec_name[..] },
-        }
-    } else if is_struct_type(&inner) {
-        let inner_struct_name = extract_struct_name(&inner);
-        let inner_struct_ident = format_ident!("{}", inner_struct_name);
-        ParamBinding {
-            check: quote! { ring_check_list!(p, #idx); },
-            get: quote! {
-                let __list = ring_get_list!(p, #idx);
-                let __size = ring_list_getsize(__list);
-                let mut #vec_name: Vec<#inner_struct_ident> = Vec::with_capacity(__size as usize);
-                for __i in 1..=__size {
-                    let __ptr = if ring_list_ispointer(__list, __i) {
-                        ring_list_getpointer(__list, __i)
-                    } else {
-                        std::ptr::null_mut()
-                    };
-                    if !__ptr.is_null() {
-                        let __item = unsafe { &*(__ptr as *const #inner_struct_ident) };
-                        #vec_name.push(__item.clone());
-                    }
+            }
+        });
+        quote! {
+            match value {
+                ::ring_lang_rs::RingValue::String(__name) => {
+                    let __name = String::from_utf8_lossy(&__name);
+                    #(#by_name)*
+                    Err(::ring_lang_rs::ring_unknown_variant::<Self>(__name))
                 }
-            },
-            arg: quote! { &#vec_name[..] },
+                other => {
+                    let __number: i64 = ::ring_lang_rs::FromRing::from_ring(other)?;
+                    #(#by_number)*
+                    Err(::ring_lang_rs::ring_unknown_variant::<Self>(__number))
+                }
+            }
         }
     } else {
-        ParamBinding {
-            check: quote! { ring_check_list!(p, #idx); },
-            get: quote! {
-                let __list = ring_get_list!(p, #idx);
-                let __size = ring_list_getsize(__list);
-                let mut #vec_name: Vec<f64> = Vec::with_capacity(__size as usize);
-                for __i in 1..=__size {
-                    if ring_list_isnumber(__list, __i) {
-                        #vec_name.push(ring_list_getdouble(__list, __i));
+        let arms = data.variants.iter().map(|variant| {
+            let ident = &variant.ident;
+            let matches = matches_name(variant, quote!(__tag));
+            let count = variant.fields.len();
+            let reads = (0..count).map(|_| {
+                quote! { ::ring_lang_rs::FromRing::from_ring(__items.next().unwrap_or_default())? }
+            });
+            let construct = match &variant.fields {
+                Fields::Named(fields) => {
+                    let fields = fields.named.iter().map(|f| f.ident.as_ref().unwrap());
+                    quote! { Self::#ident { #(#fields: #reads),* } }
+                }
+                Fields::Unnamed(_) => quote! { Self::#ident(#(#reads),*) },
+                Fields::Unit => quote! { Self::#ident },
+            };
+            quote! {
+                if #matches {
+                    if __items.len() != #count {
+                        return Err(::ring_lang_rs::RingError::Conversion(format!(
+                            "wrong number of values for variant {} of {}: expected {}, found {}",
+                            __tag,
+                            ::std::any::type_name::<Self>(),
+                            #count,
+                            __items.len()
+                        )));
                     }
+                    return Ok(#construct);
                 }
-            },
-            arg: quote! { &#vec_name[..] },
+            }
+        });
+        quote! {
+            let (__tag, __payload) = ::ring_lang_rs::ring_enum_variant::<Self>(value)?;
+            let mut __items = __payload.into_iter();
+            #(#arms)*
+            Err(::ring_lang_rs::ring_unknown_variant::<Self>(__tag))
+        }
+    };
+
+    quote! {
+        impl #impl_generics ::ring_lang_rs::FromRing for #name #ty_generics #where_clause {
+            fn from_ring(value: ::ring_lang_rs::RingValue) -> ::core::result::Result<Self, ::ring_lang_rs::RingError> {
+                #body
+            }
+        }
+    }
+}
+
+fn add_trait_bounds(mut generics: syn::Generics, bound: TokenStream2) -> syn::Generics {
+    for param in &mut generics.params {
+        if let syn::GenericParam::Type(ty) = param {
+            ty.bounds.push(syn::parse_quote!(#bound));
+        }
+    }
+    generics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Verify parameters other than plain names are rejected instead of dropped
+    #[test]
+    fn test_params_reject_patterns() {
+        let sig: syn::Signature = syn::parse_quote!(fn f(a: i32, mut b: i32));
+        assert_eq!(generate_params(&sig, 1, false).unwrap().args.len(), 2);
+
+        for sig in [
+            syn::parse_quote!(fn f(_: i32)),
+            syn::parse_quote!(fn f((a, b): (i32, i32))),
+            syn::parse_quote!(fn f(ref a: i32)),
+        ] {
+            let sig: syn::Signature = sig;
+            assert!(generate_params(&sig, 1, false).is_err());
         }
     }
 }
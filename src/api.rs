@@ -1,9 +1,14 @@
 use crate::ffi_types::{CStr, CString, c_char, c_double, c_int, c_uint, c_void};
 
+use std::collections::HashSet;
+use std::str::Utf8Error;
+use std::string::FromUtf8Error;
+use std::sync::Mutex;
+
 use crate::ffi::{self, RingString};
 use crate::{
     RING_CPOINTER_STATUS, RING_CPOINTERSTATUS_NOTASSIGNED, RING_OUTPUT_RETLISTBYREF,
-    RING_OUTPUT_RETNEWREF, RingFunc, RingList, RingState,
+    RING_OUTPUT_RETNEWREF, RegisterError, RingFunc, RingList, RingState,
 };
 
 #[inline]
@@ -61,23 +66,33 @@ pub fn ring_api_getstring(p: *mut c_void, n: c_int) -> *const c_char {
     unsafe { ffi::ring_vm_api_getstring(p, n) }
 }
 
+/// Borrow string parameter `n` as `&str`, or fail if it is not valid UTF-8.
+///
+/// Prefer [`RingCall::arg`](crate::RingCall::arg) with `&str`, which ties the string to the
+/// callback safely, or [`ring_api_getstring_string`] for an owned copy.
+///
+/// # Safety
+///
+/// The string is owned by the VM and freed after the callback returns. `p` must be the
+/// callback's own argument, and the result must not be used after the callback returns; the
+/// borrow of `p` alone does not guarantee this, since `p` could be a longer-lived copy.
 #[inline]
-pub fn ring_api_getstring_str(p: *mut c_void, n: c_int) -> &'static str {
-    unsafe {
-        let ptr = ffi::ring_vm_api_getstring(p, n);
-        if ptr.is_null() {
-            ""
-        } else {
-            CStr::from_ptr(ptr).to_str().unwrap_or("")
-        }
-    }
+pub unsafe fn ring_api_getstring_str(p: &*mut c_void, n: c_int) -> Result<&str, Utf8Error> {
+    std::str::from_utf8(unsafe { ring_api_getstring_bytes(p, n) })
 }
 
+/// Borrow string parameter `n` as bytes, including any embedded NULs or non-UTF-8 data.
+///
+/// [`ring_api_getstring_vec`] returns a copy.
+///
+/// # Safety
+///
+/// As for [`ring_api_getstring_str`]: the result must not be used after the callback returns.
 #[inline]
-pub fn ring_api_getstring_bytes(p: *mut c_void, n: c_int) -> &'static [u8] {
+pub unsafe fn ring_api_getstring_bytes(p: &*mut c_void, n: c_int) -> &[u8] {
     unsafe {
-        let ptr = ffi::ring_vm_api_getstring(p, n);
-        let size = ffi::ring_vm_api_getstringsize(p, n) as usize;
+        let ptr = ffi::ring_vm_api_getstring(*p, n);
+        let size = ffi::ring_vm_api_getstringsize(*p, n) as usize;
         if ptr.is_null() || size == 0 {
             &[]
         } else {
@@ -86,6 +101,18 @@ pub fn ring_api_getstring_bytes(p: *mut c_void, n: c_int) -> &'static [u8] {
     }
 }
 
+/// Copy string parameter `n` into a `String`, or fail if it is not valid UTF-8.
+#[inline]
+pub fn ring_api_getstring_string(p: *mut c_void, n: c_int) -> Result<String, FromUtf8Error> {
+    String::from_utf8(ring_api_getstring_vec(p, n))
+}
+
+/// Copy string parameter `n` into a `Vec<u8>`.
+#[inline]
+pub fn ring_api_getstring_vec(p: *mut c_void, n: c_int) -> Vec<u8> {
+    unsafe { ring_api_getstring_bytes(&p, n).to_vec() }
+}
+
 #[inline]
 pub fn ring_api_getstringsize(p: *mut c_void, n: c_int) -> c_uint {
     unsafe { ffi::ring_vm_api_getstringsize(p, n) }
@@ -346,24 +373,132 @@ pub fn ring_api_cpointercmp(p: *mut c_void, list1: RingList, list2: RingList) ->
 
 #[inline]
 pub fn ring_api_error(p: *mut c_void, s: &[u8]) {
+    crate::state::ring_rs_raising(p, s);
     unsafe { ffi::ring_vm_error(p, s.as_ptr() as *const c_char) }
 }
 
 #[inline]
 pub fn ring_api_error_str(p: *mut c_void, s: &str) {
     if let Ok(cstr) = CString::new(s) {
+        crate::state::ring_rs_raising(p, s.as_bytes());
         unsafe { ffi::ring_vm_error(p, cstr.as_ptr()) }
     }
 }
 
+/// Run an extension function body, turning a Rust panic into a Ring runtime error.
+///
+/// Unwinding into the VM's C frames is undefined behavior, so [`ring_func!`](crate::ring_func)
+/// calls every body through this. The panic message is reported with [`ring_api_error`], which
+/// Ring code can handle with `try/catch`. Builds with `panic = "abort"` abort before this runs.
+pub fn ring_api_catch_panic<F: FnOnce(*mut c_void)>(p: *mut c_void, body: F) {
+    let Err(payload) = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| body(p))) else {
+        return;
+    };
+    let message = if let Some(s) = payload.downcast_ref::<&str>() {
+        s
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.as_str()
+    } else {
+        "unknown panic payload"
+    };
+    let message = format!("Rust panic: {}", message).replace('\0', "");
+    ring_api_error_str(p, &message);
+}
+
+/// Register `func` under a NUL-terminated name, without any checks.
+///
+/// Ring keeps the name pointer, so `name` must live as long as the state, like a `b"name\0"`
+/// literal. Prefer [`ring_register_function_str`].
 pub fn ring_register_function(state: RingState, name: &[u8], func: RingFunc) {
     unsafe {
         ffi::ring_vm_funcregister2(state, name.as_ptr() as *const c_char, func);
     }
 }
 
-pub fn ring_register_function_str(state: RingState, name: &str, func: RingFunc) {
+/// Function names registered so far. Ring keeps the name pointer for as long as the state
+/// lives, so each distinct name is copied once and kept until the process exits, shared by every
+/// state that registers it.
+static FUNCTION_NAMES: Mutex<Option<HashSet<&'static CStr>>> = Mutex::new(None);
+
+/// Register `func` as the Ring function `name`.
+///
+/// The name must be valid (see [`ring_is_valid_function_name`]) and not already in the state's
+/// function table, whoever registered it. A state without a VM yet can't be checked for
+/// duplicates.
+///
+/// Ring keeps a pointer to the name, so the first registration of each distinct name leaks a
+/// copy of it for the rest of the process. Registering the same names in many states costs
+/// nothing more; generating new names without bound grows memory without bound.
+pub fn ring_register_function_str(
+    state: RingState,
+    name: &str,
+    func: RingFunc,
+) -> Result<(), RegisterError> {
+    if !ring_is_valid_function_name(name) {
+        return Err(RegisterError::InvalidName(name.to_string()));
+    }
+    if let Some(vm) = crate::state::state_vm(state) {
+        if unsafe { crate::builder::find_cfunction(vm, name.as_bytes()) }.is_some() {
+            return Err(RegisterError::Duplicate(name.to_string()));
+        }
+    }
+    let mut names = FUNCTION_NAMES.lock().unwrap_or_else(|e| e.into_inner());
+    let names = names.get_or_insert_with(HashSet::new);
+    let cname = match names.iter().find(|n| n.to_bytes() == name.as_bytes()) {
+        Some(&cname) => cname,
+        None => {
+            // Valid names have no NUL bytes
+            let cname: &'static CStr = Box::leak(CString::new(name).unwrap().into_boxed_c_str());
+            names.insert(cname);
+            cname
+        }
+    };
     unsafe {
-        ffi::ring_vm_funcregister2(state, name.as_ptr() as *const c_char, func);
+        ffi::ring_vm_funcregister2(state, cname.as_ptr(), func);
+    }
+    Ok(())
+}
+
+/// Register a function for [`ring_libinit!`](crate::ring_libinit).
+///
+/// Loading the library again into the same state finds `func` already registered, which is
+/// fine. Any other failure leaves the state's existing function in place and is raised as a Ring
+/// error, so the `loadlib()` call that loaded the library fails.
+#[doc(hidden)]
+pub fn ring_libinit_register(state: RingState, name: &str, func: RingFunc) {
+    let vm = crate::state::state_vm(state);
+    let loaded = vm.is_some_and(|vm| unsafe {
+        crate::builder::find_cfunction(vm, name.as_bytes())
+            .is_some_and(|f| (*f).pFunc.is_some_and(|g| std::ptr::fn_addr_eq(g, func)))
+    });
+    if loaded {
+        return;
+    }
+    // Names are checked at compile time and duplicates only with a VM, so an error always has a
+    // VM to be raised on
+    if let (Err(e), Some(vm)) = (ring_register_function_str(state, name, func), vm) {
+        ring_api_error_str(vm as *mut c_void, &e.to_string());
+    }
+}
+
+/// Whether `name` can be registered as a Ring function: not empty, not starting with a digit,
+/// and made of lowercase letters, digits and `_`. Ring looks functions up by their lowercase
+/// name, so a name with capitals could never be called.
+///
+/// This is a `const fn`, so `ring_libinit!` checks its names at compile time.
+pub const fn ring_is_valid_function_name(name: &str) -> bool {
+    let bytes = name.as_bytes();
+    if bytes.is_empty() || bytes[0].is_ascii_digit() {
+        return false;
+    }
+    let mut i = 0;
+    while i < bytes.len() {
+        let b = bytes[i];
+        // Non-ASCII bytes belong to UTF-8 letters, which Ring allows in names
+        if !(b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'_' || b >= 0x80) {
+            return false;
+        }
+        i += 1;
     }
+    true
 }
@@ -0,0 +1,285 @@
+use crate::ffi_types::{CStr, c_void};
+
+use std::collections::{HashMap, HashSet};
+use std::sync::OnceLock;
+
+use crate::ffi;
+use crate::{Ring, RingError, RingState, RingVM, ring_api_error_str};
+
+/// A group of standard functions that Ring registers in every state.
+///
+/// The groups match the `ring-*` cargo features, which decide what a statically linked Ring
+/// loads for the whole process. [`StateBuilder`] removes groups from one state at runtime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FunctionGroup {
+    /// List functions: `add()`, `del()`, `find()`, `sort()`, ...
+    List,
+    /// Math functions: `sin()`, `sqrt()`, `random()`, ...
+    Math,
+    /// File functions: `fopen()`, `read()`, `write()`, `remove()`, `dir()`, ...
+    File,
+    /// OS functions: `system()`, `sysget()`, `chdir()`, `shutdown()`, ...
+    Os,
+    /// Dynamic library functions: `loadlib()`, `closelib()`
+    Dll,
+    /// Reflection and meta-programming functions: `ringvm_*()`, `classes()`, ...
+    RefMeta,
+    /// VM information functions: `version()`, `ringvm_info()`, ...
+    Info,
+}
+
+impl FunctionGroup {
+    /// Register the group's functions in `state`. Returns `false` if the group does not exist on
+    /// this target.
+    fn load(self, state: RingState) -> bool {
+        unsafe {
+            match self {
+                FunctionGroup::List => ffi::ring_vm_list_loadfunctions(state),
+                FunctionGroup::Math => ffi::ring_vm_math_loadfunctions(state),
+                #[cfg(not(target_arch = "wasm32"))]
+                FunctionGroup::File => ffi::ring_vm_file_loadfunctions(state),
+                #[cfg(not(target_arch = "wasm32"))]
+                FunctionGroup::Os => ffi::ring_vm_os_loadfunctions(state),
+                #[cfg(not(any(target_os = "android", target_os = "ios", target_arch = "wasm32")))]
+                FunctionGroup::Dll => ffi::ring_vm_dll_loadfunctions(state),
+                FunctionGroup::RefMeta => ffi::ring_vm_refmeta_loadfunctions(state),
+                FunctionGroup::Info => ffi::ring_vm_info_loadfunctions(state),
+                #[allow(unreachable_patterns)]
+                _ => return false,
+            }
+        }
+        true
+    }
+}
+
+/// Creates a [`Ring`] with a chosen set of standard functions.
+///
+/// Each state gets its own capabilities, so a restricted state for untrusted scripts can run
+/// next to a fully featured one in the same process. Removed functions stay defined but raise a
+/// Ring error when called. [`Ring::run`] evaluates code with `eval()`, so denying `eval` makes
+/// every run fail.
+///
+/// ```rust,ignore
+/// let mut sandbox = StateBuilder::sandboxed().disable(FunctionGroup::Info).build()?;
+/// assert!(sandbox.run(r#"system("rm -rf /")"#).is_err());
+///
+/// let mut admin = StateBuilder::new().build()?;
+/// admin.run(r#"system("ls")"#)?;
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct StateBuilder {
+    disabled: Vec<FunctionGroup>,
+    denied: Vec<String>,
+    /// Deny the functions starting with one of [`ESCAPE_PREFIXES`].
+    deny_escapes: bool,
+    #[cfg(feature = "extension")]
+    extensions: Vec<String>,
+}
+
+impl StateBuilder {
+    /// A builder with every function group enabled, like [`Ring::new`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A builder without the [`File`](FunctionGroup::File), [`Os`](FunctionGroup::Os) and
+    /// [`Dll`](FunctionGroup::Dll) groups, so scripts cannot touch the file system, run
+    /// commands or load native code.
+    ///
+    /// It also denies the `ringvm_*()` functions, which can change the VM's tracing, error
+    /// handling and output or evaluate code in another scope, and the `ring_state_*()`
+    /// functions, which create new states with every function available. The rest of
+    /// [`RefMeta`](FunctionGroup::RefMeta), such as `functions()` and `attributes()`, stays.
+    ///
+    /// This removes capabilities; it is not a security boundary on its own. Set
+    /// [`Ring::set_timeout`] and [`Ring::set_memory_limit`] to bound what a script can use, and
+    /// note that extensions loaded into the state keep all of their functions.
+    pub fn sandboxed() -> Self {
+        let mut builder = Self::new()
+            .disable(FunctionGroup::File)
+            .disable(FunctionGroup::Os)
+            .disable(FunctionGroup::Dll);
+        builder.deny_escapes = true;
+        builder
+    }
+
+    /// Remove every function in `group`.
+    pub fn disable(mut self, group: FunctionGroup) -> Self {
+        if !self.disabled.contains(&group) {
+            self.disabled.push(group);
+        }
+        self
+    }
+
+    /// Keep `group`, undoing [`disable`](Self::disable). Enabling
+    /// [`RefMeta`](FunctionGroup::RefMeta) also keeps the functions [`sandboxed`](Self::sandboxed)
+    /// denies.
+    pub fn enable(mut self, group: FunctionGroup) -> Self {
+        self.disabled.retain(|&g| g != group);
+        if group == FunctionGroup::RefMeta {
+            self.deny_escapes = false;
+        }
+        self
+    }
+
+    /// Remove a single function by name. Names are case-insensitive, as in Ring.
+    pub fn deny(mut self, name: &str) -> Self {
+        self.denied.push(name.to_ascii_lowercase());
+        self
+    }
+
+    /// Remove several functions by name. See [`deny`](Self::deny).
+    pub fn deny_all<I>(self, names: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        names
+            .into_iter()
+            .fold(self, |builder, name| builder.deny(name.as_ref()))
+    }
+
+    /// Load the extension registered under `name` with
+    /// [`ring_register_extension_optin`](crate::ring_register_extension_optin). Extensions are
+    /// loaded in the order given, before any function is removed; an unregistered name makes
+    /// [`build`](Self::build) fail.
+    #[cfg(feature = "extension")]
+    pub fn extension(mut self, name: &str) -> Self {
+        if !self.extensions.iter().any(|e| e == name) {
+            self.extensions.push(name.to_string());
+        }
+        self
+    }
+
+    /// Create the state, or fail with [`RingError::UnknownExtension`] if an
+    /// [`extension`](Self::extension) is not registered.
+    ///
+    /// Fails with [`RingError::EmptyFunctionGroup`] if a disabled group exists on this target
+    /// but no function of it could be found, rather than returning a state that still has them.
+    pub fn build(self) -> Result<Ring, RingError> {
+        let ring = Ring::new();
+        #[cfg(feature = "extension")]
+        for name in &self.extensions {
+            if !crate::ring_load_extension(ring.as_ptr(), name) {
+                return Err(RingError::UnknownExtension(name.clone()));
+            }
+        }
+        let mut denied: HashSet<String> = self.denied.into_iter().collect();
+        for group in self.disabled {
+            match group_functions(group) {
+                Some([]) => return Err(RingError::EmptyFunctionGroup(group)),
+                Some(names) => denied.extend(names.iter().cloned()),
+                None => {}
+            }
+        }
+        if self.deny_escapes {
+            denied.extend(unsafe {
+                cfunctions(ring.vm())
+                    .filter_map(|f| cfunction_name(f))
+                    .filter(|name| ESCAPE_PREFIXES.iter().any(|p| name.starts_with(p)))
+            });
+        }
+        if !denied.is_empty() {
+            deny_functions(ring.vm(), &denied);
+        }
+        Ok(ring)
+    }
+}
+
+/// Walk the VM's list of C functions.
+pub(crate) unsafe fn cfunctions(vm: RingVM) -> impl Iterator<Item = *mut ffi::CFunction> {
+    let mut next = unsafe { (*vm).pCFunction };
+    std::iter::from_fn(move || {
+        let current = next;
+        if current.is_null() {
+            return None;
+        }
+        next = unsafe { (*current).pNext };
+        Some(current)
+    })
+}
+
+/// The VM's C function registered as `name`, if any.
+pub(crate) unsafe fn find_cfunction(vm: RingVM, name: &[u8]) -> Option<*mut ffi::CFunction> {
+    unsafe {
+        cfunctions(vm)
+            .find(|&f| !(*f).cName.is_null() && CStr::from_ptr((*f).cName).to_bytes() == name)
+    }
+}
+
+/// The lowercase name of a C function from [`cfunctions`].
+unsafe fn cfunction_name(f: *mut ffi::CFunction) -> Option<String> {
+    unsafe {
+        let name = (*f).cName;
+        (!name.is_null()).then(|| CStr::from_ptr(name).to_string_lossy().to_ascii_lowercase())
+    }
+}
+
+/// Name prefixes of the standard functions [`StateBuilder::sandboxed`] denies outside the
+/// disabled groups.
+const ESCAPE_PREFIXES: &[&str] = &["ringvm_", "ring_state_"];
+
+/// The lowercase names registered by each group, or `None` if the group does not exist on this
+/// target.
+///
+/// The table is built once per process, from a scratch state that loads every group again and
+/// records what each one added, so it always matches the linked Ring and the states being built
+/// are never modified by it.
+fn group_functions(group: FunctionGroup) -> Option<&'static [String]> {
+    static NAMES: OnceLock<HashMap<FunctionGroup, Option<Vec<String>>>> = OnceLock::new();
+    let names = NAMES.get_or_init(|| {
+        let scratch = Ring::new();
+        let groups = [
+            FunctionGroup::List,
+            FunctionGroup::Math,
+            FunctionGroup::File,
+            FunctionGroup::Os,
+            FunctionGroup::Dll,
+            FunctionGroup::RefMeta,
+            FunctionGroup::Info,
+        ];
+        groups
+            .into_iter()
+            .map(|group| unsafe {
+                let before: HashSet<_> = cfunctions(scratch.vm()).collect();
+                let added = group.load(scratch.as_ptr()).then(|| {
+                    cfunctions(scratch.vm())
+                        .filter(|f| !before.contains(f))
+                        .filter_map(|f| cfunction_name(f))
+                        .collect()
+                });
+                (group, added)
+            })
+            .collect()
+    });
+    names.get(&group)?.as_deref()
+}
+
+/// Point every C function named in `names` at [`ring_rs_denied`].
+fn deny_functions(vm: RingVM, names: &HashSet<String>) {
+    unsafe {
+        for f in cfunctions(vm) {
+            if cfunction_name(f).is_some_and(|name| names.contains(&name)) {
+                (*f).pFunc = Some(ring_rs_denied);
+            }
+        }
+    }
+}
+
+pub(crate) extern "C" fn ring_rs_denied(p: *mut c_void) {
+    let vm = p as RingVM;
+    // The innermost frame is the call to the denied function
+    let name = unsafe {
+        let frames = ((*vm).nCurrentFuncCall as usize).min(ffi::RING_VM_STACK_SIZE);
+        frames
+            .checked_sub(1)
+            .map(|i| (&(*vm).aFuncCall)[i].cName)
+            .filter(|name| !name.is_null())
+            .map(|name| CStr::from_ptr(name).to_string_lossy().into_owned())
+            .unwrap_or_default()
+    };
+    ring_api_error_str(
+        p,
+        &format!("Calling function disabled in this state: {}", name),
+    );
+}
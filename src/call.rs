@@ -0,0 +1,262 @@
+use crate::ffi_types::{c_int, c_void};
+
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::Deref;
+
+use crate::ffi;
+use crate::{
+    ArgError, FromRing, IntoRing, ListRef, RingError, RingType, RingValue, ring_api_error_str,
+    ring_api_getlist, ring_api_getvalue, ring_api_isstring, ring_api_newlist, ring_api_paracount,
+    ring_api_retlist, ring_api_retobject, ring_api_retvalue, ring_list_getsize,
+};
+
+/// The context of one call into an extension function.
+///
+/// `ring_func!(name, |call: RingCall| { ... })` passes one of these to the body instead of the raw
+/// VM pointer. Borrowed arguments such as `call.arg::<&str>(1)` live for `'a`, so they cannot be
+/// kept after the function returns.
+pub struct RingCall<'a> {
+    p: *mut c_void,
+    _marker: PhantomData<&'a ffi::VM>,
+}
+
+impl<'a> RingCall<'a> {
+    /// Wrap the VM pointer passed to an extension function.
+    ///
+    /// # Safety
+    ///
+    /// `p` must be the pointer Ring passed to the running extension function, and the returned
+    /// value must not be used after that function returns.
+    pub unsafe fn from_raw(p: *mut c_void) -> RingCall<'a> {
+        RingCall {
+            p,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Run `body` with a context for `p` that cannot outlive it.
+    ///
+    /// This is what `ring_func!` uses for `|call: RingCall|` bodies.
+    pub fn scope<R>(p: *mut c_void, body: impl for<'b> FnOnce(RingCall<'b>) -> R) -> R {
+        body(RingCall {
+            p,
+            _marker: PhantomData,
+        })
+    }
+
+    /// The raw VM pointer, for use with the `ring_api_*` functions and macros.
+    pub fn as_ptr(&self) -> *mut c_void {
+        self.p
+    }
+
+    /// The number of arguments the function was called with.
+    pub fn argc(&self) -> c_int {
+        ring_api_paracount(self.p)
+    }
+
+    /// Read argument `n` (1-based) as `T`.
+    pub fn arg<T: FromRingArg<'a>>(&self, n: c_int) -> Result<T, ArgError> {
+        let count = self.argc();
+        if n < 1 || n > count {
+            return Err(ArgError::Missing { index: n, count });
+        }
+        T::from_arg(self, n)
+    }
+
+    /// The arguments from `first` (1-based) to the last one, for functions taking any number of
+    /// arguments.
+    pub fn args_from(&self, first: c_int) -> RingArgs {
+        RingArgs::from_params(self.p, first)
+    }
+
+    /// Set the return value of the function.
+    pub fn ret<T: IntoRing>(&self, value: T) {
+        ring_api_retvalue(self.p, &value.into_ring());
+    }
+
+    /// Create a list owned by the VM, to fill and pass to [`ret_list`](Self::ret_list).
+    pub fn new_list(&self) -> ListRef<'a> {
+        unsafe { ListRef::from_raw(ring_api_newlist(self.p)) }
+    }
+
+    /// Return `list` without converting it to a [`RingValue`](crate::RingValue) first.
+    pub fn ret_list(&self, list: &ListRef<'_>) {
+        ring_api_retlist(self.p, list.as_ptr());
+    }
+
+    /// Return `value` as a managed C pointer that Ring frees. See [`ring_api_retobject`].
+    pub fn ret_object<T: RingType>(&self, value: Box<T>) {
+        ring_api_retobject(self.p, value);
+    }
+
+    /// Read argument `n` as an object returned by [`ret_object`](Self::ret_object). See
+    /// [`ring_api_getobject`](crate::ring_api_getobject).
+    pub fn get_object<T: RingType>(&self, n: c_int) -> Result<&'a T, ArgError> {
+        let count = self.argc();
+        if n < 1 || n > count {
+            return Err(ArgError::Missing { index: n, count });
+        }
+        unsafe { crate::object::get_object(self.p, n) }
+    }
+
+    /// Raise a Ring runtime error, which Ring code can handle with `try/catch`.
+    ///
+    /// The function should return right after: `return call.error(e);`.
+    pub fn error(&self, message: impl fmt::Display) {
+        ring_api_error_str(self.p, &message.to_string().replace('\0', ""));
+    }
+}
+
+/// The trailing arguments of a function that takes any number of them, such as Ring's own
+/// `max()`.
+///
+/// In `ring_extension!`, a last parameter of type `RingArgs` (or `&[RingValue]`) receives every
+/// argument after the named ones, and the argument count is only checked against those:
+///
+/// ```rust,ignore
+/// pub fn total(args: RingArgs) -> Result<f64, ArgError> {
+///     (0..args.len()).map(|i| args.get_as::<f64>(i)).sum()
+/// }
+/// ```
+///
+/// It dereferences to the values as a slice, so `len()`, `iter()` and indexing work directly.
+#[derive(Debug, Clone)]
+pub struct RingArgs {
+    first: c_int,
+    values: Vec<RingValue>,
+}
+
+impl RingArgs {
+    /// Read the arguments from `first` (1-based) to the last one passed to `p`'s function. This
+    /// is empty when there are fewer than `first` arguments.
+    pub fn from_params(p: *mut c_void, first: c_int) -> RingArgs {
+        let first = first.max(1);
+        let values = (first..=ring_api_paracount(p))
+            .map(|n| ring_api_getvalue(p, n))
+            .collect();
+        RingArgs { first, values }
+    }
+
+    /// Read the items of list argument `n` as the trailing arguments, numbered from `n`. The
+    /// methods of classes generated by `ring_extension!` pass them this way, since Ring
+    /// functions take a fixed number of parameters.
+    pub fn from_list_param(p: *mut c_void, n: c_int) -> RingArgs {
+        let list = ring_api_getlist(p, n);
+        let values = (1..=ring_list_getsize(list))
+            .map(|i| RingValue::from_list_item(list, i))
+            .collect();
+        RingArgs { first: n, values }
+    }
+
+    /// The 1-based argument number of the first value.
+    pub fn first(&self) -> c_int {
+        self.first
+    }
+
+    /// Convert the value at `index` (0-based) to `T`. Errors name the argument's number in the
+    /// call.
+    pub fn get_as<T: FromRing>(&self, index: usize) -> Result<T, ArgError> {
+        let n = self.first + index as c_int;
+        match self.values.get(index) {
+            Some(value) => T::from_ring(value.clone()).map_err(|e| ArgError::from_ring_error(n, e)),
+            None => Err(ArgError::Missing {
+                index: n,
+                count: self.first - 1 + self.values.len() as c_int,
+            }),
+        }
+    }
+
+    /// The values, without the argument numbers.
+    pub fn into_vec(self) -> Vec<RingValue> {
+        self.values
+    }
+}
+
+impl Deref for RingArgs {
+    type Target = [RingValue];
+
+    fn deref(&self) -> &[RingValue] {
+        &self.values
+    }
+}
+
+impl IntoIterator for RingArgs {
+    type Item = RingValue;
+    type IntoIter = std::vec::IntoIter<RingValue>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.values.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a RingArgs {
+    type Item = &'a RingValue;
+    type IntoIter = std::slice::Iter<'a, RingValue>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.values.iter()
+    }
+}
+
+/// Read an extension function argument. See [`RingCall::arg`].
+///
+/// Implemented for every [`FromRing`] type, and for `&str` and `&[u8]` borrowed from the call.
+pub trait FromRingArg<'a>: Sized {
+    fn from_arg(call: &RingCall<'a>, n: c_int) -> Result<Self, ArgError>;
+}
+
+impl<'a, T: FromRing> FromRingArg<'a> for T {
+    fn from_arg(call: &RingCall<'a>, n: c_int) -> Result<Self, ArgError> {
+        T::from_ring(ring_api_getvalue(call.p, n)).map_err(|e| ArgError::from_ring_error(n, e))
+    }
+}
+
+impl<'a> FromRingArg<'a> for &'a [u8] {
+    fn from_arg(call: &RingCall<'a>, n: c_int) -> Result<Self, ArgError> {
+        if !ring_api_isstring(call.p, n) {
+            return Err(ArgError::TypeMismatch {
+                index: n,
+                expected: "string",
+                found: ring_api_getvalue(call.p, n).type_name(),
+            });
+        }
+        unsafe {
+            let ptr = ffi::ring_vm_api_getstring(call.p, n);
+            let size = ffi::ring_vm_api_getstringsize(call.p, n) as usize;
+            if ptr.is_null() || size == 0 {
+                Ok(&[])
+            } else {
+                Ok(std::slice::from_raw_parts(ptr as *const u8, size))
+            }
+        }
+    }
+}
+
+impl<'a> FromRingArg<'a> for &'a str {
+    fn from_arg(call: &RingCall<'a>, n: c_int) -> Result<Self, ArgError> {
+        let bytes = <&'a [u8]>::from_arg(call, n)?;
+        std::str::from_utf8(bytes).map_err(|e| ArgError::Invalid {
+            index: n,
+            message: e.to_string(),
+        })
+    }
+}
+
+impl ArgError {
+    /// The error for argument `index` failing to convert. Used by `ring_get_value!`.
+    #[doc(hidden)]
+    pub fn from_ring_error(index: c_int, error: RingError) -> ArgError {
+        match error {
+            RingError::TypeMismatch { expected, found } => ArgError::TypeMismatch {
+                index,
+                expected,
+                found,
+            },
+            other => ArgError::Invalid {
+                index,
+                message: other.to_string(),
+            },
+        }
+    }
+}
@@ -0,0 +1,315 @@
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hash};
+
+use crate::ffi_types::c_int;
+
+use crate::{RingError, RingValue};
+
+/// Convert a Rust value into a [`RingValue`].
+///
+/// Implemented for numbers, `bool`, strings, `Vec<T>`, `Option<T>`, tuples, `HashMap<K, V>` and
+/// [`RingValue`] itself. Use `#[derive(IntoRing)]` from `ring-lang-codegen` for your own structs.
+pub trait IntoRing {
+    fn into_ring(self) -> RingValue;
+}
+
+/// Convert a [`RingValue`] into a Rust value, checking its type.
+///
+/// Implemented for the same types as [`IntoRing`]. Use `#[derive(FromRing)]` from
+/// `ring-lang-codegen` for your own structs.
+pub trait FromRing: Sized {
+    fn from_ring(value: RingValue) -> Result<Self, RingError>;
+}
+
+/// The error returned when a value has the wrong Ring type for `T`.
+pub fn ring_type_mismatch<T>(value: &RingValue) -> RingError {
+    RingError::TypeMismatch {
+        expected: std::any::type_name::<T>(),
+        found: value.type_name(),
+    }
+}
+
+impl IntoRing for RingValue {
+    fn into_ring(self) -> RingValue {
+        self
+    }
+}
+
+impl FromRing for RingValue {
+    fn from_ring(value: RingValue) -> Result<Self, RingError> {
+        Ok(value)
+    }
+}
+
+macro_rules! impl_ring_number {
+    ($variant:ident as $via:ty, $fits:ident: $($t:ty),*) => {$(
+        impl IntoRing for $t {
+            fn into_ring(self) -> RingValue {
+                RingValue::$variant(self as $via)
+            }
+        }
+
+        impl FromRing for $t {
+            fn from_ring(value: RingValue) -> Result<Self, RingError> {
+                let n = match value {
+                    RingValue::Int(n) => n as f64,
+                    RingValue::Double(n) => n,
+                    other => return Err(ring_type_mismatch::<$t>(&other)),
+                };
+                if !$fits(n, <$t>::MIN as f64, <$t>::MAX as f64) {
+                    return Err(RingError::Conversion(format!(
+                        "{} is not a valid {}",
+                        n,
+                        stringify!($t)
+                    )));
+                }
+                Ok(n as $t)
+            }
+        }
+    )*};
+}
+
+/// Integers take whole numbers within range; `as` would truncate or saturate instead.
+fn integer_fits(n: f64, min: f64, max: f64) -> bool {
+    n.fract() == 0.0 && n >= min && n < max + 1.0
+}
+
+/// Floats take anything except a finite number that overflows to infinity.
+fn float_fits(n: f64, min: f64, max: f64) -> bool {
+    !n.is_finite() || (min..=max).contains(&n)
+}
+
+impl_ring_number!(Int as c_int, integer_fits: i8, i16, i32, u8, u16);
+impl_ring_number!(Double as f64, integer_fits: i64, i128, isize, u32, u64, u128, usize);
+impl_ring_number!(Double as f64, float_fits: f32, f64);
+
+impl IntoRing for bool {
+    fn into_ring(self) -> RingValue {
+        RingValue::Int(self as c_int)
+    }
+}
+
+impl FromRing for bool {
+    fn from_ring(value: RingValue) -> Result<Self, RingError> {
+        f64::from_ring(value).map(|n| n != 0.0)
+    }
+}
+
+impl IntoRing for () {
+    fn into_ring(self) -> RingValue {
+        RingValue::Nothing
+    }
+}
+
+impl FromRing for () {
+    fn from_ring(_value: RingValue) -> Result<Self, RingError> {
+        Ok(())
+    }
+}
+
+impl IntoRing for String {
+    fn into_ring(self) -> RingValue {
+        RingValue::String(self.into_bytes())
+    }
+}
+
+impl IntoRing for &str {
+    fn into_ring(self) -> RingValue {
+        RingValue::String(self.as_bytes().to_vec())
+    }
+}
+
+impl IntoRing for &String {
+    fn into_ring(self) -> RingValue {
+        self.as_str().into_ring()
+    }
+}
+
+impl FromRing for String {
+    fn from_ring(value: RingValue) -> Result<Self, RingError> {
+        match value {
+            RingValue::String(bytes) => String::from_utf8(bytes)
+                .map_err(|e| RingError::Conversion(format!("string is not valid UTF-8: {}", e))),
+            other => Err(ring_type_mismatch::<String>(&other)),
+        }
+    }
+}
+
+impl<T: IntoRing> IntoRing for Vec<T> {
+    fn into_ring(self) -> RingValue {
+        RingValue::List(self.into_iter().map(IntoRing::into_ring).collect())
+    }
+}
+
+impl<T: IntoRing + Clone> IntoRing for &[T] {
+    fn into_ring(self) -> RingValue {
+        RingValue::List(self.iter().cloned().map(IntoRing::into_ring).collect())
+    }
+}
+
+impl<T: FromRing> FromRing for Vec<T> {
+    fn from_ring(value: RingValue) -> Result<Self, RingError> {
+        match value {
+            RingValue::List(items) => items.into_iter().map(T::from_ring).collect(),
+            other => Err(ring_type_mismatch::<Vec<T>>(&other)),
+        }
+    }
+}
+
+/// `None` becomes an empty string, which is how Ring represents `NULL`.
+impl<T: IntoRing> IntoRing for Option<T> {
+    fn into_ring(self) -> RingValue {
+        match self {
+            Some(value) => value.into_ring(),
+            None => RingValue::String(Vec::new()),
+        }
+    }
+}
+
+/// Nothing and the empty string (Ring's `NULL`) become `None`.
+impl<T: FromRing> FromRing for Option<T> {
+    fn from_ring(value: RingValue) -> Result<Self, RingError> {
+        match value {
+            RingValue::Nothing => Ok(None),
+            RingValue::String(ref bytes) if bytes.is_empty() => Ok(None),
+            other => T::from_ring(other).map(Some),
+        }
+    }
+}
+
+impl<T: IntoRing> IntoRing for Box<T> {
+    fn into_ring(self) -> RingValue {
+        (*self).into_ring()
+    }
+}
+
+impl<T: FromRing> FromRing for Box<T> {
+    fn from_ring(value: RingValue) -> Result<Self, RingError> {
+        T::from_ring(value).map(Box::new)
+    }
+}
+
+/// A map becomes a list of `[key, value]` pairs, which Ring code can index with `list[:key]`.
+impl<K: IntoRing, V: IntoRing, S> IntoRing for HashMap<K, V, S> {
+    fn into_ring(self) -> RingValue {
+        RingValue::List(
+            self.into_iter()
+                .map(|(k, v)| RingValue::List(vec![k.into_ring(), v.into_ring()]))
+                .collect(),
+        )
+    }
+}
+
+impl<K, V, S> FromRing for HashMap<K, V, S>
+where
+    K: FromRing + Eq + Hash,
+    V: FromRing,
+    S: BuildHasher + Default,
+{
+    fn from_ring(value: RingValue) -> Result<Self, RingError> {
+        match value {
+            RingValue::List(pairs) => pairs.into_iter().map(<(K, V)>::from_ring).collect(),
+            other => Err(ring_type_mismatch::<Self>(&other)),
+        }
+    }
+}
+
+macro_rules! impl_ring_tuple {
+    ($len:literal => $($name:ident),+) => {
+        impl<$($name: IntoRing),+> IntoRing for ($($name,)+) {
+            #[allow(non_snake_case)]
+            fn into_ring(self) -> RingValue {
+                let ($($name,)+) = self;
+                RingValue::List(vec![$($name.into_ring()),+])
+            }
+        }
+
+        impl<$($name: FromRing),+> FromRing for ($($name,)+) {
+            fn from_ring(value: RingValue) -> Result<Self, RingError> {
+                match value {
+                    RingValue::List(items) if items.len() == $len => {
+                        let mut items = items.into_iter();
+                        Ok(($($name::from_ring(items.next().unwrap_or_default())?,)+))
+                    }
+                    other => Err(ring_type_mismatch::<Self>(&other)),
+                }
+            }
+        }
+    };
+}
+
+impl_ring_tuple!(1 => A);
+impl_ring_tuple!(2 => A, B);
+impl_ring_tuple!(3 => A, B, C);
+impl_ring_tuple!(4 => A, B, C, D);
+impl_ring_tuple!(5 => A, B, C, D, E);
+impl_ring_tuple!(6 => A, B, C, D, E, F);
+impl_ring_tuple!(7 => A, B, C, D, E, F, G);
+impl_ring_tuple!(8 => A, B, C, D, E, F, G, H);
+
+/// Find a field by name in a list of `[name, value]` pairs or in an object's attributes.
+///
+/// Ring attribute names are case-insensitive, so names are compared ignoring ASCII case. Used by
+/// `#[derive(FromRing)]`.
+pub fn ring_take_field(fields: &mut Vec<(String, RingValue)>, name: &str) -> RingValue {
+    match fields
+        .iter()
+        .position(|(k, _)| k.eq_ignore_ascii_case(name))
+    {
+        Some(i) => fields.swap_remove(i).1,
+        None => RingValue::Nothing,
+    }
+}
+
+/// Split a list of `[name, value]` pairs or an object into named fields. Used by
+/// `#[derive(FromRing)]`.
+pub fn ring_named_fields<T>(value: RingValue) -> Result<Vec<(String, RingValue)>, RingError> {
+    match value {
+        RingValue::Object { attributes, .. } => Ok(attributes),
+        RingValue::List(pairs) => pairs
+            .into_iter()
+            .map(|pair| match pair {
+                RingValue::List(mut kv) if kv.len() == 2 => {
+                    let value = kv.pop().unwrap_or_default();
+                    match kv.pop() {
+                        Some(RingValue::String(key)) => {
+                            Ok((String::from_utf8_lossy(&key).into_owned(), value))
+                        }
+                        Some(other) => Err(ring_type_mismatch::<T>(&other)),
+                        None => Err(ring_type_mismatch::<T>(&RingValue::Nothing)),
+                    }
+                }
+                other => Err(ring_type_mismatch::<T>(&other)),
+            })
+            .collect(),
+        other => Err(ring_type_mismatch::<T>(&other)),
+    }
+}
+
+/// Split an enum value into its variant name and payload: a string holds just the name, and a
+/// list `[name, payload...]` holds both. Used by `#[derive(FromRing)]` for enums.
+pub fn ring_enum_variant<T>(value: RingValue) -> Result<(String, Vec<RingValue>), RingError> {
+    match value {
+        RingValue::String(name) => Ok((String::from_utf8_lossy(&name).into_owned(), Vec::new())),
+        RingValue::List(items) => {
+            let mut items = items.into_iter();
+            match items.next() {
+                Some(RingValue::String(name)) => {
+                    Ok((String::from_utf8_lossy(&name).into_owned(), items.collect()))
+                }
+                Some(other) => Err(ring_type_mismatch::<T>(&other)),
+                None => Err(ring_type_mismatch::<T>(&RingValue::List(Vec::new()))),
+            }
+        }
+        other => Err(ring_type_mismatch::<T>(&other)),
+    }
+}
+
+/// The error returned when a name or number matches none of `T`'s enum variants.
+pub fn ring_unknown_variant<T>(variant: impl std::fmt::Display) -> RingError {
+    RingError::Conversion(format!(
+        "unknown variant {} for {}",
+        variant,
+        std::any::type_name::<T>()
+    ))
+}
@@ -0,0 +1,174 @@
+use std::fmt;
+use std::io;
+
+use crate::FunctionGroup;
+use crate::ffi_types::c_int;
+
+/// Error returned by the checked execution functions on [`Ring`](crate::Ring).
+#[derive(Debug)]
+pub enum RingError {
+    /// A runtime error raised by the script (or by a C/Rust function it called).
+    Runtime {
+        /// The error message, as Ring stores it in `cCatchError`.
+        message: String,
+        /// The file that was executing when the error was caught, if known.
+        file: Option<String>,
+        /// The line number reported by the VM (`nLineNumber`).
+        line: u32,
+        /// Names of the Ring functions that were active, outermost first.
+        call_stack: Vec<String>,
+    },
+    /// The called function is not defined.
+    UndefinedFunction(String),
+    /// The global variable is not defined, or its name is not valid.
+    UndefinedVariable(String),
+    /// A value had a different Ring type than the Rust type it was converted to.
+    TypeMismatch {
+        expected: &'static str,
+        found: &'static str,
+    },
+    /// A value could not be converted between Rust and Ring (reported by serde).
+    Conversion(String),
+    /// A call's arguments would not fit on the VM's stack.
+    StackOverflow,
+    /// The script ran past the step limit or timeout set on the [`Ring`](crate::Ring).
+    Timeout,
+    /// The state's data grew past the memory limit set on the [`Ring`](crate::Ring).
+    OutOfMemory,
+    /// No extension is registered under the name given to
+    /// [`StateBuilder::extension`](crate::StateBuilder).
+    UnknownExtension(String),
+    /// No function of a group disabled with
+    /// [`StateBuilder::disable`](crate::StateBuilder::disable) was found, so it could not be
+    /// removed.
+    EmptyFunctionGroup(FunctionGroup),
+    /// A source file could not be read.
+    Io(io::Error),
+}
+
+impl RingError {
+    /// The error message without location information.
+    pub fn message(&self) -> String {
+        match self {
+            RingError::Runtime { message, .. } => message.clone(),
+            other => other.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for RingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RingError::Runtime {
+                message,
+                file,
+                line,
+                ..
+            } => {
+                write!(f, "{}", message)?;
+                match file {
+                    Some(file) => write!(f, " (in {} at line {})", file, line),
+                    None if *line > 0 => write!(f, " (at line {})", line),
+                    None => Ok(()),
+                }
+            }
+            RingError::UndefinedFunction(name) => {
+                write!(f, "Calling function without definition: {}", name)
+            }
+            RingError::UndefinedVariable(name) => {
+                write!(f, "Using uninitialized variable: {}", name)
+            }
+            RingError::TypeMismatch { expected, found } => {
+                write!(f, "Type mismatch: expected {}, found {}", expected, found)
+            }
+            RingError::Conversion(message) => write!(f, "{}", message),
+            RingError::StackOverflow => write!(f, "VM stack overflow"),
+            RingError::Timeout => write!(f, "Execution limit exceeded"),
+            RingError::OutOfMemory => write!(f, "Memory limit exceeded"),
+            RingError::UnknownExtension(name) => write!(f, "Extension not registered: {}", name),
+            RingError::EmptyFunctionGroup(group) => {
+                write!(f, "No functions found for the {:?} group", group)
+            }
+            RingError::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for RingError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RingError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for RingError {
+    fn from(e: io::Error) -> Self {
+        RingError::Io(e)
+    }
+}
+
+/// Error returned by [`RingCall::arg`](crate::RingCall::arg) when an argument cannot be read.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArgError {
+    /// The function was called with fewer than `index` arguments.
+    Missing { index: c_int, count: c_int },
+    /// The argument had a different Ring type than the Rust type it was read as.
+    TypeMismatch {
+        index: c_int,
+        expected: &'static str,
+        found: &'static str,
+    },
+    /// The argument had the right type but an unusable value, such as a string that is not UTF-8.
+    Invalid { index: c_int, message: String },
+}
+
+impl fmt::Display for ArgError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArgError::Missing { index, count } => write!(
+                f,
+                "Bad parameters count! (parameter {} requested, {} given)",
+                index, count
+            ),
+            ArgError::TypeMismatch {
+                index,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Bad parameter type! (parameter {}: expected {}, found {})",
+                index, expected, found
+            ),
+            ArgError::Invalid { index, message } => {
+                write!(f, "Bad parameter value! (parameter {}: {})", index, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ArgError {}
+
+/// Error returned by [`ring_register_function_str`](crate::ring_register_function_str) and
+/// [`Ring::register_fn`](crate::Ring::register_fn).
+#[derive(Debug, Clone, PartialEq)]
+pub enum RegisterError {
+    /// The name is empty, starts with a digit, or has capitals, spaces or punctuation.
+    InvalidName(String),
+    /// A function with this name is already registered in the state.
+    Duplicate(String),
+}
+
+impl fmt::Display for RegisterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegisterError::InvalidName(name) => write!(f, "Invalid function name: {:?}", name),
+            RegisterError::Duplicate(name) => {
+                write!(f, "Function already registered: {}", name)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RegisterError {}
@@ -2,13 +2,113 @@ use crate::RingState;
 use crate::ffi;
 use std::sync::Mutex;
 
-type ExtensionInitFn = extern "C" fn(RingState);
+pub type ExtensionInitFn = extern "C" fn(RingState);
 
-static EXTENSION_INITS: Mutex<Vec<ExtensionInitFn>> = Mutex::new(Vec::new());
+/// A registered extension. Unnamed ones come from [`ring_register_extension`].
+struct Extension {
+    name: Option<String>,
+    init: ExtensionInitFn,
+    /// Applied to every state by [`ring_vm_extension`], not only on request.
+    auto: bool,
+}
+
+/// Registered extensions, in the order they are applied.
+static EXTENSIONS: Mutex<Vec<Extension>> = Mutex::new(Vec::new());
 
+/// Apply `f` to every state created from now on. Registering the same function again does
+/// nothing.
 pub fn ring_register_extension(f: ExtensionInitFn) {
-    if let Ok(mut inits) = EXTENSION_INITS.lock() {
-        inits.push(f);
+    if let Ok(mut extensions) = EXTENSIONS.lock() {
+        if !extensions
+            .iter()
+            .any(|e| e.name.is_none() && std::ptr::fn_addr_eq(e.init, f))
+        {
+            extensions.push(Extension {
+                name: None,
+                init: f,
+                auto: true,
+            });
+        }
+    }
+}
+
+/// Apply `f` to every state created from now on, under `name`.
+///
+/// Extensions are applied in registration order. Registering a name again replaces its init
+/// function and keeps its place.
+pub fn ring_register_extension_named(name: &str, f: ExtensionInitFn) {
+    register_named(name, f, true);
+}
+
+/// Register `f` under `name` without applying it to new states.
+///
+/// Load it into the states that need it with [`ring_load_extension`] (or
+/// [`StateBuilder::extension`](crate::StateBuilder::extension)), so different states can have
+/// different extension sets.
+pub fn ring_register_extension_optin(name: &str, f: ExtensionInitFn) {
+    register_named(name, f, false);
+}
+
+fn register_named(name: &str, init: ExtensionInitFn, auto: bool) {
+    if let Ok(mut extensions) = EXTENSIONS.lock() {
+        if let Some(e) = extensions
+            .iter_mut()
+            .find(|e| e.name.as_deref() == Some(name))
+        {
+            e.init = init;
+            e.auto = auto;
+        } else {
+            extensions.push(Extension {
+                name: Some(name.to_string()),
+                init,
+                auto,
+            });
+        }
+    }
+}
+
+/// Remove the extension registered under `name`. States that already loaded it keep its
+/// functions. Returns `false` if no extension has that name.
+pub fn ring_unregister_extension(name: &str) -> bool {
+    let Ok(mut extensions) = EXTENSIONS.lock() else {
+        return false;
+    };
+    let count = extensions.len();
+    extensions.retain(|e| e.name.as_deref() != Some(name));
+    extensions.len() != count
+}
+
+/// The names of the registered extensions, in the order they are applied.
+pub fn ring_extension_names() -> Vec<String> {
+    EXTENSIONS
+        .lock()
+        .map(|extensions| extensions.iter().filter_map(|e| e.name.clone()).collect())
+        .unwrap_or_default()
+}
+
+/// Apply the extension registered under `name` to `state`. Returns `false` if no extension has
+/// that name.
+///
+/// This is meant for extensions registered with [`ring_register_extension_optin`]; the others
+/// are already applied to every state.
+///
+/// Loading is not idempotent: each call runs the init function again. Init functions made with
+/// [`ring_libinit!`](crate::ring_libinit) or `ring_extension!` allow this, since registering a
+/// function the state already has from them does nothing; other init functions must check for
+/// themselves.
+pub fn ring_load_extension(state: RingState, name: &str) -> bool {
+    let init = EXTENSIONS.lock().ok().and_then(|extensions| {
+        extensions
+            .iter()
+            .find(|e| e.name.as_deref() == Some(name))
+            .map(|e| e.init)
+    });
+    match init {
+        Some(init) => {
+            init(state);
+            true
+        }
+        None => false,
     }
 }
 
@@ -42,9 +142,18 @@ pub extern "C" fn ring_vm_extension(state: RingState) {
         ffi::ring_vm_info_loadfunctions(state);
     }
 
-    if let Ok(inits) = EXTENSION_INITS.lock() {
-        for init in inits.iter() {
-            init(state);
-        }
+    // Copied out so an init function can register extensions without deadlocking
+    let inits: Vec<ExtensionInitFn> = EXTENSIONS
+        .lock()
+        .map(|extensions| {
+            extensions
+                .iter()
+                .filter(|e| e.auto)
+                .map(|e| e.init)
+                .collect()
+        })
+        .unwrap_or_default();
+    for init in inits {
+        init(state);
     }
 }
@@ -61,6 +61,20 @@ pub struct FuncCall {
     pub bitfields: c_uint,
 }
 
+/// The leading fields of `RingState`, up to its VM. The rest of the struct is only used through
+/// the `ring_state_*` functions.
+#[repr(C)]
+pub struct RingStateHead {
+    pub pRingFilesList: RingList,
+    pub pRingFilesStack: RingList,
+    pub pRingGenCode: RingList,
+    pub pRingFunctionsMap: RingList,
+    pub pRingClassesMap: RingList,
+    pub pRingPackagesMap: RingList,
+    pub pRingCFunctions: *mut c_void,
+    pub pVM: RingVM,
+}
+
 #[repr(C)]
 pub struct VM {
     pub pRingState: *mut c_void,
@@ -189,6 +203,12 @@ impl Item {
         (self.flags >> 5) & 0x3
     }
 
+    /// Set what a pointer item points at, one of the `RING_OBJTYPE_*` constants.
+    #[inline]
+    pub fn set_nObjectType(&mut self, object_type: c_uint) {
+        self.flags = (self.flags & !(0x3 << 5)) | ((object_type & 0x3) << 5);
+    }
+
     #[inline]
     pub fn lAssignment(&self) -> bool {
         (self.flags >> 7) & 0x1 != 0
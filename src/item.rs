@@ -5,6 +5,19 @@ use crate::ffi::{
     ITEMTYPE_STRING, Item, RingItem,
 };
 
+/// Human readable name of an `ITEMTYPE_*` value.
+pub fn ring_item_typename(item_type: c_uint) -> &'static str {
+    match item_type {
+        ITEMTYPE_NOTHING => "nothing",
+        ITEMTYPE_STRING => "string",
+        ITEMTYPE_NUMBER => "number",
+        ITEMTYPE_POINTER => "pointer",
+        ITEMTYPE_LIST => "list",
+        ITEMTYPE_FUNCPOINTER => "function pointer",
+        _ => "unknown",
+    }
+}
+
 #[inline]
 pub fn ring_item_new(item_type: c_uint) -> RingItem {
     unsafe { ffi::ring_item_new(item_type) }
@@ -18,7 +18,10 @@
 //! - Pointers (`RingState`, `RingList`, `RingVM`) are valid and non-null
 //! - Objects are not used after being deleted
 //! - `ring_list_getstring_str()` returns an owned `String` (safe to store)
-//! - `ring_api_getstring_str()` returns a reference valid only during the callback
+//! - `ring_api_getstring_str()` / `ring_api_getstring_bytes()` are `unsafe`: the VM frees the
+//!   string after the callback returns. `RingCall::arg::<&str>()` borrows it safely, and
+//!   `ring_api_getstring_string()` returns an owned copy
+//! - `ring_get_string!` / `ring_get_bytes!` must only be given the callback's own `p`
 
 #![allow(non_snake_case)]
 #![allow(non_camel_case_types)]
@@ -29,6 +32,10 @@
 #![allow(clippy::manual_c_str_literals)]
 
 pub mod api;
+pub mod builder;
+pub mod call;
+pub mod convert;
+pub mod error;
 #[cfg(feature = "extension")]
 pub mod extension;
 pub mod ffi;
@@ -36,22 +43,43 @@ mod ffi_types;
 pub mod general;
 pub mod item;
 pub mod list;
+pub mod memory;
+pub mod object;
+#[cfg(not(target_family = "wasm"))]
+pub mod output;
+#[cfg(feature = "serde")]
+pub mod serialize;
 pub mod state;
 pub mod string;
 #[cfg(test)]
 mod tests;
+// Lets the derive macros' `::ring_lang_rs` paths resolve in this crate's tests
+#[cfg(test)]
+extern crate self as ring_lang_rs;
+pub mod value;
 pub mod vm;
 #[macro_use]
 pub mod macros;
 
 pub use api::*;
+pub use builder::*;
+pub use call::*;
+pub use convert::*;
+pub use error::*;
 #[cfg(feature = "extension")]
 pub use extension::*;
 pub use general::*;
 pub use item::*;
 pub use list::*;
+pub use memory::*;
+pub use object::*;
+#[cfg(not(target_family = "wasm"))]
+pub use output::*;
+#[cfg(feature = "serde")]
+pub use serialize::*;
 pub use state::*;
 pub use string::*;
+pub use value::*;
 pub use vm::*;
 
 use ffi_types::c_void;
@@ -61,9 +89,20 @@ pub type RingVM = *mut ffi::VM;
 pub type RingList = *mut ffi::List;
 pub type RingFunc = extern "C" fn(*mut c_void);
 
+pub const RING_CPOINTER_POINTER: ffi_types::c_uint = 1;
+pub const RING_CPOINTER_TYPE: ffi_types::c_uint = 2;
 pub const RING_CPOINTER_STATUS: ffi_types::c_uint = 3;
+pub const RING_CPOINTERSTATUS_NOTCOPIED: ffi_types::c_int = 0;
+pub const RING_CPOINTERSTATUS_COPIED: ffi_types::c_int = 1;
 pub const RING_CPOINTERSTATUS_NOTASSIGNED: ffi_types::c_int = 2;
 
+pub const RING_OBJECT_CLASSPTR: ffi_types::c_uint = 1;
+pub const RING_OBJECT_OBJECTDATA: ffi_types::c_uint = 2;
+
+pub const RING_OBJTYPE_VARIABLE: ffi_types::c_uint = 1;
+pub const RING_OBJTYPE_LISTITEM: ffi_types::c_uint = 2;
+pub const RING_OBJTYPE_SUBLIST: ffi_types::c_uint = 3;
+
 pub const RING_OUTPUT_RETLIST: ffi_types::c_int = 0;
 pub const RING_OUTPUT_RETLISTBYREF: ffi_types::c_int = 1;
 pub const RING_OUTPUT_RETNEWREF: ffi_types::c_int = 2;
@@ -77,6 +116,12 @@ pub const RING_VAR_VALUE: ffi_types::c_uint = 3;
 pub const RING_VAR_PVALUETYPE: ffi_types::c_uint = 4;
 pub const RING_VAR_PRIVATEFLAG: ffi_types::c_uint = 5;
 
+pub const RING_VM_NULL: ffi_types::c_int = 0;
+pub const RING_VM_STRING: ffi_types::c_int = 1;
+pub const RING_VM_NUMBER: ffi_types::c_int = 2;
+pub const RING_VM_LIST: ffi_types::c_int = 3;
+pub const RING_VM_POINTER: ffi_types::c_int = 4;
+
 pub const RING_API_MISS1PARA: &[u8] = b"Bad parameters count, the function expect one parameter\0";
 pub const RING_API_MISS2PARA: &[u8] = b"Bad parameters count, the function expect two parameters\0";
 pub const RING_API_MISS3PARA: &[u8] =
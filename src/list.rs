@@ -1,7 +1,11 @@
 use crate::ffi_types::{CStr, CString, c_char, c_double, c_int, c_uint, c_void};
 
-use crate::RingList;
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+
 use crate::ffi;
+use crate::{RingList, RingValue};
 
 #[inline]
 pub fn ring_list_new(size: c_uint) -> RingList {
@@ -367,3 +371,545 @@ pub fn ring_list_getitem(list: RingList, index: c_uint) -> *mut ffi::Item {
 pub fn ring_list_setlist(list: RingList, index: c_uint) {
     unsafe { ffi::ring_list_setlist(list, index) }
 }
+
+// Safe wrappers
+
+/// A borrowed Ring list with bounds-checked, 0-based access.
+///
+/// Indices follow Rust conventions (`0..len()`) and are converted to Ring's 1-based positions
+/// internally; out-of-range reads return `None` instead of touching memory past the list.
+/// Reading borrows the list and writing needs `&mut`, so a `&[u8]` returned by [`ListRef::get`]
+/// cannot outlive a [`ListRef::remove`] that frees it.
+///
+/// Sub-lists reached through [`ListRef::get`] are read-only [`ListView`]s; use
+/// [`ListRef::get_list_mut`] to modify one. This does not compile:
+///
+/// ```compile_fail
+/// use ring_lang_rs::{ListRef, ValueRef};
+///
+/// fn clear_first(list: &ListRef<'_>) {
+///     if let Some(ValueRef::List(mut sub)) = list.get(0) {
+///         sub.clear();
+///     }
+/// }
+/// ```
+pub struct ListRef<'a> {
+    list: RingList,
+    _marker: PhantomData<&'a mut ffi::List>,
+}
+
+/// A read-only view of a Ring list, returned for sub-lists by [`ListRef::get`].
+///
+/// It has the reading half of [`ListRef`] and none of its `push_*`, `insert_*`, `remove` or
+/// `clear` methods, so a shared borrow of a list can't be used to free its items.
+#[derive(Clone, Copy)]
+pub struct ListView<'a> {
+    list: RingList,
+    _marker: PhantomData<&'a ffi::List>,
+}
+
+/// A borrowed view of one list item, returned by [`ListRef::get`] and [`ListRef::iter`].
+#[derive(Debug, Clone, Copy)]
+pub enum ValueRef<'a> {
+    /// An item without a value.
+    Nothing,
+    /// A number stored as an integer.
+    Int(c_int),
+    /// A number stored as a double.
+    Double(f64),
+    /// A string, borrowed from the list.
+    String(&'a [u8]),
+    /// A read-only sub-list (this includes C pointer lists and objects).
+    List(ListView<'a>),
+    /// A raw pointer item.
+    Pointer(*mut c_void),
+    /// A C function pointer.
+    FuncPointer(extern "C" fn(*mut c_void)),
+}
+
+impl<'a> ListRef<'a> {
+    /// Borrow a raw list.
+    ///
+    /// # Safety
+    ///
+    /// `list` must be a valid list that stays alive, and is not modified elsewhere, for `'a`.
+    pub unsafe fn from_raw(list: RingList) -> ListRef<'a> {
+        ListRef {
+            list,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn as_ptr(&self) -> RingList {
+        self.list
+    }
+
+    /// A read-only view of the list, borrowed from `self`.
+    pub fn view(&self) -> ListView<'_> {
+        unsafe { ListView::from_raw(self.list) }
+    }
+
+    pub fn len(&self) -> usize {
+        ring_list_getsize(self.list) as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The Ring position of `index`, or `None` if it is out of range.
+    fn position(&self, index: usize) -> Option<c_uint> {
+        (index < self.len()).then(|| index as c_uint + 1)
+    }
+
+    /// The item at `index`, or `None` if `index >= len()`.
+    pub fn get(&self, index: usize) -> Option<ValueRef<'_>> {
+        self.view().get(index)
+    }
+
+    /// The sub-list at `index` for modification, or `None` if it is out of range or not a list.
+    pub fn get_list_mut(&mut self, index: usize) -> Option<ListRef<'_>> {
+        let pos = self.position(index)?;
+        if ring_list_islist(self.list, pos) {
+            unsafe { Some(ListRef::from_raw(ring_list_getlist(self.list, pos))) }
+        } else {
+            None
+        }
+    }
+
+    pub fn iter(&self) -> Iter<'_> {
+        self.view().iter()
+    }
+
+    /// Copy the whole list (see [`RingValue::from_list`]).
+    pub fn to_value(&self) -> RingValue {
+        RingValue::from_list(self.list)
+    }
+
+    pub fn push_nothing(&mut self) {
+        ring_list_newitem(self.list);
+    }
+
+    pub fn push_int(&mut self, n: c_int) {
+        ring_list_addint(self.list, n);
+    }
+
+    pub fn push_double(&mut self, n: f64) {
+        ring_list_adddouble(self.list, n);
+    }
+
+    pub fn push_str(&mut self, s: &str) {
+        ring_list_addstring2(self.list, s.as_bytes());
+    }
+
+    pub fn push_bytes(&mut self, s: &[u8]) {
+        ring_list_addstring2(self.list, s);
+    }
+
+    pub fn push_pointer(&mut self, ptr: *mut c_void) {
+        ring_list_addpointer(self.list, ptr);
+    }
+
+    /// Push a C pointer list (`ctype` is a null-terminated type name).
+    pub fn push_cpointer(&mut self, ptr: *mut c_void, ctype: &[u8]) {
+        ring_list_addcpointer(self.list, ptr, ctype);
+    }
+
+    /// Push a new, empty sub-list and return it.
+    pub fn push_list(&mut self) -> ListRef<'_> {
+        unsafe { ListRef::from_raw(ring_list_newlist(self.list)) }
+    }
+
+    /// Push a copy of `value` (see [`RingValue::add_to_list`]).
+    pub fn push_value(&mut self, value: &RingValue) {
+        value.add_to_list(self.list);
+    }
+
+    /// Ring position to insert after so the new item ends up at `index`.
+    fn insert_position(&self, index: usize) -> Option<c_uint> {
+        (index <= self.len()).then_some(index as c_uint)
+    }
+
+    /// Insert a number at `index`, shifting later items. Returns `false` if `index > len()`.
+    pub fn insert_int(&mut self, index: usize, n: c_int) -> bool {
+        self.insert_position(index)
+            .map(|pos| ring_list_insertint(self.list, pos, n))
+            .is_some()
+    }
+
+    /// Insert a number at `index`, shifting later items. Returns `false` if `index > len()`.
+    pub fn insert_double(&mut self, index: usize, n: f64) -> bool {
+        self.insert_position(index)
+            .map(|pos| ring_list_insertdouble(self.list, pos, n))
+            .is_some()
+    }
+
+    /// Insert a string at `index`, shifting later items. Returns `false` if `index > len()`.
+    pub fn insert_str(&mut self, index: usize, s: &str) -> bool {
+        self.insert_bytes(index, s.as_bytes())
+    }
+
+    /// Insert a string at `index`, shifting later items. Returns `false` if `index > len()`.
+    pub fn insert_bytes(&mut self, index: usize, s: &[u8]) -> bool {
+        self.insert_position(index)
+            .map(|pos| ring_list_insertstring2(self.list, pos, s))
+            .is_some()
+    }
+
+    /// Insert a raw pointer at `index`, shifting later items. Returns `false` if `index > len()`.
+    pub fn insert_pointer(&mut self, index: usize, ptr: *mut c_void) -> bool {
+        self.insert_position(index)
+            .map(|pos| ring_list_insertpointer(self.list, pos, ptr))
+            .is_some()
+    }
+
+    /// Insert a new, empty sub-list at `index` and return it, or `None` if `index > len()`.
+    pub fn insert_list(&mut self, index: usize) -> Option<ListRef<'_>> {
+        let pos = self.insert_position(index)?;
+        unsafe { Some(ListRef::from_raw(ring_list_insertlist(self.list, pos))) }
+    }
+
+    /// Remove the item at `index` and return a copy of it, or `None` if `index >= len()`.
+    pub fn remove(&mut self, index: usize) -> Option<RingValue> {
+        let pos = self.position(index)?;
+        let value = RingValue::from_list_item(self.list, pos);
+        ring_list_deleteitem(self.list, pos);
+        Some(value)
+    }
+
+    /// Remove all items.
+    pub fn clear(&mut self) {
+        ring_list_deleteallitems(self.list);
+    }
+}
+
+impl fmt::Debug for ListRef<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<'a> IntoIterator for &'a ListRef<'_> {
+    type Item = ValueRef<'a>;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+impl<'a> ListView<'a> {
+    /// View a raw list.
+    ///
+    /// # Safety
+    ///
+    /// `list` must be a valid list that stays alive, and is not modified, for `'a`.
+    pub unsafe fn from_raw(list: RingList) -> ListView<'a> {
+        ListView {
+            list,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn as_ptr(&self) -> RingList {
+        self.list
+    }
+
+    pub fn len(&self) -> usize {
+        ring_list_getsize(self.list) as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The item at `index`, or `None` if `index >= len()`.
+    pub fn get(&self, index: usize) -> Option<ValueRef<'a>> {
+        if index >= self.len() {
+            return None;
+        }
+        unsafe {
+            Some(ValueRef::from_item(ring_list_getitem(
+                self.list,
+                index as c_uint + 1,
+            )))
+        }
+    }
+
+    pub fn iter(&self) -> Iter<'a> {
+        Iter {
+            list: *self,
+            index: 0,
+            len: self.len(),
+        }
+    }
+
+    /// Copy the whole list (see [`RingValue::from_list`]).
+    pub fn to_value(&self) -> RingValue {
+        RingValue::from_list(self.list)
+    }
+}
+
+impl fmt::Debug for ListView<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<'a> IntoIterator for ListView<'a> {
+    type Item = ValueRef<'a>;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+impl<'a> ValueRef<'a> {
+    /// View a Ring item.
+    ///
+    /// # Safety
+    ///
+    /// `item` must be null or point to a valid item that outlives `'a`.
+    pub unsafe fn from_item(item: *mut ffi::Item) -> ValueRef<'a> {
+        if item.is_null() {
+            return ValueRef::Nothing;
+        }
+        unsafe {
+            match (*item).nType() {
+                ffi::ITEMTYPE_STRING => {
+                    let string = (*item).data.pString;
+                    if string.is_null() || (*string).cStr.is_null() {
+                        ValueRef::String(&[])
+                    } else {
+                        ValueRef::String(std::slice::from_raw_parts(
+                            (*string).cStr as *const u8,
+                            (*string).nSize as usize,
+                        ))
+                    }
+                }
+                ffi::ITEMTYPE_NUMBER => match (*item).nNumberFlag() {
+                    ffi::ITEM_NUMBERFLAG_INT => ValueRef::Int((*item).data.iNumber),
+                    ffi::ITEM_NUMBERFLAG_DOUBLE => ValueRef::Double((*item).data.dNumber),
+                    _ => ValueRef::Double(ffi::ring_item_getnumber(item)),
+                },
+                ffi::ITEMTYPE_LIST => ValueRef::List(ListView::from_raw((*item).data.pList)),
+                ffi::ITEMTYPE_POINTER => ValueRef::Pointer((*item).data.pPointer),
+                ffi::ITEMTYPE_FUNCPOINTER => match (*item).data.pFunc {
+                    Some(func) => ValueRef::FuncPointer(func),
+                    None => ValueRef::Nothing,
+                },
+                _ => ValueRef::Nothing,
+            }
+        }
+    }
+
+    /// The value as a number, if it is one.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            ValueRef::Int(n) => Some(*n as f64),
+            ValueRef::Double(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// The value as a string, if it is a valid UTF-8 string.
+    pub fn as_str(&self) -> Option<&'a str> {
+        match self {
+            ValueRef::String(bytes) => std::str::from_utf8(bytes).ok(),
+            _ => None,
+        }
+    }
+
+    /// The raw bytes of a string value.
+    pub fn as_bytes(&self) -> Option<&'a [u8]> {
+        match self {
+            ValueRef::String(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    /// The value as a read-only sub-list.
+    pub fn as_list(&self) -> Option<ListView<'a>> {
+        match self {
+            ValueRef::List(list) => Some(*list),
+            _ => None,
+        }
+    }
+
+    /// Copy the value.
+    pub fn to_value(&self) -> RingValue {
+        match self {
+            ValueRef::Nothing => RingValue::Nothing,
+            ValueRef::Int(n) => RingValue::Int(*n),
+            ValueRef::Double(n) => RingValue::Double(*n),
+            ValueRef::String(bytes) => RingValue::String(bytes.to_vec()),
+            ValueRef::List(list) => list.to_value(),
+            ValueRef::Pointer(ptr) => RingValue::CPointer {
+                type_name: String::new(),
+                ptr: *ptr,
+                free_func: None,
+            },
+            ValueRef::FuncPointer(func) => RingValue::FuncPointer(*func),
+        }
+    }
+}
+
+/// Iterator over the items of a [`ListRef`] or [`ListView`].
+pub struct Iter<'a> {
+    list: ListView<'a>,
+    index: usize,
+    len: usize,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = ValueRef<'a>;
+
+    fn next(&mut self) -> Option<ValueRef<'a>> {
+        if self.index >= self.len {
+            return None;
+        }
+        self.index += 1;
+        unsafe {
+            Some(ValueRef::from_item(ring_list_getitem(
+                self.list.list,
+                self.index as c_uint,
+            )))
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.len - self.index;
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for Iter<'_> {}
+
+/// An owned Ring list, deleted with `ring_list_delete` when dropped.
+///
+/// Derefs to [`ListRef`] for reading and modifying.
+///
+/// ```rust,ignore
+/// let mut list = OwnedList::new();
+/// list.push_int(1);
+/// list.push_str("two");
+/// assert_eq!(list.get(1).and_then(|v| v.as_str()), Some("two"));
+/// assert!(list.get(2).is_none());
+/// ```
+pub struct OwnedList {
+    list: ListRef<'static>,
+}
+
+impl OwnedList {
+    pub fn new() -> OwnedList {
+        unsafe { OwnedList::from_raw(ring_list_new(0)) }
+    }
+
+    /// Take ownership of a raw list.
+    ///
+    /// # Safety
+    ///
+    /// `list` must be a valid list that nothing else deletes or uses after this call.
+    pub unsafe fn from_raw(list: RingList) -> OwnedList {
+        OwnedList {
+            list: unsafe { ListRef::from_raw(list) },
+        }
+    }
+
+    /// Give up ownership; the caller must delete the returned list.
+    pub fn into_raw(self) -> RingList {
+        let list = self.list.list;
+        std::mem::forget(self);
+        list
+    }
+
+    /// Build a list from a [`RingValue`] (see [`RingValue::to_list`]).
+    pub fn from_value(value: &RingValue) -> OwnedList {
+        unsafe { OwnedList::from_raw(value.to_list()) }
+    }
+}
+
+impl Default for OwnedList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for OwnedList {
+    fn drop(&mut self) {
+        ring_list_delete(self.list.list);
+    }
+}
+
+impl Deref for OwnedList {
+    type Target = ListRef<'static>;
+
+    fn deref(&self) -> &ListRef<'static> {
+        &self.list
+    }
+}
+
+impl DerefMut for OwnedList {
+    fn deref_mut(&mut self) -> &mut ListRef<'static> {
+        &mut self.list
+    }
+}
+
+impl fmt::Debug for OwnedList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.list.fmt(f)
+    }
+}
+
+impl<'a> IntoIterator for &'a OwnedList {
+    type Item = ValueRef<'a>;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.list.iter()
+    }
+}
+
+/// Consumes the list, yielding owned copies of its items; the list is deleted afterwards.
+impl IntoIterator for OwnedList {
+    type Item = RingValue;
+    type IntoIter = IntoIter;
+
+    fn into_iter(self) -> IntoIter {
+        IntoIter {
+            len: self.len(),
+            list: self,
+            index: 0,
+        }
+    }
+}
+
+/// Owning iterator over an [`OwnedList`].
+pub struct IntoIter {
+    list: OwnedList,
+    index: usize,
+    len: usize,
+}
+
+impl Iterator for IntoIter {
+    type Item = RingValue;
+
+    fn next(&mut self) -> Option<RingValue> {
+        if self.index >= self.len {
+            return None;
+        }
+        self.index += 1;
+        Some(RingValue::from_list_item(
+            self.list.as_ptr(),
+            self.index as c_uint,
+        ))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.len - self.index;
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for IntoIter {}
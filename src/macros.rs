@@ -1,17 +1,32 @@
 #[macro_export]
 macro_rules! ring_func {
+    ($name:ident, |$call:ident : RingCall $(<$lt:lifetime>)?| $body:expr) => {
+        #[unsafe(no_mangle)]
+        pub extern "C" fn $name(p: *mut ::std::ffi::c_void) {
+            $crate::ring_api_catch_panic(p, |p| $crate::RingCall::scope(p, |$call| $body))
+        }
+    };
     ($name:ident, $body:expr) => {
         #[unsafe(no_mangle)]
         pub extern "C" fn $name(p: *mut ::std::ffi::c_void) {
-            $body(p)
+            $crate::ring_api_catch_panic(p, $body)
         }
     };
 }
 
 #[macro_export]
 macro_rules! ring_libinit {
+    (@register $state:ident, $name:literal, $func:ident) => {{
+        const {
+            assert!(
+                $crate::ring_is_valid_function_name($name),
+                concat!("invalid Ring function name: ", $name)
+            )
+        };
+        $crate::ring_libinit_register($state, $name, $func);
+    }};
     (@cfg $state:ident [ ] { $($name:literal => $func:ident),* $(,)? }) => {
-        $( $crate::ring_register_function_str($state, concat!($name, "\0"), $func); )*
+        $( $crate::ring_libinit!(@register $state, $name, $func); )*
     };
     (@cfg $state:ident [ #[$attr:meta] $($rest:tt)* ] { $($body:tt)* }) => {
         #[$attr]
@@ -23,13 +38,13 @@ macro_rules! ring_libinit {
         $( $crate::ring_libinit!(@munch $state $($rest)*); )?
     };
     (@munch $state:ident $name:literal => $func:ident $(, $($rest:tt)*)?) => {
-        $crate::ring_register_function_str($state, concat!($name, "\0"), $func);
+        $crate::ring_libinit!(@register $state, $name, $func);
         $( $crate::ring_libinit!(@munch $state $($rest)*); )?
     };
     ($($name:literal => $func:ident),* $(,)?) => {
         #[unsafe(no_mangle)]
         pub extern "C" fn ringlib_init(state: $crate::RingState) {
-            $( $crate::ring_register_function_str(state, concat!($name, "\0"), $func); )*
+            $( $crate::ring_libinit!(@register state, $name, $func); )*
         }
     };
     ($($tt:tt)*) => {
@@ -59,8 +74,17 @@ macro_rules! ring_check_paracount {
 #[macro_export]
 macro_rules! ring_check_paracount_range {
     ($p:expr, $min:expr, $max:expr) => {
-        let count = $crate::ring_api_paracount($p);
-        if count < $min || count > $max {
+        if !($min..=$max).contains(&$crate::ring_api_paracount($p)) {
+            $crate::ring_api_error($p, $crate::RING_API_BADPARACOUNT);
+            return;
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! ring_check_paracount_min {
+    ($p:expr, $min:expr) => {
+        if $crate::ring_api_paracount($p) < $min {
             $crate::ring_api_error($p, $crate::RING_API_BADPARACOUNT);
             return;
         }
@@ -120,7 +144,25 @@ macro_rules! ring_check_list {
 #[macro_export]
 macro_rules! ring_get_string {
     ($p:expr, $n:expr) => {
-        $crate::ring_api_getstring_str($p, $n)
+        // The macro is only for use in the callback that owns `$p`
+        match unsafe { $crate::ring_api_getstring_str(&$p, $n) } {
+            Ok(value) => value,
+            Err(e) => {
+                let error = $crate::ArgError::Invalid {
+                    index: $n,
+                    message: e.to_string(),
+                };
+                $crate::ring_api_error_str($p, &error.to_string());
+                return;
+            }
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! ring_get_bytes {
+    ($p:expr, $n:expr) => {
+        unsafe { $crate::ring_api_getstring_bytes(&$p, $n) }
     };
 }
 
@@ -164,6 +206,20 @@ macro_rules! ring_get_list {
     };
 }
 
+#[macro_export]
+macro_rules! ring_get_value {
+    ($p:expr, $n:expr) => {
+        match $crate::FromRing::from_ring($crate::ring_api_getvalue($p, $n)) {
+            Ok(value) => value,
+            Err(e) => {
+                let message = $crate::ArgError::from_ring_error($n, e).to_string();
+                $crate::ring_api_error_str($p, &message.replace('\0', ""));
+                return;
+            }
+        }
+    };
+}
+
 #[macro_export]
 macro_rules! ring_ret_number {
     ($p:expr, $n:expr) => {
@@ -178,6 +234,25 @@ macro_rules! ring_ret_string {
     };
 }
 
+/// Implement [`RingType`](crate::RingType) for a type, so it can be passed to Ring with
+/// `ring_api_retobject`/`RingCall::ret_object`.
+///
+/// ```rust,ignore
+/// ring_type!(Point);            // TYPE_NAME = "Point"
+/// ring_type!(Grid, "GridMap");  // TYPE_NAME = "GridMap"
+/// ```
+#[macro_export]
+macro_rules! ring_type {
+    ($type:ty) => {
+        $crate::ring_type!($type, stringify!($type));
+    };
+    ($type:ty, $name:expr) => {
+        impl $crate::RingType for $type {
+            const TYPE_NAME: &'static str = $name;
+        }
+    };
+}
+
 #[macro_export]
 macro_rules! ring_ret_cpointer {
     ($p:expr, $ptr:expr, $ctype:expr) => {
@@ -204,6 +279,13 @@ macro_rules! ring_ret_list {
     };
 }
 
+#[macro_export]
+macro_rules! ring_ret_value {
+    ($p:expr, $value:expr) => {
+        $crate::ring_api_retvalue($p, &$crate::IntoRing::into_ring($value))
+    };
+}
+
 #[macro_export]
 macro_rules! ring_new_list {
     ($p:expr) => {
@@ -0,0 +1,207 @@
+use crate::ffi_types::{CStr, c_int, c_void};
+
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::mem::size_of;
+
+use crate::ffi::{self, ITEMTYPE_LIST, ITEMTYPE_STRING};
+use crate::{
+    RingFunc, RingList, RingVM, ring_api_getnumber, ring_api_getstringsize, ring_api_isnumber,
+    ring_api_isstring, ring_api_paracount, ring_api_scopescount, ring_list_getitem,
+};
+
+/// Bytes Ring allocates for each list item besides the [`ffi::Item`] itself (the `Items` node).
+const ITEM_NODE_SIZE: usize = 3 * size_of::<*mut c_void>();
+
+/// Estimate the memory held by the data of a VM: its variables, the lists and strings they
+/// hold, and temporary values.
+///
+/// This walks the VM's scopes, so it takes time proportional to the amount of data. Bytecode,
+/// class definitions and allocator overhead are not counted.
+pub fn ring_vm_memoryusage(vm: RingVM) -> usize {
+    memory_walk(vm).0
+}
+
+/// [`ring_vm_memoryusage`] and the number of lists and items walked to find it, which is what
+/// measuring costs.
+pub(crate) fn memory_walk(vm: RingVM) -> (usize, usize) {
+    let mut visited = HashSet::new();
+    unsafe {
+        let scopes =
+            (ring_api_scopescount(vm as *mut c_void).max(0) as usize).min(ffi::RING_VM_STACK_SIZE);
+        let mut total: usize = (&mut (*vm).aScopes)[..scopes]
+            .iter_mut()
+            .map(|scope| list_size(scope, &mut visited))
+            .sum();
+        let roots = [
+            (*vm).pActiveMem,
+            (*vm).pGlobalScopes,
+            (*vm).pTempMem,
+            (*vm).pDeleteLater,
+        ];
+        for list in roots {
+            total += list_size(list, &mut visited);
+        }
+        let frames = ((*vm).nCurrentFuncCall as usize).min(ffi::RING_VM_STACK_SIZE);
+        for call in &(&(*vm).aFuncCall)[..frames] {
+            total += list_size(call.pTempMem, &mut visited);
+        }
+        let walked = visited.iter().map(|&list| 1 + (*list).nSize as usize).sum();
+        (total, walked)
+    }
+}
+
+/// The size of `list` and everything it holds, skipping lists already counted.
+unsafe fn list_size(list: RingList, visited: &mut HashSet<RingList>) -> usize {
+    if list.is_null() || !visited.insert(list) {
+        return 0;
+    }
+    let mut total = size_of::<ffi::List>();
+    unsafe {
+        for index in 1..=(*list).nSize {
+            let item = ring_list_getitem(list, index);
+            total += size_of::<ffi::Item>() + ITEM_NODE_SIZE;
+            match (*item).nType() {
+                ITEMTYPE_STRING if !(*item).data.pString.is_null() => {
+                    let string = &*(*item).data.pString;
+                    total += size_of::<ffi::String>();
+                    if !std::ptr::eq(string.cStr, string.cStrArray.as_ptr()) {
+                        total += string.nCapacity as usize;
+                    }
+                }
+                ITEMTYPE_LIST => total += list_size((*item).data.pList, visited),
+                _ => {}
+            }
+        }
+    }
+    total
+}
+
+/// Decides whether the running script may allocate this many more bytes. It raises the Ring
+/// error itself when it returns `false`.
+pub(crate) type AllocationCheck = fn(*mut c_void, f64) -> bool;
+
+/// The standard functions checked before they allocate, since one call can ask for more memory
+/// than the machine has, and the wrappers that replace them.
+const GUARDED: [(&[u8], RingFunc); 4] = [
+    (b"list", ring_rs_list),
+    (b"space", ring_rs_space),
+    (b"copy", ring_rs_copy),
+    (b"read", ring_rs_read),
+];
+
+/// The allocation check of one state and the implementations replaced by the wrappers in
+/// [`GUARDED`], in the same order.
+struct Guard {
+    check: AllocationCheck,
+    originals: [Option<RingFunc>; GUARDED.len()],
+}
+
+thread_local! {
+    /// The guard of each state, keyed by VM like the closures of
+    /// [`Ring::register_fn`](crate::Ring::register_fn).
+    static GUARDS: RefCell<HashMap<RingVM, Guard>> = RefCell::new(HashMap::new());
+}
+
+/// Check the size requested by the functions in [`GUARDED`] with `check` before the
+/// allocation happens.
+pub(crate) fn guard_allocations(vm: RingVM, check: AllocationCheck) {
+    GUARDS.with(|guards| {
+        let mut guards = guards.borrow_mut();
+        let guard = guards.entry(vm).or_insert(Guard {
+            check,
+            originals: [None; GUARDED.len()],
+        });
+        guard.check = check;
+        unsafe {
+            let mut next = (*vm).pCFunction;
+            while !next.is_null() {
+                let f = &mut *next;
+                next = f.pNext;
+                let (Some(func), false) = (f.pFunc, f.cName.is_null()) else {
+                    continue;
+                };
+                let name = CStr::from_ptr(f.cName).to_bytes();
+                for ((guarded, wrapper), original) in GUARDED.iter().zip(&mut guard.originals) {
+                    // A state already guarded keeps its originals; one at the address of a
+                    // deleted state replaces them
+                    if name == *guarded && !std::ptr::fn_addr_eq(func, *wrapper) {
+                        *original = Some(func);
+                        f.pFunc = Some(*wrapper);
+                    }
+                }
+            }
+        }
+    });
+}
+
+/// Forget the guard of a deleted state.
+pub(crate) fn release_guard(vm: RingVM) {
+    GUARDS.with(|guards| guards.borrow_mut().remove(&vm));
+}
+
+/// `list(nRows)` or `list(nRows, nColumns)`.
+extern "C" fn ring_rs_list(p: *mut c_void) {
+    let item = (size_of::<ffi::Item>() + ITEM_NODE_SIZE) as f64;
+    let rows = number_arg(p, 1);
+    let requested = if ring_api_paracount(p) >= 2 {
+        rows * (size_of::<ffi::List>() as f64 + number_arg(p, 2) * item)
+    } else {
+        rows * item
+    };
+    guarded_call(p, requested, 0);
+}
+
+/// `space(nBytes)`.
+extern "C" fn ring_rs_space(p: *mut c_void) {
+    guarded_call(p, number_arg(p, 1), 1);
+}
+
+/// `copy(cString, nCount)`.
+extern "C" fn ring_rs_copy(p: *mut c_void) {
+    let size = if ring_api_isstring(p, 1) {
+        ring_api_getstringsize(p, 1) as f64
+    } else {
+        0.0
+    };
+    guarded_call(p, size * number_arg(p, 2), 2);
+}
+
+/// `read(cFileName)`, which loads the whole file.
+extern "C" fn ring_rs_read(p: *mut c_void) {
+    let size = if ring_api_isstring(p, 1) {
+        let path = String::from_utf8_lossy(&crate::ring_api_getstring_vec(p, 1)).into_owned();
+        std::fs::metadata(path).map_or(0.0, |meta| meta.len() as f64)
+    } else {
+        0.0
+    };
+    guarded_call(p, size, 3);
+}
+
+/// Numeric parameter `n`, or 0 if it is missing or not a number. Ring reports bad parameters
+/// itself once the original function runs.
+fn number_arg(p: *mut c_void, n: c_int) -> f64 {
+    if ring_api_paracount(p) >= n && ring_api_isnumber(p, n) {
+        ring_api_getnumber(p, n).max(0.0)
+    } else {
+        0.0
+    }
+}
+
+fn guarded_call(p: *mut c_void, requested: f64, guard: usize) {
+    let guard = GUARDS.with(|guards| {
+        guards
+            .borrow()
+            .get(&(p as RingVM))
+            .map(|g| (g.check, g.originals[guard]))
+    });
+    let Some((check, original)) = guard else {
+        return;
+    };
+    if !check(p, requested) {
+        return;
+    }
+    if let Some(original) = original {
+        original(p);
+    }
+}
@@ -0,0 +1,147 @@
+use crate::ffi_types::{CStr, CString, c_int, c_void};
+
+use std::any::TypeId;
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+use crate::{
+    ArgError, RING_CPOINTER_POINTER, RING_CPOINTER_TYPE, ring_api_getlist, ring_api_getvalue,
+    ring_api_iscpointer, ring_api_retcpointer2, ring_list_getpointer, ring_list_getstring,
+};
+
+/// A Rust type that Ring code can hold as a C pointer.
+///
+/// Implement it with [`ring_type!`](crate::ring_type), then pass values with
+/// [`ring_api_retobject`] and read them back with [`ring_api_getobject`] (or
+/// [`RingCall::ret_object`](crate::RingCall::ret_object) and
+/// [`RingCall::get_object`](crate::RingCall::get_object)).
+pub trait RingType: 'static {
+    /// A readable name for the type, such as `"Point"`. It does not have to be unique: the pointer
+    /// type Ring stores is made unique by [`ring_type_name`].
+    const TYPE_NAME: &'static str;
+}
+
+/// Pointer type names handed out so far, by Rust type.
+#[derive(Default)]
+pub(crate) struct TypeNames {
+    by_type: HashMap<TypeId, &'static CStr>,
+    taken: HashSet<&'static CStr>,
+}
+
+static TYPE_NAMES: Mutex<Option<TypeNames>> = Mutex::new(None);
+
+/// The pointer type name Ring stores for `T`'s objects.
+///
+/// It is [`RingType::TYPE_NAME`] qualified with the Rust type's path and this library's copy of
+/// the name table, such as `"Point@geometry::Point@7f3a2c41d0"`. Types from different crates
+/// never share a name, and neither do types with the same path compiled into two extension
+/// libraries loaded into one process. Two distinct types with the same path within one library,
+/// such as two versions of one crate, get a numbered suffix.
+pub fn ring_type_name<T: RingType>() -> &'static CStr {
+    registry_type_name::<T>(&TYPE_NAMES)
+}
+
+/// [`ring_type_name`] as handed out by `registry`, whose address tells libraries apart.
+pub(crate) fn registry_type_name<T: RingType>(
+    registry: &'static Mutex<Option<TypeNames>>,
+) -> &'static CStr {
+    let mut names = registry.lock().unwrap_or_else(|e| e.into_inner());
+    let names = names.get_or_insert_with(TypeNames::default);
+    if let Some(&name) = names.by_type.get(&TypeId::of::<T>()) {
+        return name;
+    }
+    let base = format!(
+        "{}@{}@{:x}",
+        T::TYPE_NAME,
+        std::any::type_name::<T>(),
+        registry as *const _ as usize
+    )
+    .replace('\0', "");
+    let mut candidate = base.clone();
+    let mut suffix = 1;
+    let name = loop {
+        let cname = CString::new(candidate).unwrap();
+        if !names.taken.contains(cname.as_c_str()) {
+            break &*Box::leak(cname.into_boxed_c_str());
+        }
+        suffix += 1;
+        candidate = format!("{}#{}", base, suffix);
+    };
+    names.taken.insert(name);
+    names.by_type.insert(TypeId::of::<T>(), name);
+    name
+}
+
+/// Return `value` to Ring as a managed C pointer. Ring's garbage collector drops it once no
+/// Ring variable refers to it.
+pub fn ring_api_retobject<T: RingType>(p: *mut c_void, value: Box<T>) {
+    ring_api_retcpointer2(
+        p,
+        Box::into_raw(value) as *mut c_void,
+        ring_type_name::<T>().to_bytes_with_nul(),
+        Some(ring_free_object::<T>),
+    );
+}
+
+extern "C" fn ring_free_object<T: RingType>(_state: *mut c_void, ptr: *mut c_void) {
+    if !ptr.is_null() {
+        unsafe { drop(Box::from_raw(ptr as *mut T)) }
+    }
+}
+
+/// Read parameter `n` as an object created by [`ring_api_retobject`].
+///
+/// The pointer's type name must be exactly `T`'s (see [`ring_type_name`]), so a pointer to any
+/// other type is an error rather than a bad cast. The object is shared with Ring, so use
+/// `Cell`/`RefCell` fields for data that functions change.
+///
+/// Prefer [`RingCall::get_object`](crate::RingCall::get_object), which ties the reference to
+/// the callback safely.
+///
+/// # Safety
+///
+/// The object is owned by Ring, which frees it once no variable refers to it. `p` must be the
+/// callback's own argument, and the result must not be used after the callback returns; the
+/// borrow of `p` alone does not guarantee this, since `p` could be a longer-lived copy.
+pub unsafe fn ring_api_getobject<T: RingType>(p: &*mut c_void, n: c_int) -> Result<&T, ArgError> {
+    unsafe { get_object(*p, n) }
+}
+
+/// [`ring_api_getobject`] with a lifetime chosen by the caller, which must not outlive the call.
+pub(crate) unsafe fn get_object<'a, T: RingType>(
+    p: *mut c_void,
+    n: c_int,
+) -> Result<&'a T, ArgError> {
+    if !ring_api_iscpointer(p, n) {
+        return Err(ArgError::TypeMismatch {
+            index: n,
+            expected: T::TYPE_NAME,
+            found: ring_api_getvalue(p, n).type_name(),
+        });
+    }
+    let list = ring_api_getlist(p, n);
+    let found = ring_list_getstring(list, RING_CPOINTER_TYPE);
+    let found = if found.is_null() {
+        Default::default()
+    } else {
+        unsafe { CStr::from_ptr(found) }
+    };
+    if found != ring_type_name::<T>() {
+        return Err(ArgError::Invalid {
+            index: n,
+            message: format!(
+                "expected a {} pointer, found {}",
+                T::TYPE_NAME,
+                found.to_string_lossy()
+            ),
+        });
+    }
+    let ptr = ring_list_getpointer(list, RING_CPOINTER_POINTER) as *const T;
+    if ptr.is_null() {
+        return Err(ArgError::Invalid {
+            index: n,
+            message: format!("the {} object was released", T::TYPE_NAME),
+        });
+    }
+    Ok(unsafe { &*ptr })
+}
@@ -0,0 +1,128 @@
+use crate::ffi_types::{c_int, c_void, size_t};
+
+use std::cell::Cell;
+use std::io::Write;
+use std::sync::Mutex;
+
+/// Serializes captures: stdout and stderr belong to the whole process.
+static CAPTURE_LOCK: Mutex<()> = Mutex::new(());
+
+thread_local! {
+    static CAPTURING: Cell<bool> = const { Cell::new(false) };
+}
+
+unsafe extern "C" {
+    fn tmpfile() -> *mut c_void;
+    fn fclose(stream: *mut c_void) -> c_int;
+    fn fflush(stream: *mut c_void) -> c_int;
+    fn fread(ptr: *mut c_void, size: size_t, count: size_t, stream: *mut c_void) -> size_t;
+    fn rewind(stream: *mut c_void);
+    #[cfg_attr(windows, link_name = "_fileno")]
+    fn fileno(stream: *mut c_void) -> c_int;
+    #[cfg_attr(windows, link_name = "_dup")]
+    fn dup(fd: c_int) -> c_int;
+    #[cfg_attr(windows, link_name = "_dup2")]
+    fn dup2(fd: c_int, fd2: c_int) -> c_int;
+    #[cfg_attr(windows, link_name = "_close")]
+    fn close(fd: c_int) -> c_int;
+}
+
+/// Run `f` with the process's stdout and stderr redirected, and return what was written to them.
+///
+/// This captures everything Ring prints (`?`, `see`, `print()`, error messages) as well as
+/// output from C and Rust code called by the script. Because the file descriptors are shared by
+/// the whole process, concurrent captures wait for each other, and output from other threads
+/// during a capture ends up in it. A capture started inside another one on the same thread runs
+/// `f` directly, leaving its output to the outer capture. stdout is buffered and stderr is not,
+/// so text written to both may not keep its relative order.
+///
+/// To capture what one [`Ring`](crate::Ring) prints without touching the process's descriptors,
+/// use [`Ring::capture`](crate::Ring::capture).
+///
+/// ```rust,ignore
+/// let state = ring_state_init();
+/// let ((), output) = ring_capture_output(|| ring_state_runcode_str(state, r#"? "hi""#));
+/// assert_eq!(output, b"hi\n");
+/// ```
+pub fn ring_capture_output<R>(f: impl FnOnce() -> R) -> (R, Vec<u8>) {
+    if CAPTURING.with(|c| c.get()) {
+        return (f(), Vec::new());
+    }
+    let _lock = CAPTURE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(redirect) = Redirect::start() else {
+        return (f(), Vec::new());
+    };
+    CAPTURING.with(|c| c.set(true));
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(f));
+    CAPTURING.with(|c| c.set(false));
+    let output = redirect.finish();
+    match result {
+        Ok(value) => (value, output),
+        Err(payload) => std::panic::resume_unwind(payload),
+    }
+}
+
+/// stdout and stderr pointed at a temporary file, with the original descriptors saved.
+struct Redirect {
+    file: *mut c_void,
+    saved: [c_int; 2],
+}
+
+impl Redirect {
+    fn start() -> Option<Redirect> {
+        flush_all();
+        unsafe {
+            let file = tmpfile();
+            if file.is_null() {
+                return None;
+            }
+            let fd = fileno(file);
+            let saved = [dup(1), dup(2)];
+            if saved.contains(&-1) || dup2(fd, 1) == -1 || dup2(fd, 2) == -1 {
+                Redirect { file, saved }.restore();
+                fclose(file);
+                return None;
+            }
+            Some(Redirect { file, saved })
+        }
+    }
+
+    fn finish(self) -> Vec<u8> {
+        flush_all();
+        self.restore();
+        let mut output = Vec::new();
+        let mut buf = [0u8; 8192];
+        unsafe {
+            rewind(self.file);
+            loop {
+                let n = fread(buf.as_mut_ptr() as *mut c_void, 1, buf.len(), self.file);
+                if n == 0 {
+                    break;
+                }
+                output.extend_from_slice(&buf[..n]);
+            }
+            fclose(self.file);
+        }
+        output
+    }
+
+    fn restore(&self) {
+        for (fd, &saved) in self.saved.iter().enumerate() {
+            if saved != -1 {
+                unsafe {
+                    dup2(saved, fd as c_int);
+                    close(saved);
+                }
+            }
+        }
+    }
+}
+
+/// Flush Rust's and C's buffered output so it lands on the right side of a redirect.
+fn flush_all() {
+    let _ = std::io::stdout().flush();
+    let _ = std::io::stderr().flush();
+    unsafe {
+        fflush(std::ptr::null_mut());
+    }
+}
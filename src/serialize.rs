@@ -0,0 +1,792 @@
+//! serde support (enabled with the `serde` feature).
+//!
+//! Values are converted through [`RingValue`] using the same layout as [`IntoRing`](crate::IntoRing):
+//! structs and maps become lists of `[key, value]` pairs, sequences and tuples become lists and
+//! `None` becomes an empty string. Enum variants become their name, or a list
+//! `[variant, payload...]` when they carry data, with a struct variant's fields in declaration
+//! order.
+//!
+//! Two differences remain from `#[derive(IntoRing)]`, and each side reads the other's form:
+//!
+//! - Variant names are serde's (the Rust name unless renamed), where the derive writes them in
+//!   snake case. Names are read ignoring case and underscores, so `dark_red` reads as `DarkRed`.
+//! - Unit variants are written by name, where the derive writes an enum without data as its
+//!   discriminant. A number is read as the variant's index, which is the same unless the enum
+//!   sets its discriminants explicitly.
+
+use std::fmt;
+
+use serde::de::{self, DeserializeOwned, Visitor};
+use serde::ser::{self, Serialize};
+
+use crate::ffi_types::c_int;
+use crate::{RingError, RingList, RingValue};
+
+impl ser::Error for RingError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        RingError::Conversion(msg.to_string())
+    }
+}
+
+impl de::Error for RingError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        RingError::Conversion(msg.to_string())
+    }
+}
+
+/// Serialize a value into a [`RingValue`].
+pub fn to_ring_value<T: Serialize + ?Sized>(value: &T) -> Result<RingValue, RingError> {
+    value.serialize(ValueSerializer)
+}
+
+/// Deserialize a value from a [`RingValue`].
+pub fn from_ring_value<T: DeserializeOwned>(value: RingValue) -> Result<T, RingError> {
+    T::deserialize(ValueDeserializer(value))
+}
+
+/// Serialize a value into a new Ring list. The caller owns the list and must delete it.
+///
+/// A value that does not serialize to a list (a number, a string, ...) becomes a one-item list.
+///
+/// ```rust,ignore
+/// #[derive(Serialize)]
+/// struct Point { x: f64, y: f64 }
+///
+/// let list = to_ring_list(&Point { x: 1.0, y: 2.0 })?;   // [[:x, 1], [:y, 2]]
+/// ring_api_retlist(p, list);   // Ring copies the list
+/// ring_list_delete(list);
+/// ```
+pub fn to_ring_list<T: Serialize + ?Sized>(value: &T) -> Result<RingList, RingError> {
+    to_ring_value(value).map(|value| value.to_list())
+}
+
+/// Deserialize a value from a Ring list (or a Ring object).
+///
+/// ```rust,ignore
+/// let point: Point = from_ring_list(ring_api_getlist(p, 1))?;
+/// ```
+pub fn from_ring_list<T: DeserializeOwned>(list: RingList) -> Result<T, RingError> {
+    from_ring_value(RingValue::from_list(list))
+}
+
+fn pair(key: RingValue, value: RingValue) -> RingValue {
+    RingValue::List(vec![key, value])
+}
+
+fn variant(name: &str, payload: Vec<RingValue>) -> RingValue {
+    let mut items = Vec::with_capacity(payload.len() + 1);
+    items.push(name.into());
+    items.extend(payload);
+    RingValue::List(items)
+}
+
+// Serialization
+
+struct ValueSerializer;
+
+impl ser::Serializer for ValueSerializer {
+    type Ok = RingValue;
+    type Error = RingError;
+
+    type SerializeSeq = SeqSerializer;
+    type SerializeTuple = SeqSerializer;
+    type SerializeTupleStruct = SeqSerializer;
+    type SerializeTupleVariant = SeqSerializer;
+    type SerializeMap = MapSerializer;
+    type SerializeStruct = MapSerializer;
+    type SerializeStructVariant = SeqSerializer;
+
+    fn serialize_bool(self, v: bool) -> Result<RingValue, RingError> {
+        Ok(RingValue::Int(v as c_int))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<RingValue, RingError> {
+        Ok(RingValue::Int(v as c_int))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<RingValue, RingError> {
+        Ok(RingValue::Int(v as c_int))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<RingValue, RingError> {
+        Ok(RingValue::Int(v as c_int))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<RingValue, RingError> {
+        Ok(RingValue::Double(v as f64))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<RingValue, RingError> {
+        Ok(RingValue::Int(v as c_int))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<RingValue, RingError> {
+        Ok(RingValue::Int(v as c_int))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<RingValue, RingError> {
+        Ok(RingValue::Double(v as f64))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<RingValue, RingError> {
+        Ok(RingValue::Double(v as f64))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<RingValue, RingError> {
+        Ok(RingValue::Double(v as f64))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<RingValue, RingError> {
+        Ok(RingValue::Double(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<RingValue, RingError> {
+        Ok(RingValue::from(v.to_string()))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<RingValue, RingError> {
+        Ok(RingValue::from(v))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<RingValue, RingError> {
+        Ok(RingValue::from(v))
+    }
+
+    fn serialize_none(self) -> Result<RingValue, RingError> {
+        Ok(RingValue::String(Vec::new()))
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<RingValue, RingError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<RingValue, RingError> {
+        Ok(RingValue::Nothing)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<RingValue, RingError> {
+        Ok(RingValue::Nothing)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<RingValue, RingError> {
+        Ok(RingValue::from(variant))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<RingValue, RingError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant_name: &'static str,
+        value: &T,
+    ) -> Result<RingValue, RingError> {
+        Ok(variant(variant_name, vec![value.serialize(self)?]))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SeqSerializer, RingError> {
+        Ok(SeqSerializer {
+            variant: None,
+            items: Vec::with_capacity(len.unwrap_or(0)),
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SeqSerializer, RingError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SeqSerializer, RingError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SeqSerializer, RingError> {
+        Ok(SeqSerializer {
+            variant: Some(variant),
+            items: Vec::with_capacity(len),
+        })
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<MapSerializer, RingError> {
+        Ok(MapSerializer {
+            pairs: Vec::with_capacity(len.unwrap_or(0)),
+            key: None,
+        })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<MapSerializer, RingError> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SeqSerializer, RingError> {
+        Ok(SeqSerializer {
+            variant: Some(variant),
+            items: Vec::with_capacity(len),
+        })
+    }
+}
+
+struct SeqSerializer {
+    variant: Option<&'static str>,
+    items: Vec<RingValue>,
+}
+
+impl SeqSerializer {
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), RingError> {
+        self.items.push(value.serialize(ValueSerializer)?);
+        Ok(())
+    }
+
+    fn finish(self) -> Result<RingValue, RingError> {
+        Ok(match self.variant {
+            Some(name) => variant(name, self.items),
+            None => RingValue::List(self.items),
+        })
+    }
+}
+
+impl ser::SerializeSeq for SeqSerializer {
+    type Ok = RingValue;
+    type Error = RingError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), RingError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<RingValue, RingError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTuple for SeqSerializer {
+    type Ok = RingValue;
+    type Error = RingError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), RingError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<RingValue, RingError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleStruct for SeqSerializer {
+    type Ok = RingValue;
+    type Error = RingError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), RingError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<RingValue, RingError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleVariant for SeqSerializer {
+    type Ok = RingValue;
+    type Error = RingError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), RingError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<RingValue, RingError> {
+        self.finish()
+    }
+}
+
+struct MapSerializer {
+    pairs: Vec<RingValue>,
+    key: Option<RingValue>,
+}
+
+impl ser::SerializeMap for MapSerializer {
+    type Ok = RingValue;
+    type Error = RingError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), RingError> {
+        self.key = Some(key.serialize(ValueSerializer)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), RingError> {
+        let key = self.key.take().unwrap_or_default();
+        self.pairs
+            .push(pair(key, value.serialize(ValueSerializer)?));
+        Ok(())
+    }
+
+    fn end(self) -> Result<RingValue, RingError> {
+        Ok(RingValue::List(self.pairs))
+    }
+}
+
+impl ser::SerializeStruct for MapSerializer {
+    type Ok = RingValue;
+    type Error = RingError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), RingError> {
+        self.pairs
+            .push(pair(key.into(), value.serialize(ValueSerializer)?));
+        Ok(())
+    }
+
+    fn end(self) -> Result<RingValue, RingError> {
+        Ok(RingValue::List(self.pairs))
+    }
+}
+
+impl ser::SerializeStructVariant for SeqSerializer {
+    type Ok = RingValue;
+    type Error = RingError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        _key: &'static str,
+        value: &T,
+    ) -> Result<(), RingError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<RingValue, RingError> {
+        self.finish()
+    }
+}
+
+// Deserialization
+
+struct ValueDeserializer(RingValue);
+
+impl ValueDeserializer {
+    fn invalid(&self, expected: &dyn de::Expected) -> RingError {
+        de::Error::invalid_type(self.unexpected(), expected)
+    }
+
+    fn unexpected(&self) -> de::Unexpected<'_> {
+        match &self.0 {
+            RingValue::Nothing => de::Unexpected::Unit,
+            RingValue::Int(n) => de::Unexpected::Signed(*n as i64),
+            RingValue::Double(n) => de::Unexpected::Float(*n),
+            RingValue::String(bytes) => match std::str::from_utf8(bytes) {
+                Ok(s) => de::Unexpected::Str(s),
+                Err(_) => de::Unexpected::Bytes(bytes),
+            },
+            RingValue::List(_) => de::Unexpected::Seq,
+            RingValue::Object { .. } => de::Unexpected::Map,
+            RingValue::CPointer { .. } | RingValue::FuncPointer(_) => {
+                de::Unexpected::Other("pointer")
+            }
+        }
+    }
+
+    fn number(&self, expected: &dyn de::Expected) -> Result<f64, RingError> {
+        self.0.as_f64().ok_or_else(|| self.invalid(expected))
+    }
+
+    fn is_null(&self) -> bool {
+        match &self.0 {
+            RingValue::Nothing => true,
+            RingValue::String(bytes) => bytes.is_empty(),
+            _ => false,
+        }
+    }
+
+    /// The `[key, value]` pairs of a pair list or an object.
+    fn into_pairs(self, exp: &dyn de::Expected) -> Result<Vec<(RingValue, RingValue)>, RingError> {
+        match self.0 {
+            RingValue::Object { attributes, .. } => Ok(attributes
+                .into_iter()
+                .map(|(name, value)| (RingValue::from(name), value))
+                .collect()),
+            RingValue::List(items) => items
+                .into_iter()
+                .map(|item| match item {
+                    RingValue::List(mut kv) if kv.len() == 2 => {
+                        let value = kv.pop().unwrap_or_default();
+                        let key = kv.pop().unwrap_or_default();
+                        Ok((key, value))
+                    }
+                    other => Err(ValueDeserializer(other).invalid(exp)),
+                })
+                .collect(),
+            other => Err(ValueDeserializer(other).invalid(exp)),
+        }
+    }
+}
+
+macro_rules! deserialize_integer {
+    ($($method:ident => $visit:ident as $t:ty),* $(,)?) => {$(
+        fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RingError> {
+            let n = self.number(&visitor)?;
+            // `MAX as f64 + 1.0` is exact for every integer type, unlike `MAX as f64` itself
+            if n.fract() != 0.0 || !(n >= <$t>::MIN as f64 && n < <$t>::MAX as f64 + 1.0) {
+                return Err(de::Error::invalid_value(de::Unexpected::Float(n), &visitor));
+            }
+            visitor.$visit(n as $t)
+        }
+    )*};
+}
+
+impl<'de> de::Deserializer<'de> for ValueDeserializer {
+    type Error = RingError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RingError> {
+        match self.0 {
+            RingValue::Nothing => visitor.visit_unit(),
+            RingValue::Int(n) => visitor.visit_i32(n),
+            RingValue::Double(n) => visitor.visit_f64(n),
+            RingValue::String(bytes) => match String::from_utf8(bytes) {
+                Ok(s) => visitor.visit_string(s),
+                Err(e) => visitor.visit_byte_buf(e.into_bytes()),
+            },
+            RingValue::List(_) => self.deserialize_seq(visitor),
+            RingValue::Object { .. } => self.deserialize_map(visitor),
+            RingValue::CPointer { .. } | RingValue::FuncPointer(_) => Err(self.invalid(&visitor)),
+        }
+    }
+
+    deserialize_integer! {
+        deserialize_i8 => visit_i8 as i8,
+        deserialize_i16 => visit_i16 as i16,
+        deserialize_i32 => visit_i32 as i32,
+        deserialize_i64 => visit_i64 as i64,
+        deserialize_u8 => visit_u8 as u8,
+        deserialize_u16 => visit_u16 as u16,
+        deserialize_u32 => visit_u32 as u32,
+        deserialize_u64 => visit_u64 as u64,
+    }
+
+    fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RingError> {
+        let n = self.number(&visitor)?;
+        if n.is_finite() && (n as f32).is_infinite() {
+            return Err(de::Error::invalid_value(de::Unexpected::Float(n), &visitor));
+        }
+        visitor.visit_f32(n as f32)
+    }
+
+    fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RingError> {
+        let n = self.number(&visitor)?;
+        visitor.visit_f64(n)
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RingError> {
+        let n = self.number(&visitor)?;
+        visitor.visit_bool(n != 0.0)
+    }
+
+    fn deserialize_char<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RingError> {
+        self.deserialize_string(visitor)
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RingError> {
+        self.deserialize_string(visitor)
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RingError> {
+        match self.0 {
+            RingValue::String(bytes) => match String::from_utf8(bytes) {
+                Ok(s) => visitor.visit_string(s),
+                Err(e) => visitor.visit_byte_buf(e.into_bytes()),
+            },
+            _ => Err(self.invalid(&visitor)),
+        }
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RingError> {
+        self.deserialize_byte_buf(visitor)
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RingError> {
+        match self.0 {
+            RingValue::String(bytes) => visitor.visit_byte_buf(bytes),
+            RingValue::List(_) => self.deserialize_seq(visitor),
+            _ => Err(self.invalid(&visitor)),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RingError> {
+        if self.is_null() {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RingError> {
+        if self.is_null() {
+            visitor.visit_unit()
+        } else {
+            Err(self.invalid(&visitor))
+        }
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, RingError> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, RingError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RingError> {
+        match self.0 {
+            RingValue::List(items) => {
+                let mut seq = SeqDeserializer(items.into_iter());
+                let value = visitor.visit_seq(&mut seq)?;
+                match seq.0.len() {
+                    0 => Ok(value),
+                    n => Err(de::Error::invalid_length(n, &"fewer elements in list")),
+                }
+            }
+            _ => Err(self.invalid(&visitor)),
+        }
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, RingError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, RingError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RingError> {
+        let pairs = self.into_pairs(&visitor)?;
+        visitor.visit_map(MapDeserializer {
+            pairs: pairs.into_iter(),
+            value: None,
+            fields: &[],
+        })
+    }
+
+    /// Ring attribute names are lowercase, so keys are matched to field names ignoring case.
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, RingError> {
+        let pairs = self.into_pairs(&visitor)?;
+        visitor.visit_map(MapDeserializer {
+            pairs: pairs.into_iter(),
+            value: None,
+            fields,
+        })
+    }
+
+    /// Variant names are matched ignoring case and underscores, so the snake case names written
+    /// by `#[derive(IntoRing)]` are found too.
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, RingError> {
+        let (variant, payload) = match self.0 {
+            RingValue::List(items) if !items.is_empty() => {
+                let mut items = items.into_iter();
+                (items.next().unwrap_or_default(), items.collect())
+            }
+            RingValue::List(_) => return Err(self.invalid(&visitor)),
+            variant => (variant, Vec::new()),
+        };
+        let found = variant.as_str().and_then(|name| {
+            let plain = name.replace('_', "");
+            variants
+                .iter()
+                .find(|v| v.eq_ignore_ascii_case(name) || v.eq_ignore_ascii_case(&plain))
+        });
+        let variant = found.map_or(variant, |&name| RingValue::from(name));
+        visitor.visit_enum(EnumDeserializer { variant, payload })
+    }
+
+    /// A number is taken as the index of a field or variant.
+    fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RingError> {
+        match self.0 {
+            RingValue::Int(_) | RingValue::Double(_) => self.deserialize_u64(visitor),
+            _ => self.deserialize_string(visitor),
+        }
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RingError> {
+        visitor.visit_unit()
+    }
+}
+
+struct SeqDeserializer(std::vec::IntoIter<RingValue>);
+
+impl<'de> de::SeqAccess<'de> for SeqDeserializer {
+    type Error = RingError;
+
+    fn next_element_seed<T: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, RingError> {
+        match self.0.next() {
+            Some(value) => seed.deserialize(ValueDeserializer(value)).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.0.len())
+    }
+}
+
+struct MapDeserializer {
+    pairs: std::vec::IntoIter<(RingValue, RingValue)>,
+    value: Option<RingValue>,
+    fields: &'static [&'static str],
+}
+
+impl<'de> de::MapAccess<'de> for MapDeserializer {
+    type Error = RingError;
+
+    fn next_key_seed<K: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, RingError> {
+        let Some((key, value)) = self.pairs.next() else {
+            return Ok(None);
+        };
+        self.value = Some(value);
+        let key = match key.as_str() {
+            Some(name) => match self.fields.iter().find(|f| f.eq_ignore_ascii_case(name)) {
+                Some(field) => RingValue::from(*field),
+                None => key,
+            },
+            None => key,
+        };
+        seed.deserialize(ValueDeserializer(key)).map(Some)
+    }
+
+    fn next_value_seed<V: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, RingError> {
+        seed.deserialize(ValueDeserializer(self.value.take().unwrap_or_default()))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.pairs.len())
+    }
+}
+
+struct EnumDeserializer {
+    variant: RingValue,
+    payload: Vec<RingValue>,
+}
+
+impl<'de> de::EnumAccess<'de> for EnumDeserializer {
+    type Error = RingError;
+    type Variant = VariantDeserializer;
+
+    fn variant_seed<V: de::DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, VariantDeserializer), RingError> {
+        let variant = seed.deserialize(ValueDeserializer(self.variant))?;
+        Ok((variant, VariantDeserializer(self.payload)))
+    }
+}
+
+/// The values following a variant's name.
+struct VariantDeserializer(Vec<RingValue>);
+
+impl VariantDeserializer {
+    fn seq(self) -> ValueDeserializer {
+        ValueDeserializer(RingValue::List(self.0))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for VariantDeserializer {
+    type Error = RingError;
+
+    fn unit_variant(self) -> Result<(), RingError> {
+        match self.0.len() {
+            0 => Ok(()),
+            n => Err(de::Error::invalid_length(
+                n,
+                &"no values for a unit variant",
+            )),
+        }
+    }
+
+    fn newtype_variant_seed<T: de::DeserializeSeed<'de>>(
+        mut self,
+        seed: T,
+    ) -> Result<T::Value, RingError> {
+        match self.0.len() {
+            1 => seed.deserialize(ValueDeserializer(self.0.pop().unwrap_or_default())),
+            n => Err(de::Error::invalid_length(
+                n,
+                &"one value for a newtype variant",
+            )),
+        }
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, RingError> {
+        de::Deserializer::deserialize_seq(self.seq(), visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, RingError> {
+        de::Deserializer::deserialize_seq(self.seq(), visitor)
+    }
+}
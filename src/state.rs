@@ -1,7 +1,17 @@
-use crate::ffi_types::{CString, c_char, c_int, c_uint, c_void, size_t};
+use crate::ffi_types::{CStr, CString, c_char, c_int, c_uint, c_void, size_t};
+use std::cell::{Cell, RefCell};
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::Path;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
 
 use crate::ffi;
-use crate::{RingList, RingState};
+use crate::{
+    CallArgs, FromRing, IntoRing, RING_VAR_VALUE, RegisterError, RingCall, RingError, RingFunc,
+    RingList, RingState, RingVM, RingValue, ring_list_deleteitem, ring_list_getsize,
+};
 
 #[inline]
 pub fn ring_state_new() -> RingState {
@@ -195,3 +205,1006 @@ pub fn ring_vm_loadcfunctions(state: RingState) {
 pub fn ring_vm_generallib_loadfunctions(state: RingState) {
     unsafe { ffi::ring_vm_generallib_loadfunctions(state) }
 }
+
+// Owned runtime
+
+const RING_RS_VMPROBE: &[u8] = b"ring_rs_vm\0";
+const RING_RS_CATCH: &[u8] = b"ring_rs_catch\0";
+const RING_RS_EXEC: &[u8] = b"ring_rs_exec\0";
+/// Runs the pending [`Job`] through `ring_rs_exec`, so errors in it reach `ring_rs_catch`.
+const RING_RS_RUN: &[u8] = b"try ring_rs_exec() catch ring_rs_catch(cCatchError) done\0";
+const RING_RS_SOURCE: &[u8] = b"ring_rs_source\0";
+const RING_RS_HASMAIN: &[u8] = b"ring_rs_hasmain\0";
+/// Runs a pending [`Job::Run`] without limits: `eval` leaves the code to the VM's own main loop.
+const RING_RS_EVAL: &[u8] = b"try eval(ring_rs_source()) if ring_rs_hasmain() main() ok \
+    catch ring_rs_catch(cCatchError) done\0";
+const RING_RS_OUTPUT: &[u8] = b"ring_rs_output\0";
+const RING_RS_ATTRIBUTES: &[u8] = b"ring_rs_attributes\0";
+/// Run once per state: finds its VM and defines the `see` function installed by
+/// [`Ring::capture`], which hands the text Ring would have printed to `ring_rs_output`. Numbers
+/// go through Ring's own conversion so `decimals()` applies; lists print an item per line like
+/// `see` does, and objects an attribute per line from `ring_rs_attributes`, so it works without
+/// the `RefMeta` functions.
+const RING_RS_INIT: &[u8] = b"ring_rs_vm() \
+    func ring_rs_see ring_rs_value \
+        if isstring(ring_rs_value) ring_rs_output(ring_rs_value) \
+        but isnumber(ring_rs_value) ring_rs_output(\"\" + ring_rs_value) \
+        but islist(ring_rs_value) \
+            for ring_rs_item in ring_rs_value \
+                ring_rs_see(ring_rs_item) \
+                if not islist(ring_rs_item) ring_rs_output(nl) ok \
+            next \
+        but isobject(ring_rs_value) \
+            for ring_rs_attribute in ring_rs_attributes(ring_rs_value) \
+                ring_rs_output(ring_rs_attribute[1] + \": \") \
+                ring_rs_see(ring_rs_attribute[3]) \
+                ring_rs_output(nl) \
+            next \
+        ok\0";
+const RINGVM_SEE: &[u8] = b"ringvm_see";
+const RING_RS_SEEON: &[u8] = b"ringvm_see(\"ring_rs_see\")\0";
+const RING_RS_SEEOFF: &[u8] = b"ringvm_see(\"\")\0";
+const RING_RS_LIMITERROR: &[u8] = b"Execution limit exceeded\0";
+const RING_RS_MEMORYERROR: &[u8] = b"Memory limit exceeded\0";
+/// Steps between two memory usage checks, which walk all of the state's data, for each time the
+/// current usage still fits in the headroom left below the limit.
+const MEMORY_CHECK_STEPS: u32 = 100;
+
+type OutputHandler = Box<dyn FnMut(&[u8])>;
+
+thread_local! {
+    static PROBED_VM: Cell<RingVM> = const { Cell::new(std::ptr::null_mut()) };
+    static CAUGHT_ERRORS: RefCell<Vec<Option<RingError>>> = const { RefCell::new(Vec::new()) };
+    static RAISED_ERROR: RefCell<Option<(Vec<u8>, RingError)>> = const { RefCell::new(None) };
+    static FILE_NAMES: RefCell<HashSet<&'static CStr>> = RefCell::new(HashSet::new());
+    static BUDGETS: RefCell<Vec<Budget>> = const { RefCell::new(Vec::new()) };
+    static JOBS: RefCell<Vec<PendingJob>> = const { RefCell::new(Vec::new()) };
+    /// Output collected for each state inside [`Ring::capture`].
+    static OUTPUTS: RefCell<HashMap<RingVM, Vec<u8>>> = RefCell::new(HashMap::new());
+    static HOST_FNS: RefCell<HashMap<RingVM, HashMap<String, HostFn>>> =
+        RefCell::new(HashMap::new());
+}
+
+/// What a run does inside its `try`, through `ring_rs_exec`, or [`RING_RS_EVAL`] for code run
+/// without limits.
+enum Job {
+    /// Evaluate source code, then call `main()` if `main` is set and the code defined it.
+    Run { code: Vec<u8>, main: bool },
+    /// Call a function with arguments.
+    Call { name: String, args: Vec<RingValue> },
+}
+
+/// A run's [`Job`], taken by `ring_rs_exec`, and what it returned.
+struct PendingJob {
+    job: Option<Job>,
+    result: Option<Result<RingValue, RingError>>,
+}
+
+/// A closure registered with [`Ring::register_fn`].
+type HostFn = Rc<dyn Fn(&mut RingCall<'_>)>;
+
+/// The execution limits of one [`Ring::run`] call, counted down by [`budget_fetch`].
+struct Budget {
+    steps: Option<u64>,
+    deadline: Option<Instant>,
+    memory: Option<usize>,
+    /// Steps left before the next memory check.
+    memory_check: u32,
+    /// The usage found by the last memory check plus the bytes requested through
+    /// [`ring_rs_checkalloc`] since, or `None` before the first check.
+    memory_estimate: Option<f64>,
+    /// The string on top of the stack when it was last counted against the estimate.
+    memory_pending: *mut ffi::String,
+    /// Size of `pTry` with the run's own `try` pushed.
+    try_depth: c_uint,
+    /// [`RingError::Timeout`] or [`RingError::OutOfMemory`] once a limit was hit.
+    exceeded: Option<RingError>,
+}
+
+impl Budget {
+    /// Whether any limit is set, so the run has to be stepped through [`budget_fetch`].
+    fn is_limited(&self) -> bool {
+        self.steps.is_some() || self.deadline.is_some() || self.memory.is_some()
+    }
+
+    /// Count one step, returning `true` the first time a limit is hit. Later steps, such as the
+    /// run's own `catch` handler, are let through.
+    fn tick(&mut self, vm: RingVM) -> bool {
+        if self.exceeded.is_some() || !self.is_limited() {
+            return false;
+        }
+        if let Some(steps) = &mut self.steps {
+            match steps.checked_sub(1) {
+                Some(left) => *steps = left,
+                None => self.exceeded = Some(RingError::Timeout),
+            }
+        }
+        if self
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            self.exceeded = Some(RingError::Timeout);
+        }
+        if let Some(limit) = self.memory {
+            // A new string on the stack, such as `s + s` before it is stored, is not counted by
+            // a measurement yet, so one that would pass the limit forces a check right away
+            let pending = match unsafe { stack_string(vm) } {
+                Some((string, size)) if string != self.memory_pending => {
+                    self.memory_pending = string;
+                    size
+                }
+                _ => 0,
+            };
+            let estimate = self.memory_estimate.unwrap_or(0.0) + pending as f64;
+            if self.memory_check == 0 || estimate > limit as f64 {
+                let (usage, walked) = self.measure(vm);
+                if usage + pending > limit {
+                    self.exceeded = Some(RingError::OutOfMemory);
+                }
+                self.memory_estimate = Some((usage + pending) as f64);
+                // Wait `MEMORY_CHECK_STEPS` steps for each current usage's worth of headroom:
+                // every 100 steps at half the limit and rarely while usage is a small fraction
+                // of it. Each check walks all of the data, so near the limit the wait is at
+                // least a step per list and item walked, keeping checks from dominating the run
+                let headroom = limit.saturating_sub(usage + pending) as u64;
+                let interval =
+                    (headroom * MEMORY_CHECK_STEPS as u64 / usage.max(1) as u64).max(walked as u64);
+                self.memory_check = interval.try_into().unwrap_or(u32::MAX);
+            } else {
+                self.memory_check -= 1;
+            }
+        }
+        self.exceeded.is_some()
+    }
+
+    /// Measure the VM's memory usage, restarting the estimate from it. Also returns how many
+    /// lists and items were walked.
+    fn measure(&mut self, vm: RingVM) -> (usize, usize) {
+        let (usage, walked) = crate::memory::memory_walk(vm);
+        self.memory_estimate = Some(usage as f64);
+        (usage, walked)
+    }
+}
+
+/// Run the VM's next instruction, counting it against the run's limits. Once a limit is hit the
+/// error is raised instead, which leaves the VM in the run's `catch`.
+fn budget_fetch(vm: RingVM) {
+    let exceeded = BUDGETS.with(|budgets| {
+        let mut budgets = budgets.borrow_mut();
+        let budget = budgets.last_mut()?;
+        budget.tick(vm).then(|| budget_abort(budget))
+    });
+    match exceeded {
+        Some((try_depth, message)) => abort_run(vm as *mut c_void, try_depth, message),
+        None => crate::ring_vm_fetch(vm),
+    }
+}
+
+/// Called by the guarded functions' wrappers before allocating `requested` bytes.
+///
+/// Measuring walks all of the state's data, so the request is checked against the running
+/// estimate instead, and usage is only measured again when the estimate would pass the limit.
+/// A loop of small allocations then measures about once per limit's worth of requests.
+fn ring_rs_checkalloc(p: *mut c_void, requested: f64) -> bool {
+    let exceeded = BUDGETS.with(|budgets| {
+        let mut budgets = budgets.borrow_mut();
+        let budget = budgets.last_mut()?;
+        let limit = budget.memory? as f64;
+        if budget.exceeded.is_some() {
+            return None;
+        }
+        let usage = match budget.memory_estimate {
+            Some(estimate) if estimate + requested <= limit => estimate,
+            _ => budget.measure(p as RingVM).0 as f64,
+        };
+        if usage + requested <= limit {
+            budget.memory_estimate = Some(usage + requested);
+            return None;
+        }
+        budget.exceeded = Some(RingError::OutOfMemory);
+        Some(budget_abort(budget))
+    });
+    match exceeded {
+        Some((try_depth, message)) => {
+            abort_run(p, try_depth, message);
+            false
+        }
+        None => true,
+    }
+}
+
+/// The string on top of the VM stack and its size in bytes, if the top item is a string.
+unsafe fn stack_string(vm: RingVM) -> Option<(*mut ffi::String, usize)> {
+    unsafe {
+        let sp = (*vm).nSP as usize;
+        let item = (*vm).aStack.get(sp).filter(|_| sp > 0)?;
+        let string = item.data.pString;
+        (item.nType() == ffi::ITEMTYPE_STRING && !string.is_null())
+            .then(|| (string, (*string).nSize as usize))
+    }
+}
+
+fn budget_abort(budget: &Budget) -> (c_uint, &'static [u8]) {
+    let message = match budget.exceeded {
+        Some(RingError::OutOfMemory) => RING_RS_MEMORYERROR,
+        _ => RING_RS_LIMITERROR,
+    };
+    (budget.try_depth, message)
+}
+
+/// Drop every `try` the script opened and raise `message`, so the error can only be caught by
+/// the run itself.
+fn abort_run(p: *mut c_void, try_depth: c_uint, message: &[u8]) {
+    let tries = unsafe { (*(p as RingVM)).pTry };
+    while ring_list_getsize(tries) > try_depth {
+        ring_list_deleteitem(tries, ring_list_getsize(tries));
+    }
+    crate::ring_api_error(p, message);
+}
+
+/// The VM of `state`, or `None` if it has none yet.
+///
+/// The VM is only trusted if it points back to `state`.
+pub(crate) fn state_vm(state: RingState) -> Option<RingVM> {
+    if state.is_null() {
+        return None;
+    }
+    unsafe {
+        let vm = (*(state as *const ffi::RingStateHead)).pVM;
+        (!vm.is_null() && (*vm).pRingState == state).then_some(vm)
+    }
+}
+
+extern "C" fn ring_rs_vmprobe(p: *mut c_void) {
+    PROBED_VM.with(|vm| vm.set(p as RingVM));
+}
+
+/// Point the C function `name` at `func`, returning what it pointed at before, or `None` if the
+/// state has no such function.
+fn replace_cfunction(vm: RingVM, name: &[u8], func: Option<RingFunc>) -> Option<Option<RingFunc>> {
+    unsafe {
+        let f = crate::builder::find_cfunction(vm, name)?;
+        Some(std::mem::replace(&mut (*f).pFunc, func))
+    }
+}
+
+/// Whether the VM's function table has a Ring function `name`, such as one defined by code it ran.
+fn defines_function(vm: RingVM, name: &[u8]) -> bool {
+    let functions = unsafe { (*vm).pFunctionsMap };
+    (1..=ring_list_getsize(functions)).any(|i| {
+        let function = crate::ring_list_getlist(functions, i);
+        let found = crate::ring_list_getstring(function, 1);
+        !found.is_null()
+            && unsafe { CStr::from_ptr(found) }
+                .to_bytes()
+                .eq_ignore_ascii_case(name)
+    })
+}
+
+/// Runs the innermost pending [`Job`] and keeps its result. Under limits, it runs one
+/// instruction at a time through [`budget_fetch`]; a call without them goes through
+/// [`ring_vm_call`](crate::ring_vm_call).
+extern "C" fn ring_rs_exec(p: *mut c_void) {
+    let vm = p as RingVM;
+    let job = JOBS.with(|jobs| {
+        jobs.borrow_mut()
+            .last_mut()
+            .and_then(|pending| pending.job.take())
+    });
+    let limited = BUDGETS.with(|budgets| budgets.borrow().last().is_some_and(Budget::is_limited));
+    let result = match job {
+        Some(Job::Run { code, main }) => run_source(vm, code, main),
+        Some(Job::Call { name, args }) if limited => {
+            crate::vm::ring_vm_call_with(vm, &name, args, budget_fetch)
+        }
+        Some(Job::Call { name, args }) => crate::ring_vm_call(vm, &name, args),
+        None => return,
+    };
+    JOBS.with(|jobs| {
+        if let Some(pending) = jobs.borrow_mut().last_mut() {
+            pending.result = Some(result);
+        }
+    });
+}
+
+/// Evaluate `code` and run it to its end under the run's limits, then call `main()` if `main`
+/// is set and it is defined.
+///
+/// `eval` only compiles the code and points `nPC` at it, returning to the instruction after the
+/// call once the code ends, so the code runs here until `nPC` is back there. An error caught by
+/// the run's `try` leaves the call frames below this function's own, which also ends the loop.
+fn run_source(vm: RingVM, code: Vec<u8>, main: bool) -> Result<RingValue, RingError> {
+    let depth = crate::ring_vm_funccallscount(vm);
+    let resume = unsafe { (*vm).nPC };
+    crate::vm::ring_vm_call_with::<_, ()>(vm, "eval", (RingValue::String(code),), budget_fetch)?;
+    loop {
+        let frames = crate::ring_vm_funccallscount(vm);
+        if frames < depth {
+            return Err(unsafe { runtime_error(vm, "The run was stopped by an error".into(), 0) });
+        }
+        if frames == depth && unsafe { (*vm).nPC } == resume {
+            break;
+        }
+        budget_fetch(vm);
+    }
+    if main && defines_function(vm, b"main") {
+        crate::vm::ring_vm_call_with(vm, "main", (), budget_fetch)
+    } else {
+        Ok(RingValue::Nothing)
+    }
+}
+
+/// Returns the code of the pending [`Job::Run`] to [`RING_RS_EVAL`].
+extern "C" fn ring_rs_source(p: *mut c_void) {
+    let code = JOBS.with(|jobs| {
+        match jobs
+            .borrow_mut()
+            .last_mut()
+            .and_then(|pending| pending.job.as_mut())
+        {
+            Some(Job::Run { code, .. }) => std::mem::take(code),
+            _ => Vec::new(),
+        }
+    });
+    crate::ring_api_retstring2(p, &code);
+}
+
+/// Takes the pending [`Job::Run`] for [`RING_RS_EVAL`] once its code ran, returning whether
+/// `main()` should be called next: the job asks for it and the code defined one.
+extern "C" fn ring_rs_hasmain(p: *mut c_void) {
+    let job = JOBS.with(|jobs| {
+        jobs.borrow_mut()
+            .last_mut()
+            .and_then(|pending| pending.job.take())
+    });
+    let main =
+        matches!(job, Some(Job::Run { main: true, .. })) && defines_function(p as RingVM, b"main");
+    crate::ring_api_retnumber(p, if main { 1.0 } else { 0.0 });
+}
+
+/// Receives the text printed by `see` while its state is capturing.
+extern "C" fn ring_rs_output(p: *mut c_void) {
+    let text = crate::ring_api_getstring_vec(p, 1);
+    OUTPUTS.with(|outputs| {
+        if let Some(output) = outputs.borrow_mut().get_mut(&(p as RingVM)) {
+            output.extend_from_slice(&text);
+        }
+    });
+}
+
+/// Returns a copy of an object's attribute variables (`[name, type, value, ...]`), without
+/// `self`, for `ring_rs_see`.
+extern "C" fn ring_rs_attributes(p: *mut c_void) {
+    let data =
+        crate::ring_list_getlist(crate::ring_api_getlist(p, 1), crate::RING_OBJECT_OBJECTDATA);
+    let attributes = crate::ring_list_new(0);
+    for i in 1..=ring_list_getsize(data) {
+        let var = crate::ring_list_getlist(data, i);
+        if crate::ring_list_getstring_str(var, crate::RING_VAR_NAME) != "self" {
+            crate::ring_list_copy(crate::ring_list_newlist(attributes), var);
+        }
+    }
+    crate::ring_api_retlist(p, attributes);
+    crate::ring_list_delete(attributes);
+}
+
+extern "C" fn ring_rs_catch(p: *mut c_void) {
+    let message = crate::ring_api_getstring_vec(p, 1);
+    // Errors raised from Rust were recorded where they were raised; for the VM's own errors the
+    // line and file are still those of the failing instruction, since the catch handler is on
+    // the same line as the `try`.
+    let raised = RAISED_ERROR
+        .with(|raised| raised.borrow_mut().take())
+        .filter(|(raised, _)| *raised == message)
+        .map(|(_, error)| error);
+    let error = raised.unwrap_or_else(|| unsafe {
+        runtime_error(
+            p as RingVM,
+            String::from_utf8_lossy(&message).into_owned(),
+            1,
+        )
+    });
+    CAUGHT_ERRORS.with(|errors| {
+        if let Some(slot) = errors.borrow_mut().last_mut() {
+            *slot = Some(error);
+        }
+    });
+}
+
+/// Dispatches every [`Ring::register_fn`] function to the closure registered under its name.
+extern "C" fn ring_rs_hostfn(p: *mut c_void) {
+    let vm = p as RingVM;
+    // The innermost frame is the call to the registered function
+    let name = unsafe {
+        let frames = ((*vm).nCurrentFuncCall as usize).min(ffi::RING_VM_STACK_SIZE);
+        frames
+            .checked_sub(1)
+            .map(|i| (&(*vm).aFuncCall)[i].cName)
+            .filter(|name| !name.is_null())
+            .map(|name| CStr::from_ptr(name).to_string_lossy().to_ascii_lowercase())
+            .unwrap_or_default()
+    };
+    // Cloned out of the registry so the closure can run Ring code that calls back into it
+    let func = HOST_FNS.with(|fns| {
+        fns.borrow()
+            .get(&vm)
+            .and_then(|names| names.get(&name))
+            .cloned()
+    });
+    let Some(func) = func else {
+        crate::ring_api_error_str(
+            p,
+            &format!("Calling function not registered in this state: {}", name),
+        );
+        return;
+    };
+    crate::ring_api_catch_panic(p, |p| RingCall::scope(p, |mut call| func(&mut call)));
+}
+
+/// Record the position of an error raised through [`ring_api_error`](crate::ring_api_error)
+/// while a checked run is active, before the VM unwinds to the run's catch handler.
+pub(crate) fn ring_rs_raising(p: *mut c_void, message: &[u8]) {
+    if CAUGHT_ERRORS.with(|errors| errors.borrow().is_empty()) {
+        return;
+    }
+    let message = message.strip_suffix(b"\0").unwrap_or(message);
+    let error = unsafe {
+        runtime_error(
+            p as RingVM,
+            String::from_utf8_lossy(message).into_owned(),
+            0,
+        )
+    };
+    RAISED_ERROR.with(|raised| *raised.borrow_mut() = Some((message.to_vec(), error)));
+}
+
+/// Build a [`RingError::Runtime`] from the VM's current position, leaving out the innermost
+/// `skip` frames.
+unsafe fn runtime_error(vm: RingVM, message: String, skip: usize) -> RingError {
+    unsafe {
+        let file = if (*vm).cFileName.is_null() {
+            None
+        } else {
+            Some(
+                CStr::from_ptr((*vm).cFileName)
+                    .to_string_lossy()
+                    .into_owned(),
+            )
+        };
+        let frames = ((*vm).nCurrentFuncCall as usize).min(ffi::RING_VM_STACK_SIZE);
+        let call_stack = (&(*vm).aFuncCall)[..frames.saturating_sub(skip)]
+            .iter()
+            .filter(|call| !call.cName.is_null())
+            .map(|call| CStr::from_ptr(call.cName).to_string_lossy().into_owned())
+            .collect();
+        RingError::Runtime {
+            message,
+            file,
+            line: (*vm).nLineNumber,
+            call_stack,
+        }
+    }
+}
+
+/// A file name that can be handed to the VM as `cFileName`. The VM may keep the pointer (for
+/// example in the functions a file defines), so each name is leaked once.
+fn intern_file_name(path: &Path) -> Result<&'static CStr, RingError> {
+    let name = CString::new(path.as_os_str().as_encoded_bytes())
+        .map_err(|e| RingError::Io(std::io::Error::new(std::io::ErrorKind::InvalidInput, e)))?;
+    Ok(FILE_NAMES.with(|names| {
+        let mut names = names.borrow_mut();
+        match names.get(name.as_c_str()) {
+            Some(name) => *name,
+            None => {
+                let name: &'static CStr = Box::leak(name.into_boxed_c_str());
+                names.insert(name);
+                name
+            }
+        }
+    }))
+}
+
+/// Rewrite the `load` statements of `code` whose file exists in `folder` to load it by absolute
+/// path, so a file's loads resolve against its own folder without switching the process's
+/// current directory. Loads of files that aren't there, such as Ring's own libraries, are left
+/// for Ring to find.
+pub(crate) fn resolve_loads(code: &[u8], folder: &Path) -> Vec<u8> {
+    let is_word = |b: u8| b.is_ascii_alphanumeric() || b == b'_' || b >= 0x80;
+    let is_quote = |b: u8| matches!(b, b'"' | b'\'' | b'`');
+    let skip_spaces = |i: usize| {
+        i + code[i..]
+            .iter()
+            .take_while(|&&b| b == b' ' || b == b'\t')
+            .count()
+    };
+    let mut resolved = Vec::with_capacity(code.len());
+    let mut copied = 0;
+    let mut i = 0;
+    while i < code.len() {
+        let rest = &code[i..];
+        if rest[0] == b'#' || rest.starts_with(b"//") {
+            i += rest.iter().position(|&b| b == b'\n').unwrap_or(rest.len());
+        } else if rest.starts_with(b"/*") {
+            i += rest
+                .windows(2)
+                .position(|w| w == b"*/")
+                .map_or(rest.len(), |end| end + 2);
+        } else if is_quote(rest[0]) {
+            i += rest[1..]
+                .iter()
+                .position(|&b| b == rest[0])
+                .map_or(rest.len(), |end| end + 2);
+        } else if is_word(rest[0]) {
+            let word = rest.iter().position(|&b| !is_word(b)).unwrap_or(rest.len());
+            i += word;
+            if !rest[..word].eq_ignore_ascii_case(b"load") {
+                continue;
+            }
+            let mut start = skip_spaces(i);
+            let package = code[start..].iter().position(|&b| !is_word(b));
+            let package = package.unwrap_or(code.len() - start);
+            if code[start..start + package].eq_ignore_ascii_case(b"package") {
+                start = skip_spaces(start + package);
+            }
+            let Some(&quote) = code.get(start).filter(|&&b| is_quote(b)) else {
+                continue;
+            };
+            let Some(len) = code[start + 1..].iter().position(|&b| b == quote) else {
+                continue;
+            };
+            let name = String::from_utf8_lossy(&code[start + 1..start + 1 + len]);
+            let path = folder.join(name.as_ref());
+            i = start + len + 2;
+            let literal = path.to_str().filter(|_| path.is_file()).and_then(|path| {
+                let quote = ['"', '\'', '`'].into_iter().find(|&q| !path.contains(q))?;
+                Some(format!("{0}{1}{0}", quote, path))
+            });
+            if let Some(literal) = literal {
+                resolved.extend_from_slice(&code[copied..start]);
+                resolved.extend_from_slice(literal.as_bytes());
+                copied = i;
+            }
+        } else {
+            i += 1;
+        }
+    }
+    resolved.extend_from_slice(&code[copied..]);
+    resolved
+}
+
+/// An owned Ring runtime.
+///
+/// `Ring` creates its state with [`ring_state_init`] and calls [`ring_state_delete`] exactly once
+/// when dropped. It holds raw pointers, so it is neither `Send` nor `Sync`: a state must be used
+/// and dropped on the thread that created it.
+///
+/// Code is run inside a Ring `try`/`catch`, so a runtime error in the script is returned as
+/// [`RingError`] instead of printing the error and terminating the process.
+///
+/// ```rust,ignore
+/// let mut ring = Ring::new();
+/// ring.run(r#"x = 10 + 20"#)?;
+/// let x = ring.find_var("x").unwrap();
+/// println!("{}", ring_list_getdouble(x, RING_VAR_VALUE));
+/// ```
+pub struct Ring {
+    state: RingState,
+    vm: RingVM,
+    output: Option<OutputHandler>,
+    step_limit: Option<u64>,
+    timeout: Option<Duration>,
+    memory_limit: Option<usize>,
+    /// `ringvm_see()` as the state was created with, used for capture even after a
+    /// [`StateBuilder`](crate::StateBuilder) removes it.
+    see: Option<RingFunc>,
+}
+
+impl Ring {
+    /// Create a new state with an initialized VM.
+    pub fn new() -> Self {
+        unsafe { Self::from_raw(ring_state_init()) }
+    }
+
+    /// Take ownership of a state created with [`ring_state_init`].
+    ///
+    /// # Safety
+    ///
+    /// `state` must be a valid, initialized state that is not deleted elsewhere.
+    pub unsafe fn from_raw(state: RingState) -> Self {
+        PROBED_VM.with(|vm| vm.set(std::ptr::null_mut()));
+        crate::ring_register_function(state, RING_RS_VMPROBE, ring_rs_vmprobe);
+        crate::ring_register_function(state, RING_RS_CATCH, ring_rs_catch);
+        crate::ring_register_function(state, RING_RS_OUTPUT, ring_rs_output);
+        crate::ring_register_function(state, RING_RS_EXEC, ring_rs_exec);
+        crate::ring_register_function(state, RING_RS_SOURCE, ring_rs_source);
+        crate::ring_register_function(state, RING_RS_HASMAIN, ring_rs_hasmain);
+        crate::ring_register_function(state, RING_RS_ATTRIBUTES, ring_rs_attributes);
+        ring_state_runcode(state, RING_RS_INIT);
+        let vm = PROBED_VM.with(|vm| vm.replace(std::ptr::null_mut()));
+        // Left behind by a `Ring` that was forgotten and whose state was deleted since, at the
+        // same address
+        let stale = HOST_FNS.with(|fns| fns.borrow_mut().remove(&vm));
+        drop(stale);
+        let see =
+            unsafe { crate::builder::find_cfunction(vm, RINGVM_SEE).and_then(|f| (*f).pFunc) };
+        Self {
+            state,
+            vm,
+            output: None,
+            step_limit: None,
+            timeout: None,
+            memory_limit: None,
+            see,
+        }
+    }
+
+    /// Release ownership of the state without deleting it.
+    ///
+    /// Closures added with [`Ring::register_fn`] belong to the `Ring` and are dropped here, so
+    /// calling their functions from the released state raises a Ring error.
+    pub fn into_raw(self) -> RingState {
+        let state = self.state;
+        let closures = HOST_FNS.with(|fns| fns.borrow_mut().remove(&self.vm));
+        std::mem::forget(self);
+        drop(closures);
+        state
+    }
+
+    /// The underlying state pointer, for use with the `ring_state_*` functions.
+    #[inline]
+    pub fn as_ptr(&self) -> RingState {
+        self.state
+    }
+
+    /// The VM owned by this state, for use with the `ring_vm_*` functions.
+    #[inline]
+    pub fn vm(&self) -> RingVM {
+        self.vm
+    }
+
+    /// Run Ring source code on this state's VM.
+    ///
+    /// Runtime errors (including syntax errors reported by `eval`) are returned as
+    /// [`RingError::Runtime`]; the state stays usable afterwards.
+    pub fn run(&mut self, code: &str) -> Result<(), RingError> {
+        self.run_bytes(code.as_bytes())
+    }
+
+    /// Binary-safe variant of [`Ring::run`].
+    pub fn run_bytes(&mut self, code: &[u8]) -> Result<(), RingError> {
+        let job = Job::Run {
+            code: code.to_vec(),
+            main: false,
+        };
+        self.with_output(|ring| ring.run_checked(job)).map(drop)
+    }
+
+    /// Run `job` inside the run's `try`/`catch`, with the limits applied, and return its result.
+    fn run_checked(&mut self, job: Job) -> Result<RingValue, RingError> {
+        let try_depth = unsafe { ring_list_getsize((*self.vm).pTry) } + 1;
+        let budget = Budget {
+            steps: self.step_limit,
+            deadline: self.timeout.map(|timeout| Instant::now() + timeout),
+            memory: self.memory_limit,
+            memory_check: 0,
+            memory_estimate: None,
+            memory_pending: std::ptr::null_mut(),
+            try_depth,
+            exceeded: None,
+        };
+        // Without limits, code runs in the VM's own main loop instead of being stepped from Rust
+        let code = match job {
+            Job::Run { .. } if !budget.is_limited() => RING_RS_EVAL,
+            _ => RING_RS_RUN,
+        };
+        BUDGETS.with(|budgets| budgets.borrow_mut().push(budget));
+        JOBS.with(|jobs| {
+            jobs.borrow_mut().push(PendingJob {
+                job: Some(job),
+                result: None,
+            })
+        });
+
+        CAUGHT_ERRORS.with(|errors| errors.borrow_mut().push(None));
+        ring_state_runcode(self.state, code);
+        let caught = CAUGHT_ERRORS.with(|errors| errors.borrow_mut().pop().flatten());
+        let result = JOBS.with(|jobs| jobs.borrow_mut().pop().and_then(|pending| pending.result));
+        let exceeded = BUDGETS.with(|budgets| budgets.borrow_mut().pop().and_then(|b| b.exceeded));
+        RAISED_ERROR.with(|raised| raised.borrow_mut().take());
+
+        match (exceeded, caught) {
+            (Some(error), _) | (None, Some(error)) => Err(error),
+            (None, None) => result.unwrap_or(Ok(RingValue::Nothing)),
+        }
+    }
+
+    /// Run `code`, which calls the C function `name`, with `name` pointing at `func` (the
+    /// implementation saved by [`from_raw`](Self::from_raw)), whatever the state has under that
+    /// name now.
+    fn run_saved(&self, name: &[u8], func: Option<RingFunc>, code: &[u8]) {
+        if func.is_none() {
+            return;
+        }
+        if let Some(current) = replace_cfunction(self.vm, name, func) {
+            ring_state_runcode(self.state, code);
+            replace_cfunction(self.vm, name, current);
+        }
+    }
+
+    /// Stop [`run`](Self::run), [`run_bytes`](Self::run_bytes) and [`run_file`](Self::run_file)
+    /// after `steps` steps, returning [`RingError::Timeout`]. `None` removes the limit.
+    ///
+    /// A step is one VM instruction. While a limit is set, runs execute their instructions one
+    /// at a time from Rust, checking the limits as the VM advances `nPC`, so any loop is
+    /// interrupted, including one written on a single line such as `while true end`. Without
+    /// limits, runs use the VM's own main loop. A `try`/`catch` in the script cannot catch the
+    /// limit error.
+    pub fn set_step_limit(&mut self, steps: Option<u64>) {
+        self.step_limit = steps;
+    }
+
+    /// Stop each run after `timeout` of wall-clock time, returning [`RingError::Timeout`]. `None`
+    /// removes the limit.
+    ///
+    /// The deadline is checked on every step (see [`set_step_limit`](Self::set_step_limit)), so a
+    /// single long-running C or Rust function call is not interrupted, nor is Ring code run
+    /// from one, such as by [`ring_vm_call`](crate::ring_vm_call).
+    ///
+    /// ```rust,ignore
+    /// ring.set_timeout(Some(Duration::from_secs(1)));
+    /// assert!(matches!(ring.run("while true\n    x = 1\nend"), Err(RingError::Timeout)));
+    /// ```
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout;
+    }
+
+    /// Stop each run with [`RingError::OutOfMemory`] once the state's data grows past `bytes`.
+    /// `None` removes the limit.
+    ///
+    /// `list()`, `space()`, `copy()` and `read()` are checked before they allocate, against the
+    /// last measured usage plus what they requested since; usage is measured again only when
+    /// that estimate would pass the limit. Other growth, such as string concatenation or adding
+    /// to a list, is caught by measuring [`memory_usage`](Self::memory_usage) every few steps
+    /// (see [`set_step_limit`](Self::set_step_limit)). The wait grows with the headroom left:
+    /// 100 steps for each time the measured usage still fits below the limit, so every 100
+    /// steps at half the limit and far less often while usage is low. Each check walks all of
+    /// the data, so the wait never drops below one step per list and item walked: near the
+    /// limit, a state holding a million list items is checked every million steps. A new string
+    /// that would pass the limit, such as the result of `s + s`, is checked right away.
+    ///
+    /// A run can therefore overshoot by what it allocates between two checks, which is large for
+    /// code that grows lists very fast, such as copying a list into itself every step. Memory
+    /// held by C or Rust extensions, including buffers of other standard functions, is not
+    /// counted.
+    pub fn set_memory_limit(&mut self, bytes: Option<usize>) {
+        if bytes.is_some() {
+            crate::memory::guard_allocations(self.vm, ring_rs_checkalloc);
+        }
+        self.memory_limit = bytes;
+    }
+
+    /// An estimate of the memory held by this state's variables and temporary values, in bytes.
+    /// See [`ring_vm_memoryusage`](crate::ring_vm_memoryusage).
+    pub fn memory_usage(&self) -> usize {
+        crate::ring_vm_memoryusage(self.vm)
+    }
+
+    /// Run a Ring source file on this state's VM.
+    ///
+    /// The file runs the way the `ring` executable runs it: errors report its path, `load`
+    /// statements resolve against the file's folder, and `main()` is called afterwards if the
+    /// file defines it. The process's current directory is left alone, so states on other
+    /// threads are unaffected; other relative paths, such as those given to `read()`, resolve
+    /// against it as usual.
+    pub fn run_file(&mut self, path: impl AsRef<Path>) -> Result<(), RingError> {
+        let path = std::path::absolute(path)?;
+        let mut code = std::fs::read(&path)?;
+        if let Some(folder) = path.parent() {
+            code = resolve_loads(&code, folder);
+        }
+        let file = intern_file_name(&path)?;
+        // A `main()` defined before the file ran isn't the file's
+        let job = Job::Run {
+            code,
+            main: !defines_function(self.vm, b"main"),
+        };
+        let previous = unsafe { std::mem::replace(&mut (*self.vm).cFileName, file.as_ptr()) };
+        let result = self.with_output(|ring| ring.run_checked(job)).map(drop);
+        unsafe { (*self.vm).cFileName = previous };
+        result
+    }
+
+    /// Call a Ring function and convert its return value.
+    ///
+    /// Arguments can be any [`IntoRing`] values, including lists and `#[derive(IntoRing)]`
+    /// structs; they are pushed onto the VM stack by [`ring_vm_call`](crate::ring_vm_call), not
+    /// written into source code. The call runs like [`run`](Self::run): a runtime error in the
+    /// function is returned as [`RingError::Runtime`], and the limits and output handler apply.
+    ///
+    /// ```rust,ignore
+    /// ring.run("func add a, b return a + b")?;
+    /// let sum = ring.call::<f64>("add", (1, 2))?;
+    /// let total = ring.call::<f64>("sum", (vec![1, 2, 3],))?;
+    /// ```
+    pub fn call<R: FromRing>(&mut self, name: &str, args: impl CallArgs) -> Result<R, RingError> {
+        let job = Job::Call {
+            name: name.to_string(),
+            args: args.to_values(),
+        };
+        R::from_ring(self.with_output(|ring| ring.run_checked(job))?)
+    }
+
+    /// Run code and return what it printed with `see` and `?` along with the result.
+    ///
+    /// ```rust,ignore
+    /// let (result, output) = ring.run_captured(r#"? "Hello""#);
+    /// result?;
+    /// assert_eq!(output, b"Hello\n");
+    /// ```
+    pub fn run_captured(&mut self, code: &str) -> (Result<(), RingError>, Vec<u8>) {
+        self.capture(|ring| ring.run(code))
+    }
+
+    /// Run `f` and return what this state printed with `see` and `?` meanwhile.
+    ///
+    /// Only `see`/`?` output is captured: the state's `see` command is pointed at a buffer
+    /// through `ringvm_see()`, so other states and threads keep printing normally and captures
+    /// don't wait for each other. Anything else written to stdout, such as the output of C or
+    /// Rust functions, is not; use [`ring_capture_output`](crate::ring_capture_output) for that. A capture started inside
+    /// another one on the same state runs `f` directly, leaving its output to the outer capture.
+    pub fn capture<R>(&mut self, f: impl FnOnce(&mut Self) -> R) -> (R, Vec<u8>) {
+        let vm = self.vm;
+        let started = OUTPUTS.with(|outputs| match outputs.borrow_mut().entry(vm) {
+            Entry::Occupied(_) => false,
+            Entry::Vacant(entry) => {
+                entry.insert(Vec::new());
+                true
+            }
+        });
+        if !started {
+            return (f(self), Vec::new());
+        }
+        self.run_saved(RINGVM_SEE, self.see, RING_RS_SEEON);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| f(self)));
+        self.run_saved(RINGVM_SEE, self.see, RING_RS_SEEOFF);
+        let output = OUTPUTS
+            .with(|outputs| outputs.borrow_mut().remove(&vm))
+            .unwrap_or_default();
+        match result {
+            Ok(value) => (value, output),
+            Err(payload) => std::panic::resume_unwind(payload),
+        }
+    }
+
+    /// Send the output of every later [`run`](Self::run), [`run_file`](Self::run_file) and
+    /// [`call`](Self::call) to `handler` instead of the process's stdout. See
+    /// [`capture`](Self::capture) for what is captured.
+    ///
+    /// The handler is called once per run, after it finishes, and not at all if nothing was
+    /// printed.
+    pub fn set_output_handler(&mut self, handler: impl FnMut(&[u8]) + 'static) {
+        self.output = Some(Box::new(handler));
+    }
+
+    /// Print to the process's stdout again.
+    pub fn clear_output_handler(&mut self) {
+        self.output = None;
+    }
+
+    fn with_output<R>(&mut self, f: impl FnOnce(&mut Self) -> R) -> R {
+        if let Some(mut handler) = self.output.take() {
+            let (result, output) = self.capture(f);
+            if !output.is_empty() {
+                handler(&output);
+            }
+            self.output.get_or_insert(handler);
+            return result;
+        }
+        f(self)
+    }
+
+    /// Make a Rust closure callable from Ring code as `name()`.
+    ///
+    /// The closure belongs to this state, so it can capture whatever the state needs (handles,
+    /// configuration, per-tenant context) and is dropped with the state. Its `Ok` value is
+    /// returned to Ring and its `Err` is raised as a Ring error, which scripts can handle with
+    /// `try/catch`. Registering a name again replaces the closure; any other function already
+    /// defined under `name`, such as a standard function, is a [`RegisterError::Duplicate`].
+    ///
+    /// ```rust,ignore
+    /// let prefix = String::from("Hello, ");
+    /// ring.register_fn("greet", move |call| {
+    ///     let name: &str = call.arg(1)?;
+    ///     Ok::<_, ArgError>(format!("{}{}", prefix, name))
+    /// })?;
+    /// ring.run(r#"? greet("Ring")"#)?;
+    /// ```
+    pub fn register_fn<F, T, E>(&mut self, name: &str, func: F) -> Result<(), RegisterError>
+    where
+        F: Fn(&mut RingCall<'_>) -> Result<T, E> + 'static,
+        T: IntoRing,
+        E: fmt::Display,
+    {
+        if !crate::ring_is_valid_function_name(name) {
+            return Err(RegisterError::InvalidName(name.to_string()));
+        }
+        let existing = unsafe { crate::builder::find_cfunction(self.vm, name.as_bytes()) };
+        match existing {
+            Some(f) => {
+                let is_closure = unsafe { (*f).pFunc }
+                    .is_some_and(|f| std::ptr::fn_addr_eq(f, ring_rs_hostfn as RingFunc));
+                if !is_closure {
+                    return Err(RegisterError::Duplicate(name.to_string()));
+                }
+            }
+            None => crate::ring_register_function_str(self.state, name, ring_rs_hostfn)?,
+        }
+        let func: HostFn = Rc::new(move |call: &mut RingCall<'_>| match func(call) {
+            Ok(value) => call.ret(value),
+            Err(e) => call.error(e),
+        });
+        // The replaced closure is dropped outside the registry borrow
+        let previous = HOST_FNS.with(|fns| {
+            fns.borrow_mut()
+                .entry(self.vm)
+                .or_default()
+                .insert(name.to_string(), func)
+        });
+        drop(previous);
+        Ok(())
+    }
+
+    /// Read a global variable and convert it to `T`.
+    ///
+    /// ```rust,ignore
+    /// ring.run("total = 6 * 7  name = 'Ring'")?;
+    /// let total: f64 = ring.get("total")?;
+    /// let name: String = ring.get("name")?;
+    /// ```
+    pub fn get<T: FromRing>(&self, name: &str) -> Result<T, RingError> {
+        let var = self
+            .find_var(&name.to_ascii_lowercase())
+            .ok_or_else(|| RingError::UndefinedVariable(name.to_string()))?;
+        T::from_ring(RingValue::from_list_item(var, RING_VAR_VALUE))
+    }
+
+    /// Define or overwrite a global variable.
+    ///
+    /// The value is stored directly, not formatted into source code, so strings may hold any
+    /// bytes, including quotes.
+    pub fn set(&mut self, name: &str, value: impl IntoRing) -> Result<(), RingError> {
+        let var = self.new_var(&name.to_ascii_lowercase());
+        if var.is_null() {
+            return Err(RingError::UndefinedVariable(name.to_string()));
+        }
+        value.into_ring().set_variable(var);
+        Ok(())
+    }
+
+    /// Find a global variable. Returns `None` if it is not defined.
+    pub fn find_var(&self, name: &str) -> Option<RingList> {
+        let var = ring_state_findvar_str(self.state, name);
+        if var.is_null() { None } else { Some(var) }
+    }
+
+    /// Define a global variable (or return the existing one) and return its variable list.
+    pub fn new_var(&mut self, name: &str) -> RingList {
+        ring_state_newvar_str(self.state, name)
+    }
+}
+
+impl Default for Ring {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for Ring {
+    fn drop(&mut self) {
+        ring_state_delete(self.state);
+        crate::memory::release_guard(self.vm);
+        // Dropped outside the registry borrow, since a closure's captures may own another `Ring`
+        let closures = HOST_FNS.with(|fns| fns.borrow_mut().remove(&self.vm));
+        drop(closures);
+    }
+}
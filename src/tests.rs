@@ -2,7 +2,9 @@
 
 use std::mem::size_of;
 
-use crate::ffi::{ByteCode, CFunction, FuncCall, Item, List, String as RingString, VM};
+use crate::ffi::{
+    ByteCode, CFunction, FuncCall, Item, List, RingStateHead, String as RingString, VM,
+};
 
 #[test]
 fn test_struct_sizes() {
@@ -13,6 +15,894 @@ fn test_struct_sizes() {
     assert_eq!(size_of::<FuncCall>(), 104, "FuncCall struct size mismatch");
     assert_eq!(size_of::<ByteCode>(), 24, "ByteCode struct size mismatch");
     assert_eq!(size_of::<CFunction>(), 24, "CFunction struct size mismatch");
+    assert_eq!(
+        size_of::<RingStateHead>(),
+        64,
+        "RingStateHead struct size mismatch"
+    );
+}
+
+/// Verify `RingStateHead` matches the library's state: the VM read through it is the one Ring
+/// passes to registered functions
+#[test]
+fn test_state_head_layout() {
+    let ring = crate::Ring::new();
+    assert!(!ring.vm().is_null());
+    assert_eq!(crate::state::state_vm(ring.as_ptr()), Some(ring.vm()));
+}
+
+/// Verify runtime errors are returned with the line and function they were raised in
+#[test]
+fn test_vm_run_error_location() {
+    let mut ring = crate::Ring::new();
+    match ring.run("x = 1\ny = 0\nz = x / y") {
+        Err(crate::RingError::Runtime { line, .. }) => assert_eq!(line, 3),
+        other => panic!("expected a runtime error, got {:?}", other),
+    }
+
+    ring.register_fn("fail", |_| Err::<(), _>("failed"))
+        .unwrap();
+    match ring.run("func outer\n    inner()\nfunc inner\n    x = 1\n    fail()") {
+        Ok(()) => {}
+        other => panic!("defining functions failed: {:?}", other),
+    }
+    match ring.run("outer()") {
+        Err(crate::RingError::Runtime {
+            message,
+            line,
+            call_stack,
+            ..
+        }) => {
+            assert_eq!(message, "failed");
+            assert_eq!(line, 5);
+            assert!(call_stack.iter().any(|name| name == "inner"));
+        }
+        other => panic!("expected a runtime error, got {:?}", other),
+    }
+    assert!(ring.run("ok = 1").is_ok());
+}
+
+/// Verify dropping a `Ring` deletes its state and releases its closures, and that `into_raw`
+/// releases the closures while keeping the state
+#[test]
+fn test_vm_drop_releases() {
+    use std::ffi::c_void;
+    use std::rc::Rc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static FREES: AtomicUsize = AtomicUsize::new(0);
+
+    extern "C" fn free(_state: *mut c_void, _ptr: *mut c_void) {
+        FREES.fetch_add(1, Ordering::SeqCst);
+    }
+
+    let captured = Rc::new(());
+    let mut ring = crate::Ring::new();
+    let held = captured.clone();
+    ring.register_fn("rs_held", move |_call| {
+        Ok::<_, crate::ArgError>(Rc::strong_count(&held) as f64)
+    })
+    .unwrap();
+    ring.set(
+        "owned",
+        crate::RingValue::CPointer {
+            type_name: "Owned".to_string(),
+            ptr: std::ptr::NonNull::<u8>::dangling().as_ptr() as *mut c_void,
+            free_func: Some(unsafe { crate::RingFreeFunc::new(free) }),
+        },
+    )
+    .unwrap();
+    ring.run("x = rs_held()").unwrap();
+    assert_eq!(Rc::strong_count(&captured), 2);
+    drop(ring);
+    assert_eq!(Rc::strong_count(&captured), 1);
+    assert_eq!(FREES.load(Ordering::SeqCst), 1);
+
+    let mut ring = crate::Ring::new();
+    let held = captured.clone();
+    ring.register_fn("rs_held", move |_call| {
+        Ok::<_, crate::ArgError>(Rc::strong_count(&held) as f64)
+    })
+    .unwrap();
+    let state = ring.into_raw();
+    assert_eq!(Rc::strong_count(&captured), 1);
+    crate::ring_state_delete(state);
+}
+
+/// Verify Ring::call passes lists, returns values and reports errors
+#[test]
+fn test_vm_call() {
+    let mut ring = crate::Ring::new();
+    ring.run(
+        "func add a, b return a + b\n\
+         func total items\n    n = 0\n    for x in items n += x next\n    return n\n\
+         func echo x return x
+         func fail return 1 / 0",
+    )
+    .unwrap();
+    assert_eq!(ring.call::<f64>("add", (1, 2)).unwrap(), 3.0);
+    assert_eq!(ring.call::<String>("ADD", ("a", "b")).unwrap(), "ab");
+    assert_eq!(
+        ring.call::<String>("add", ("\"' +", "\n")).unwrap(),
+        "\"' +\n"
+    );
+    assert_eq!(ring.call::<f64>("total", (vec![1, 2, 3],)).unwrap(), 6.0);
+    assert_eq!(
+        ring.call::<Vec<Vec<i32>>>("echo", (vec![vec![1], vec![2, 3]],))
+            .unwrap(),
+        vec![vec![1], vec![2, 3]]
+    );
+    assert!(ring.find_var("ring_rs_args").is_none());
+    assert!(ring.find_var("ring_rs_result").is_none());
+    assert!(matches!(
+        ring.call::<f64>("fail", ()),
+        Err(crate::RingError::Runtime { .. })
+    ));
+    assert!(matches!(
+        ring.call::<f64>("missing", ()),
+        Err(crate::RingError::UndefinedFunction(_))
+    ));
+
+    let too_many = vec![0; crate::ffi::RING_VM_STACK_SIZE];
+    assert!(matches!(
+        crate::ring_vm_call::<_, f64>(ring.vm(), "add", too_many),
+        Err(crate::RingError::StackOverflow)
+    ));
+    assert_eq!(
+        crate::ring_vm_call::<_, f64>(ring.vm(), "add", (2, 3)).unwrap(),
+        5.0
+    );
+}
+
+/// Verify Ring::set stores quotes, backticks, newlines and NUL bytes as-is, for both get and
+/// Ring code
+#[test]
+fn test_vm_set_get_bytes() {
+    let text: &[u8] = b"\"'`\n\0end";
+    let mut ring = crate::Ring::new();
+    ring.set("text", crate::RingValue::from(text)).unwrap();
+    assert_eq!(
+        ring.get::<crate::RingValue>("text").unwrap(),
+        crate::RingValue::from(text)
+    );
+    ring.run("size = len(text)\ncodes = []\nfor c in text codes + ascii(c) next")
+        .unwrap();
+    assert_eq!(ring.get::<usize>("size").unwrap(), text.len());
+    assert_eq!(ring.get::<Vec<u8>>("codes").unwrap(), text.to_vec());
+}
+
+/// Verify run_file calls main(), with and without limits, and reports the file in errors
+#[test]
+fn test_vm_run_file() {
+    let folder = std::env::temp_dir().join("ring_lang_rs_test_vm_run_file");
+    std::fs::create_dir_all(&folder).unwrap();
+    let script = folder.join("main.ring");
+    std::fs::write(&script, "x = 1\nfunc main\n    y = x + 1\n    z = y / 0\n").unwrap();
+
+    let mut ring = crate::Ring::new();
+    match ring.run_file(&script) {
+        Err(crate::RingError::Runtime { file, line, .. }) => {
+            assert_eq!(line, 4);
+            assert!(file.is_some_and(|file| file.ends_with("main.ring")));
+        }
+        other => panic!("expected a runtime error, got {:?}", other),
+    }
+    assert_eq!(ring.get::<f64>("y").ok(), None);
+    assert_eq!(ring.get::<f64>("x").unwrap(), 1.0);
+
+    let current = std::env::current_dir().unwrap();
+    std::fs::write(folder.join("lib.ring"), "func helper return 41\n").unwrap();
+    std::fs::write(
+        &script,
+        "load \"lib.ring\"\nfunc main\n    y = helper() + 1\n",
+    )
+    .unwrap();
+    let mut ring = crate::StateBuilder::new()
+        .disable(crate::FunctionGroup::List)
+        .disable(crate::FunctionGroup::RefMeta)
+        .build()
+        .unwrap();
+    ring.run_file(&script).unwrap();
+    assert_eq!(ring.get::<f64>("y").unwrap(), 42.0);
+    assert_eq!(std::env::current_dir().unwrap(), current);
+
+    // Stepped from Rust instead of running in the VM's main loop
+    let mut ring = crate::Ring::new();
+    ring.set_step_limit(Some(1_000_000));
+    ring.run_file(&script).unwrap();
+    assert_eq!(ring.get::<f64>("y").unwrap(), 42.0);
+    std::fs::remove_dir_all(&folder).unwrap();
+}
+
+/// Verify loads of files next to a script are made absolute, leaving strings, comments and
+/// loads of other files alone
+#[test]
+fn test_resolve_loads() {
+    let folder = std::env::temp_dir().join("ring_lang_rs_test_resolve_loads");
+    std::fs::create_dir_all(&folder).unwrap();
+    std::fs::write(folder.join("lib.ring"), "").unwrap();
+    let lib = folder.join("lib.ring");
+    let lib = lib.to_str().unwrap();
+
+    let code = b"Load \"lib.ring\"\nload package 'lib.ring'\nload \"stdlib.ring\"\n\
+        # load \"lib.ring\"\nx = \"load 'lib.ring'\"\n/* load 'lib.ring' */";
+    let expected = format!(
+        "Load \"{0}\"\nload package \"{0}\"\nload \"stdlib.ring\"\n\
+         # load \"lib.ring\"\nx = \"load 'lib.ring'\"\n/* load 'lib.ring' */",
+        lib
+    );
+    assert_eq!(
+        String::from_utf8(crate::state::resolve_loads(code, &folder)).unwrap(),
+        expected
+    );
+    std::fs::remove_dir_all(&folder).unwrap();
+}
+
+/// Verify ListRef bounds checks, inserts and removes, and read-only sub-lists
+#[test]
+fn test_vm_list_ref() {
+    use crate::{OwnedList, RingValue, ValueRef};
+
+    let mut list = OwnedList::new();
+    assert!(list.get(0).is_none());
+    list.push_str("a");
+    assert!(list.insert_int(0, 1));
+    assert!(list.insert_double(2, 2.5));
+    assert!(!list.insert_int(4, 9));
+    assert_eq!(list.len(), 3);
+    assert!(matches!(list.get(0), Some(ValueRef::Int(1))));
+    assert_eq!(list.get(1).and_then(|v| v.as_str()), Some("a"));
+    assert!(list.get(3).is_none());
+
+    assert_eq!(list.remove(3), None);
+    assert_eq!(list.remove(1), Some(RingValue::from("a")));
+    assert_eq!(list.len(), 2);
+    assert_eq!(list.get(1).and_then(|v| v.as_f64()), Some(2.5));
+
+    list.insert_list(0).unwrap().push_int(7);
+    let sub = list.get(0).and_then(|v| v.as_list()).unwrap();
+    assert_eq!(sub.len(), 1);
+    assert!(sub.get(1).is_none());
+    assert!(list.get_list_mut(1).is_none());
+    list.get_list_mut(0).unwrap().clear();
+    assert_eq!(list.get(0).and_then(|v| v.as_list()).unwrap().len(), 0);
+
+    list.clear();
+    assert!(list.is_empty());
+}
+
+/// Verify a list that refers back to itself, and very deep nesting, are cut off when copied
+#[test]
+fn test_vm_value_cycle() {
+    use crate::{RING_VALUE_MAX_DEPTH, RingValue};
+
+    let list = crate::ring_list_new(0);
+    crate::ring_list_addint(list, 1);
+    crate::ring_list_addpointer(list, list as *mut std::ffi::c_void);
+    let item = crate::ring_list_getitem(list, 2);
+    unsafe { (*item).set_nObjectType(crate::RING_OBJTYPE_SUBLIST) };
+    assert_eq!(
+        RingValue::from_list(list),
+        RingValue::List(vec![RingValue::Int(1), RingValue::Nothing])
+    );
+    crate::ring_list_delete(list);
+
+    let list = crate::ring_list_new(0);
+    let mut inner = list;
+    for _ in 0..RING_VALUE_MAX_DEPTH * 4 {
+        inner = crate::ring_list_newlist(inner);
+    }
+    let mut value = RingValue::from_list(list);
+    let mut depth = 0;
+    while let RingValue::List(mut items) = value {
+        depth += 1;
+        value = items.pop().unwrap_or_default();
+    }
+    assert_eq!(depth, RING_VALUE_MAX_DEPTH);
+    crate::ring_list_delete(list);
+}
+
+/// Verify infinite loops stop with a timeout, including single-line loops and loops inside a
+/// `try` or a called function
+#[test]
+fn test_vm_limits() {
+    use std::time::Duration;
+
+    let looping = "while true\n    x = 1\nend";
+    let mut ring = crate::Ring::new();
+    ring.set_timeout(Some(Duration::from_millis(100)));
+    assert!(matches!(ring.run(looping), Err(crate::RingError::Timeout)));
+    assert!(matches!(
+        ring.run("while true end"),
+        Err(crate::RingError::Timeout)
+    ));
+    let escape = format!("try\n{}\ncatch done", looping);
+    assert!(matches!(ring.run(&escape), Err(crate::RingError::Timeout)));
+    ring.run("func spin while true end").unwrap();
+    assert!(matches!(
+        ring.call::<()>("spin", ()),
+        Err(crate::RingError::Timeout)
+    ));
+
+    ring.set_timeout(None);
+    ring.set_step_limit(Some(1000));
+    assert!(matches!(ring.run(looping), Err(crate::RingError::Timeout)));
+    assert!(matches!(
+        ring.run("for i = 1 to 1e12 x = i next"),
+        Err(crate::RingError::Timeout)
+    ));
+    assert!(ring.run("for i = 1 to 10\n    x = i\nnext").is_ok());
+    assert_eq!(ring.get::<f64>("x").unwrap(), 10.0);
+
+    ring.set_step_limit(None);
+    assert!(ring.run("x = 1").is_ok());
+}
+
+/// Verify the memory limit stops large requests up front and strings that keep doubling, while
+/// small requests that are freed again add up to more than the limit without failing
+#[test]
+fn test_vm_memory_limit() {
+    let mut ring = crate::Ring::new();
+    ring.set_memory_limit(Some(16 * 1024 * 1024));
+    for code in [
+        "x = list(1000000000)",
+        "x = space(1000000000)",
+        r#"x = copy("abc", 1000000000)"#,
+        "s = \"x\"\nwhile true\n    s += s\nend",
+    ] {
+        assert!(
+            matches!(ring.run(code), Err(crate::RingError::OutOfMemory)),
+            "{}",
+            code
+        );
+    }
+    assert!(ring.run(r#"x = copy("abc", 10)"#).is_ok());
+    assert!(ring.run("for i = 1 to 100000 x = list(100) next").is_ok());
+}
+
+/// Verify each state keeps its own allocation guard: wrappers call the guarded state's own
+/// functions, and dropping one state leaves the others' guards working
+#[test]
+fn test_vm_memory_guard_per_state() {
+    let limit = Some(16 * 1024 * 1024);
+    let mut denied = crate::StateBuilder::new().deny("space").build().unwrap();
+    denied.set_memory_limit(limit);
+    let mut ring = crate::Ring::new();
+    ring.set_memory_limit(limit);
+    assert!(denied.run("x = space(10)").is_err());
+    ring.run("x = len(space(10))").unwrap();
+    assert_eq!(ring.get::<f64>("x").unwrap(), 10.0);
+    drop(denied);
+    ring.run("x = len(list(3)) + len(copy(\"ab\", 2))").unwrap();
+    assert_eq!(ring.get::<f64>("x").unwrap(), 7.0);
+    assert!(matches!(
+        ring.run("x = space(1000000000)"),
+        Err(crate::RingError::OutOfMemory)
+    ));
+}
+
+/// Verify output is captured per state, leaving other states printing normally
+#[test]
+fn test_vm_capture() {
+    let mut ring = crate::Ring::new();
+    let mut other = crate::Ring::new();
+    let ((), output) = ring.capture(|ring| {
+        ring.run(r#"? "a" see 1.5 see nl see [1, "b"]"#).unwrap();
+        let (result, inner) = other.run_captured(r#"see "other""#);
+        result.unwrap();
+        assert_eq!(inner, b"other");
+    });
+    assert_eq!(output, b"a\n1.50\n1\nb\n");
+
+    let (result, output) = ring.run_captured(r#"x = 1"#);
+    assert!(result.is_ok());
+    assert!(output.is_empty());
+}
+
+/// Verify `Ring::capture` records the same text as native `see` for nested lists and objects
+#[test]
+fn test_vm_capture_matches_see() {
+    let code = "see [1, [2, [3, \"x\"]], \"y\"]\n\
+                see new point\n\
+                ? 2.5\n\
+                class point x = 1.5 y = [2, [3, \"z\"]] z = new inner\n\
+                class inner a = \"b\" c = [4]";
+
+    let mut native = crate::Ring::new();
+    let (result, expected) = crate::ring_capture_output(|| native.run(code));
+    result.unwrap();
+    let mut ring = crate::Ring::new();
+    let (result, output) = ring.run_captured(code);
+    result.unwrap();
+    assert_eq!(
+        String::from_utf8_lossy(&output),
+        String::from_utf8_lossy(&expected)
+    );
+}
+
+/// Verify denied and disabled functions raise errors, sandboxed states keep normal code
+/// working, and capture prints objects without the RefMeta functions
+#[test]
+fn test_vm_state_builder() {
+    use crate::{FunctionGroup, StateBuilder};
+
+    let mut ring = StateBuilder::new()
+        .deny("SQRT")
+        .disable(FunctionGroup::Os)
+        .build()
+        .unwrap();
+    assert!(ring.run("x = sqrt(4)").is_err());
+    assert!(ring.run(r#"system("echo")"#).is_err());
+    ring.run("x = sin(0)").unwrap();
+    assert_eq!(ring.get::<f64>("x").unwrap(), 0.0);
+
+    let mut sandbox = StateBuilder::sandboxed().build().unwrap();
+    match sandbox.run(r#"system("echo")"#) {
+        Err(e) => assert_eq!(
+            e.message(),
+            "Calling function disabled in this state: system"
+        ),
+        Ok(()) => panic!("system() ran in a sandbox"),
+    }
+    assert!(sandbox.run(r#"ringvm_see("")"#).is_err());
+    assert!(sandbox.run(r#"ringvm_settrace("")"#).is_err());
+    sandbox
+        .run("x = len([1, 2]) + sqrt(4)\nfunc twice n return n * 2")
+        .unwrap();
+    assert_eq!(sandbox.call::<f64>("twice", (21,)).unwrap(), 42.0);
+    assert_eq!(sandbox.get::<f64>("x").unwrap(), 4.0);
+
+    let mut ring = StateBuilder::new()
+        .disable(FunctionGroup::List)
+        .disable(FunctionGroup::RefMeta)
+        .build()
+        .unwrap();
+    assert!(ring.run("x = classes()").is_err());
+    let (result, output) = ring.run_captured("see new point\nclass point x = 1 y = [2, 3]");
+    result.unwrap();
+    assert_eq!(output, b"x: 1\ny: 2\n3\n\n");
+}
+
+/// Verify registration refuses names already in a state's function table, and that a new
+/// state starts without the names of a deleted one
+#[test]
+fn test_vm_register_duplicate() {
+    use crate::{RegisterError, ring_register_function_str};
+
+    extern "C" fn noop(_: *mut std::ffi::c_void) {}
+
+    let ring = crate::Ring::new();
+    assert_eq!(crate::state::state_vm(ring.as_ptr()), Some(ring.vm()));
+    assert_eq!(
+        ring_register_function_str(ring.as_ptr(), "len", noop),
+        Err(RegisterError::Duplicate("len".to_string()))
+    );
+    assert!(ring_register_function_str(ring.as_ptr(), "rs_noop", noop).is_ok());
+    assert!(ring_register_function_str(ring.as_ptr(), "rs_noop", noop).is_err());
+    drop(ring);
+
+    let ring = crate::Ring::new();
+    assert!(ring_register_function_str(ring.as_ptr(), "rs_noop", noop).is_ok());
+}
+
+/// Verify a managed object outlives the variable it was created in while a copy of the pointer
+/// remains, and is dropped once the last copy is gone
+#[test]
+fn test_vm_object_copy() {
+    use std::ffi::c_void;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use crate::{RingType, ring_api_getobject, ring_api_retobject, ring_register_function_str};
+
+    static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+    struct Tracked(f64);
+    impl RingType for Tracked {
+        const TYPE_NAME: &'static str = "Tracked";
+    }
+    impl Drop for Tracked {
+        fn drop(&mut self) {
+            DROPS.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    extern "C" fn make(p: *mut c_void) {
+        ring_api_retobject(p, Box::new(Tracked(7.0)));
+    }
+    extern "C" fn value(p: *mut c_void) {
+        match unsafe { ring_api_getobject::<Tracked>(&p, 1) } {
+            Ok(tracked) => crate::ring_api_retnumber(p, tracked.0),
+            Err(e) => crate::ring_api_error_str(p, &e.to_string()),
+        }
+    }
+
+    let mut ring = crate::Ring::new();
+    ring_register_function_str(ring.as_ptr(), "rs_make", make).unwrap();
+    ring_register_function_str(ring.as_ptr(), "rs_value", value).unwrap();
+    ring.run("a = rs_make()\nb = a\na = NULL\ncallgc()\nx = rs_value(b)")
+        .unwrap();
+    assert_eq!(ring.get::<f64>("x").unwrap(), 7.0);
+    assert_eq!(DROPS.load(Ordering::SeqCst), 0);
+    ring.run("b = NULL\ncallgc()").unwrap();
+    assert_eq!(DROPS.load(Ordering::SeqCst), 1);
+}
+
+/// Verify a managed C pointer in a `RingValue` is freed exactly once, however often the value is
+/// returned, stored or cloned
+#[test]
+fn test_vm_value_free_once() {
+    use std::ffi::c_void;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use crate::{RingFreeFunc, RingValue, ring_api_retvalue, ring_register_function_str};
+
+    static FREES: AtomicUsize = AtomicUsize::new(0);
+
+    extern "C" fn free(_state: *mut c_void, ptr: *mut c_void) {
+        FREES.fetch_add(1, Ordering::SeqCst);
+        unsafe { drop(Box::from_raw(ptr as *mut u32)) };
+    }
+
+    thread_local! {
+        static VALUE: RingValue = RingValue::CPointer {
+            type_name: "Counted".to_string(),
+            ptr: Box::into_raw(Box::new(7u32)) as *mut c_void,
+            free_func: Some(unsafe { RingFreeFunc::new(free) }),
+        };
+    }
+
+    extern "C" fn counted(p: *mut c_void) {
+        VALUE.with(|value| ring_api_retvalue(p, value));
+    }
+
+    let mut ring = crate::Ring::new();
+    ring_register_function_str(ring.as_ptr(), "rs_counted", counted).unwrap();
+    ring.run("a = rs_counted()\nb = rs_counted()\nc = [rs_counted()]")
+        .unwrap();
+    VALUE.with(|value| {
+        let copy = value.clone();
+        crate::ring_list_delete(value.to_list());
+        crate::ring_list_delete(copy.to_list());
+        match value {
+            RingValue::CPointer { free_func, .. } => {
+                assert!(!free_func.as_ref().unwrap().is_owned())
+            }
+            other => panic!("expected a C pointer, got {:?}", other),
+        }
+    });
+    assert_eq!(FREES.load(Ordering::SeqCst), 0);
+    ring.run("a = NULL\nb = NULL\nc = NULL\ncallgc()").unwrap();
+    assert_eq!(FREES.load(Ordering::SeqCst), 1);
+}
+
+/// Verify an object of the same type created by another library is rejected instead of cast
+#[test]
+fn test_vm_object_other_library() {
+    use std::ffi::c_void;
+    use std::sync::Mutex;
+
+    use crate::object::{TypeNames, registry_type_name};
+    use crate::{RingType, ring_api_getobject, ring_api_retobject, ring_register_function_str};
+
+    struct Shared(f64);
+    impl RingType for Shared {
+        const TYPE_NAME: &'static str = "Shared";
+    }
+
+    static OTHER: Mutex<Option<TypeNames>> = Mutex::new(None);
+
+    extern "C" fn make(p: *mut c_void) {
+        ring_api_retobject(p, Box::new(Shared(1.0)));
+    }
+    extern "C" fn make_other(p: *mut c_void) {
+        crate::ring_api_retcpointer2(
+            p,
+            Box::into_raw(Box::new(Shared(2.0))) as *mut c_void,
+            registry_type_name::<Shared>(&OTHER).to_bytes_with_nul(),
+            None,
+        );
+    }
+    extern "C" fn value(p: *mut c_void) {
+        match unsafe { ring_api_getobject::<Shared>(&p, 1) } {
+            Ok(shared) => crate::ring_api_retnumber(p, shared.0),
+            Err(e) => crate::ring_api_error_str(p, &e.to_string()),
+        }
+    }
+
+    let mut ring = crate::Ring::new();
+    ring_register_function_str(ring.as_ptr(), "rs_make", make).unwrap();
+    ring_register_function_str(ring.as_ptr(), "rs_make_other", make_other).unwrap();
+    ring_register_function_str(ring.as_ptr(), "rs_value", value).unwrap();
+    ring.run("x = rs_value(rs_make())").unwrap();
+    assert_eq!(ring.get::<f64>("x").unwrap(), 1.0);
+    assert!(ring.run("y = rs_value(rs_make_other())").is_err());
+}
+
+/// The extension used by the `test_vm_extension_*` tests. Its `ringlib_init` is exported
+/// unmangled, so the test binary can only hold one.
+mod ext {
+    use crate::*;
+
+    ring_lang_codegen::ring_extension! {
+        prefix: "rsext";
+
+        #[derive(Default)]
+        #[ring(prefix = "rsdel")]
+        pub struct Managed {
+            pub value: i64,
+        }
+
+        #[derive(Default)]
+        #[ring(unmanaged, prefix = "rsdel")]
+        pub struct Unmanaged {
+            pub value: i64,
+        }
+
+        #[derive(Default)]
+        #[ring(prefix = "rsh", name = "handle")]
+        pub struct Handle {
+            pub id: i64,
+            #[ring(name = "label")]
+            pub title: String,
+            #[ring(readonly)]
+            pub size: i64,
+            #[ring(skip)]
+            pub cache: Vec<u8>,
+        }
+
+        impl Handle {
+            #[ring(name = "open")]
+            pub fn new(id: i64) -> Self {
+                Handle {
+                    id,
+                    size: 3,
+                    ..Default::default()
+                }
+            }
+
+            #[ring(skip)]
+            pub fn secret(&self) -> usize {
+                self.cache.len()
+            }
+
+            pub fn grow(&mut self, #[ring(default = 1)] by: i64) -> i64 {
+                self.size += by;
+                self.size
+            }
+
+            pub fn tag(&mut self, label: &str, values: RingArgs) -> usize {
+                self.title = label.to_string();
+                values.len()
+            }
+        }
+
+        #[ring(name = "sum")]
+        pub fn add(a: i64, b: i64) -> i64 {
+            a + b
+        }
+
+        #[ring(skip)]
+        pub fn hidden() -> i64 {
+            0
+        }
+
+        pub fn total(args: RingArgs) -> Result<f64, ArgError> {
+            (0..args.len()).map(|i| args.get_as::<f64>(i)).sum()
+        }
+
+        pub fn kinds(sep: &str, values: &[RingValue]) -> String {
+            let kinds: Vec<&str> = values.iter().map(RingValue::type_name).collect();
+            kinds.join(sep)
+        }
+
+        pub fn explode(message: &str) -> i64 {
+            panic!("{}", message)
+        }
+
+        pub fn pad(text: &str, #[ring(default = 10)] width: usize, fill: Option<String>) -> String {
+            let fill = fill.unwrap_or_else(|| " ".to_string());
+            format!("{}{}", fill.repeat(width.saturating_sub(text.len())), text)
+        }
+    }
+}
+
+/// Verify a generated `_delete` nulls the pointer, so deleting twice is harmless for managed and
+/// unmanaged structs alike
+#[test]
+fn test_vm_extension_delete() {
+    let mut ring = crate::Ring::new();
+    ext::ringlib_init(ring.as_ptr());
+    for name in ["managed", "unmanaged"] {
+        let code = format!(
+            "p = rsdel_{0}_new()\nrsdel_{0}_delete(p)\nrsdel_{0}_delete(p)",
+            name
+        );
+        ring.run(&code).unwrap();
+        assert!(ring.run(&format!("rsdel_{}_get_value(p)", name)).is_err());
+    }
+}
+
+/// Verify `#[ring(...)]` renames, prefixes and skips decide which functions an extension
+/// registers
+#[test]
+fn test_vm_extension_names() {
+    let mut ring = crate::Ring::new();
+    ext::ringlib_init(ring.as_ptr());
+    ring.run(
+        "h = rsh_handle_open(7)\n\
+         rsh_handle_set_label(h, \"seven\")\n\
+         id = rsh_handle_get_id(h)\n\
+         label = rsh_handle_get_label(h)\n\
+         size = rsh_handle_get_size(h)\n\
+         total = rsext_sum(2, 3)",
+    )
+    .unwrap();
+    assert_eq!(ring.get::<i64>("id").unwrap(), 7);
+    assert_eq!(ring.get::<String>("label").unwrap(), "seven");
+    assert_eq!(ring.get::<i64>("size").unwrap(), 3);
+    assert_eq!(ring.get::<i64>("total").unwrap(), 5);
+    for missing in [
+        "rsext_handle_new()",
+        "rsh_handle_new()",
+        "rsh_handle_get_title(h)",
+        "rsh_handle_set_size(h, 1)",
+        "rsh_handle_get_cache(h)",
+        "rsh_handle_secret(h)",
+        "rsext_add(2, 3)",
+        "rsext_hidden()",
+    ] {
+        assert!(ring.run(missing).is_err(), "{}", missing);
+    }
+    assert_eq!(ext::Handle::default().secret(), 0);
+    assert_eq!(ext::hidden(), 0);
+}
+
+/// Verify trailing `Option` and `#[ring(default = ...)]` parameters can be left out, falling
+/// back to `None` or the default, while too few or too many arguments are still rejected
+#[test]
+fn test_vm_extension_optional() {
+    let mut ring = crate::Ring::new();
+    ext::ringlib_init(ring.as_ptr());
+    for (call, expected) in [
+        (r#"rsext_pad("7")"#, "         7"),
+        (r#"rsext_pad("7", 3)"#, "  7"),
+        (r#"rsext_pad("7", 3, "0")"#, "007"),
+        (r#"rsext_pad("7", 3, NULL)"#, "  7"),
+    ] {
+        ring.run(&format!("x = {}", call)).unwrap();
+        assert_eq!(ring.get::<String>("x").unwrap(), expected, "{}", call);
+    }
+    for call in ["rsext_pad()", r#"rsext_pad("7", 3, "0", 1)"#] {
+        assert!(ring.run(call).is_err(), "{}", call);
+    }
+
+    ring.run("h = rsh_handle_open(1)\nx = rsh_handle_grow(h)\ny = rsh_handle_grow(h, 5)")
+        .unwrap();
+    assert_eq!(ring.get::<i64>("x").unwrap(), 4);
+    assert_eq!(ring.get::<i64>("y").unwrap(), 9);
+    assert!(ring.run("rsh_handle_grow(h, 1, 2)").is_err());
+}
+
+/// Verify variadic parameters take every argument after the named ones, and that methods also
+/// take them as one list, before the named parameters, through their `_list` function
+#[test]
+fn test_vm_extension_variadic() {
+    let mut ring = crate::Ring::new();
+    ext::ringlib_init(ring.as_ptr());
+    ring.run(
+        "a = rsext_total()\n\
+         b = rsext_total(1, 2, 3.5)\n\
+         c = rsext_kinds(\",\")\n\
+         d = rsext_kinds(\",\", 1, \"x\", [2, 3])",
+    )
+    .unwrap();
+    assert_eq!(ring.get::<f64>("a").unwrap(), 0.0);
+    assert_eq!(ring.get::<f64>("b").unwrap(), 6.5);
+    assert_eq!(ring.get::<String>("c").unwrap(), "");
+    assert_eq!(ring.get::<String>("d").unwrap(), "number,string,list");
+    assert!(ring.run(r#"rsext_total(1, "x")"#).is_err());
+    assert!(ring.run("rsext_kinds()").is_err());
+
+    ring.run(
+        "h = rsh_handle_open(1)\n\
+         a = rsh_handle_tag(h, \"first\")\n\
+         b = rsh_handle_tag(h, \"second\", 1, [2, 3], \"y\")\n\
+         c = rsh_handle_tag_list(h, [1, [2, 3], \"y\", 4], \"third\")\n\
+         label = rsh_handle_get_label(h)",
+    )
+    .unwrap();
+    assert_eq!(ring.get::<f64>("a").unwrap(), 0.0);
+    assert_eq!(ring.get::<f64>("b").unwrap(), 3.0);
+    assert_eq!(ring.get::<f64>("c").unwrap(), 4.0);
+    assert_eq!(ring.get::<String>("label").unwrap(), "third");
+    assert!(ring.run(r#"rsh_handle_tag_list(h, "x", "third")"#).is_err());
+}
+
+/// Verify a panic in a function body becomes a Ring error that `try/catch` handles, whether the
+/// function comes from `ring_func!`, `register_fn` or `ring_extension!`
+#[test]
+fn test_vm_panic_caught() {
+    crate::ring_func!(rs_test_panic, |_p| panic!("from ring_func"));
+
+    let mut ring = crate::Ring::new();
+    ext::ringlib_init(ring.as_ptr());
+    crate::ring_register_function_str(ring.as_ptr(), "rs_panic", rs_test_panic).unwrap();
+    ring.register_fn(
+        "rs_panic_closure",
+        |_call| -> Result<f64, crate::ArgError> { panic!("from register_fn") },
+    )
+    .unwrap();
+    for (call, expected) in [
+        ("rs_panic()", "Rust panic: from ring_func"),
+        ("rs_panic_closure()", "Rust panic: from register_fn"),
+        (
+            r#"rsext_explode("from ring_extension")"#,
+            "Rust panic: from ring_extension",
+        ),
+    ] {
+        ring.run(&format!(
+            "message = \"\"\ntry {} catch message = cCatchError done",
+            call
+        ))
+        .unwrap();
+        let message = ring.get::<String>("message").unwrap();
+        assert!(message.contains(expected), "{}: {}", call, message);
+    }
+}
+
+/// Verify the extension registry keeps registration order, replaces a name in place, forgets
+/// unregistered names, and only loads opt-in extensions on request, once per call
+#[cfg(feature = "extension")]
+#[test]
+fn test_vm_extension_registry() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use crate::{
+        RingState, ring_extension_names, ring_load_extension, ring_register_extension_optin,
+        ring_unregister_extension,
+    };
+
+    static FIRST: AtomicUsize = AtomicUsize::new(0);
+    static SECOND: AtomicUsize = AtomicUsize::new(0);
+
+    extern "C" fn first(_state: RingState) {
+        FIRST.fetch_add(1, Ordering::SeqCst);
+    }
+    extern "C" fn second(_state: RingState) {
+        SECOND.fetch_add(1, Ordering::SeqCst);
+    }
+
+    // Other tests create states in parallel, so only opt-in extensions are registered here
+    let ours = || -> Vec<String> {
+        ring_extension_names()
+            .into_iter()
+            .filter(|name| name.starts_with("rs_registry_"))
+            .collect()
+    };
+    ring_register_extension_optin("rs_registry_b", first);
+    ring_register_extension_optin("rs_registry_a", first);
+    ring_register_extension_optin("rs_registry_b", second);
+    assert_eq!(ours(), ["rs_registry_b", "rs_registry_a"]);
+
+    let ring = crate::Ring::new();
+    assert_eq!(FIRST.load(Ordering::SeqCst), 0);
+    assert!(ring_load_extension(ring.as_ptr(), "rs_registry_b"));
+    assert!(ring_load_extension(ring.as_ptr(), "rs_registry_b"));
+    assert_eq!(FIRST.load(Ordering::SeqCst), 0);
+    assert_eq!(SECOND.load(Ordering::SeqCst), 2);
+
+    assert!(ring_unregister_extension("rs_registry_a"));
+    assert!(!ring_unregister_extension("rs_registry_a"));
+    assert_eq!(ours(), ["rs_registry_b"]);
+    assert!(!ring_load_extension(ring.as_ptr(), "rs_registry_a"));
+    assert!(matches!(
+        crate::StateBuilder::new()
+            .extension("rs_registry_a")
+            .build(),
+        Err(crate::RingError::UnknownExtension(_))
+    ));
+    assert!(ring_unregister_extension("rs_registry_b"));
+    assert_eq!(FIRST.load(Ordering::SeqCst), 0);
 }
 
 /// Verify stack size constant matches Ring's definition
@@ -58,3 +948,310 @@ fn test_output_constants() {
     assert_eq!(crate::RING_OUTPUT_RETLISTBYREF, 1);
     assert_eq!(crate::RING_OUTPUT_RETNEWREF, 2);
 }
+
+/// Verify runtime errors render their location
+#[test]
+fn test_ring_error_display() {
+    let error = crate::RingError::Runtime {
+        message: "Error (R1) : Cann't divide by zero !".to_string(),
+        file: Some("script.ring".to_string()),
+        line: 3,
+        call_stack: vec![],
+    };
+    assert_eq!(
+        error.to_string(),
+        "Error (R1) : Cann't divide by zero ! (in script.ring at line 3)"
+    );
+
+    let error = crate::RingError::from(std::io::Error::from(std::io::ErrorKind::NotFound));
+    assert!(matches!(error, crate::RingError::Io(_)));
+
+    let error = crate::RingError::UnknownExtension("admin".to_string());
+    assert_eq!(error.to_string(), "Extension not registered: admin");
+
+    let error = crate::RingError::EmptyFunctionGroup(crate::FunctionGroup::Os);
+    assert_eq!(error.to_string(), "No functions found for the Os group");
+}
+
+/// Verify argument errors render in Ring's message style
+#[test]
+fn test_arg_error_display() {
+    use crate::{ArgError, FromRing, IntoRing};
+
+    assert_eq!(
+        ArgError::Missing { index: 2, count: 1 }.to_string(),
+        "Bad parameters count! (parameter 2 requested, 1 given)"
+    );
+    assert_eq!(
+        ArgError::TypeMismatch {
+            index: 1,
+            expected: "number",
+            found: "string",
+        }
+        .to_string(),
+        "Bad parameter type! (parameter 1: expected number, found string)"
+    );
+
+    // What ring_get_value! raises when a conversion fails
+    let error = u8::from_ring(300.into_ring()).unwrap_err();
+    assert_eq!(
+        ArgError::from_ring_error(2, error).to_string(),
+        "Bad parameter value! (parameter 2: 300 is not a valid u8)"
+    );
+}
+
+/// Verify function names are checked before registration
+#[test]
+fn test_function_name_validation() {
+    use crate::ring_is_valid_function_name;
+
+    assert!(ring_is_valid_function_name("mylib_add"));
+    assert!(ring_is_valid_function_name("_sum2"));
+    assert!(!ring_is_valid_function_name(""));
+    assert!(!ring_is_valid_function_name("2sum"));
+    assert!(!ring_is_valid_function_name("MyAdd"));
+    assert!(!ring_is_valid_function_name("my add"));
+    assert!(!ring_is_valid_function_name("add\0"));
+}
+
+/// Verify types sharing a Ring type name still get distinct pointer types
+#[test]
+fn test_ring_type_names() {
+    use crate::ring_type_name;
+
+    mod a {
+        pub struct Point;
+        crate::ring_type!(Point);
+    }
+    mod b {
+        pub struct Point;
+        crate::ring_type!(Point);
+    }
+
+    let a = ring_type_name::<a::Point>();
+    let b = ring_type_name::<b::Point>();
+    assert_ne!(a, b);
+    assert!(a.to_str().unwrap().starts_with("Point@"));
+    assert_eq!(a, ring_type_name::<a::Point>());
+}
+
+/// Verify the same type gets a different pointer type in another library's name table
+#[test]
+fn test_ring_type_names_per_library() {
+    use std::sync::Mutex;
+
+    use crate::object::{TypeNames, registry_type_name};
+    use crate::ring_type_name;
+
+    struct Point;
+    crate::ring_type!(Point);
+
+    static OTHER: Mutex<Option<TypeNames>> = Mutex::new(None);
+    let other = registry_type_name::<Point>(&OTHER);
+    assert_ne!(other, ring_type_name::<Point>());
+    assert_eq!(other, registry_type_name::<Point>(&OTHER));
+}
+
+/// Verify enum values split into their variant name and payload
+#[test]
+fn test_ring_enum_variant() {
+    use crate::{RingValue, ring_enum_variant};
+
+    assert_eq!(
+        ring_enum_variant::<()>(RingValue::from("dot")).unwrap(),
+        ("dot".to_string(), vec![])
+    );
+    assert_eq!(
+        ring_enum_variant::<()>(RingValue::List(vec![
+            RingValue::from("circle"),
+            RingValue::Double(2.5)
+        ]))
+        .unwrap(),
+        ("circle".to_string(), vec![RingValue::Double(2.5)])
+    );
+    assert!(ring_enum_variant::<()>(RingValue::Double(1.0)).is_err());
+    assert!(ring_enum_variant::<()>(RingValue::List(vec![])).is_err());
+}
+
+/// Verify output written to stdout and stderr is captured
+#[cfg(not(target_family = "wasm"))]
+#[test]
+fn test_capture_output() {
+    use std::io::Write;
+
+    let (value, output) = crate::ring_capture_output(|| {
+        std::io::stdout().write_all(b"out\n").unwrap();
+        std::io::stderr().write_all(b"err\n").unwrap();
+        42
+    });
+    assert_eq!(value, 42);
+    assert_eq!(output, b"out\nerr\n");
+}
+
+/// Verify RingValue conversions that do not touch the VM
+#[test]
+fn test_ring_value_accessors() {
+    use crate::RingValue;
+
+    assert_eq!(RingValue::from(3), RingValue::Int(3));
+    assert_eq!(RingValue::from(1.5).as_f64(), Some(1.5));
+    assert_eq!(RingValue::from(true).as_f64(), Some(1.0));
+    assert_eq!(RingValue::from("ring").as_str(), Some("ring"));
+    assert_eq!(RingValue::from(&b"\xff\x00"[..]).as_str(), None);
+    assert_eq!(
+        RingValue::from(&b"\xff\x00"[..]).as_bytes(),
+        Some(&b"\xff\x00"[..])
+    );
+    assert_eq!(
+        RingValue::from(vec![RingValue::Nothing, 2.into()]).as_list(),
+        Some(&[RingValue::Nothing, RingValue::Int(2)][..])
+    );
+    assert_eq!(RingValue::Int(1).type_name(), "number");
+    assert_ne!(RingValue::Int(1), RingValue::Double(1.0));
+}
+
+/// Verify IntoRing/FromRing round trips and type checks
+#[test]
+fn test_ring_conversions() {
+    use crate::{FromRing, IntoRing, RingError, RingValue};
+    use std::collections::HashMap;
+
+    assert_eq!(i64::from_ring(42i64.into_ring()).unwrap(), 42);
+    assert!(bool::from_ring(true.into_ring()).unwrap());
+    assert!(i32::from_ring("42".into_ring()).is_err());
+    assert_eq!(u8::from_ring(255.into_ring()).unwrap(), 255);
+    assert_eq!(u32::from_ring(2.0.into_ring()).unwrap(), 2);
+    assert!(u8::from_ring(300.into_ring()).is_err());
+    assert!(u32::from_ring((-1).into_ring()).is_err());
+    assert!(i32::from_ring(1.5.into_ring()).is_err());
+    assert!(i64::from_ring(f64::NAN.into_ring()).is_err());
+    assert!(f32::from_ring(1e300.into_ring()).is_err());
+    assert!(matches!(
+        String::from_ring(RingValue::from(&b"\xff"[..])),
+        Err(RingError::Conversion(message)) if message.contains("not valid UTF-8")
+    ));
+
+    let list = (1, "two", vec![3.5]).into_ring();
+    assert_eq!(
+        <(u8, String, Vec<f64>)>::from_ring(list).unwrap(),
+        (1, "two".to_string(), vec![3.5])
+    );
+
+    assert_eq!(Option::<i32>::from_ring("".into_ring()).unwrap(), None);
+    assert_eq!(None::<i32>.into_ring(), RingValue::String(Vec::new()));
+
+    let map = HashMap::from([("a".to_string(), 1), ("b".to_string(), 2)]);
+    assert_eq!(
+        HashMap::<String, i32>::from_ring(map.clone().into_ring()).unwrap(),
+        map
+    );
+}
+
+/// Verify serde round trips through RingValue
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_ring_value() {
+    use crate::{RingValue, from_ring_value, to_ring_value};
+    use std::collections::BTreeMap;
+
+    let map = BTreeMap::from([("a".to_string(), vec![1u32, 2]), ("b".to_string(), vec![])]);
+    let value = to_ring_value(&map).unwrap();
+    assert_eq!(
+        value,
+        RingValue::List(vec![
+            RingValue::List(vec![
+                "a".into(),
+                RingValue::List(vec![1.0.into(), 2.0.into()])
+            ]),
+            RingValue::List(vec!["b".into(), RingValue::List(vec![])]),
+        ])
+    );
+    assert_eq!(
+        from_ring_value::<BTreeMap<String, Vec<u32>>>(value).unwrap(),
+        map
+    );
+
+    let tuple = (Some(1.5), None::<i64>, "ring".to_string(), true);
+    let value = to_ring_value(&tuple).unwrap();
+    assert_eq!(
+        from_ring_value::<(Option<f64>, Option<i64>, String, bool)>(value).unwrap(),
+        tuple
+    );
+
+    assert!(from_ring_value::<Vec<i32>>(RingValue::from("x")).is_err());
+}
+
+/// Verify enums convert between serde and the IntoRing/FromRing derives in both directions
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_derive_enum_layout() {
+    use crate::{FromRing, IntoRing, RingValue, from_ring_value, to_ring_value};
+    use ring_lang_codegen::{FromRing, IntoRing};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, IntoRing, FromRing)]
+    enum Shape {
+        Empty,
+        Circle(f64),
+        Line(f64, f64),
+        DarkRect { width: f64, height: f64 },
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, IntoRing, FromRing)]
+    enum Color {
+        Red,
+        DarkBlue,
+    }
+
+    let shapes = [
+        Shape::Empty,
+        Shape::Circle(1.5),
+        Shape::Line(1.0, 2.0),
+        Shape::DarkRect {
+            width: 3.0,
+            height: 4.0,
+        },
+    ];
+    for shape in shapes {
+        let serialized = to_ring_value(&shape).unwrap();
+        assert_eq!(Shape::from_ring(serialized).unwrap(), shape);
+        let derived = shape.clone().into_ring();
+        assert_eq!(from_ring_value::<Shape>(derived).unwrap(), shape);
+    }
+    assert_eq!(
+        to_ring_value(&Shape::Line(1.0, 2.0)).unwrap(),
+        RingValue::List(vec!["Line".into(), 1.0.into(), 2.0.into()])
+    );
+
+    for color in [Color::Red, Color::DarkBlue] {
+        assert_eq!(
+            Color::from_ring(to_ring_value(&color).unwrap()).unwrap(),
+            color
+        );
+        assert_eq!(from_ring_value::<Color>(color.into_ring()).unwrap(), color);
+    }
+}
+
+/// Verify numbers are only deserialized into integer types that hold them exactly
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_number_range() {
+    use crate::{RingValue, from_ring_value};
+
+    assert_eq!(
+        from_ring_value::<u8>(RingValue::Double(255.0)).unwrap(),
+        255
+    );
+    assert_eq!(from_ring_value::<i64>(RingValue::Int(-7)).unwrap(), -7);
+    assert_eq!(
+        from_ring_value::<u64>(RingValue::Double(9007199254740992.0)).unwrap(),
+        9007199254740992
+    );
+    assert!(from_ring_value::<u8>(RingValue::Double(256.0)).is_err());
+    assert!(from_ring_value::<u32>(RingValue::Int(-1)).is_err());
+    assert!(from_ring_value::<i32>(RingValue::Double(1.5)).is_err());
+    assert!(from_ring_value::<i64>(RingValue::Double(9.3e18)).is_err());
+    assert!(from_ring_value::<i16>(RingValue::Double(f64::NAN)).is_err());
+    assert!(from_ring_value::<f32>(RingValue::Double(1e40)).is_err());
+    assert_eq!(from_ring_value::<f32>(RingValue::Double(0.5)).unwrap(), 0.5);
+}
@@ -0,0 +1,534 @@
+use crate::ffi_types::{CString, c_int, c_uint, c_void};
+
+use std::cell::Cell;
+
+use crate::ffi::{
+    self, ITEM_NUMBERFLAG_DOUBLE, ITEM_NUMBERFLAG_INT, ITEMTYPE_FUNCPOINTER, ITEMTYPE_LIST,
+    ITEMTYPE_NUMBER, ITEMTYPE_POINTER, ITEMTYPE_STRING, Item,
+};
+use crate::{
+    RING_CPOINTER_POINTER, RING_CPOINTER_TYPE, RING_OBJECT_CLASSPTR, RING_OBJECT_OBJECTDATA,
+    RING_OBJTYPE_LISTITEM, RING_OBJTYPE_SUBLIST, RING_OBJTYPE_VARIABLE, RING_VAR_NAME,
+    RING_VAR_TYPE, RING_VAR_VALUE, RING_VM_LIST, RING_VM_NUMBER, RING_VM_POINTER, RING_VM_STRING,
+    RingList, ring_api_getlist, ring_api_getnumber, ring_api_getstring_vec, ring_api_iscpointer,
+    ring_api_islist, ring_api_isnumber, ring_api_isstring, ring_api_newlist, ring_api_retcpointer2,
+    ring_api_retlist, ring_api_retnumber, ring_api_retstring2, ring_list_addcpointer,
+    ring_list_adddouble, ring_list_addfuncpointer, ring_list_addint, ring_list_addpointer,
+    ring_list_addstring2, ring_list_getitem, ring_list_getlist, ring_list_getpointer,
+    ring_list_getsize, ring_list_getstring_str, ring_list_iscpointerlist, ring_list_isobject,
+    ring_list_new, ring_list_newitem, ring_list_newlist, ring_list_setdouble,
+    ring_list_setfuncpointer, ring_list_setint, ring_list_setlist, ring_list_setstring2,
+};
+
+/// Name given to the attribute that holds an object's `self` reference.
+const RING_OBJECT_SELF: &str = "self";
+
+/// How many lists deep [`RingValue::from_list`] copies before cutting the value off, so data
+/// built by a script cannot overflow the Rust stack.
+pub const RING_VALUE_MAX_DEPTH: usize = 256;
+
+/// An owned copy of a Ring value.
+///
+/// `RingValue` mirrors Ring's item types, so a whole Ring data structure can be moved across the
+/// boundary in one call with [`RingValue::from_list`] / [`RingValue::to_list`] or
+/// [`ring_api_getvalue`] / [`ring_api_retvalue`].
+///
+/// Cloning a value copies everything except the ownership of managed C pointers (see
+/// [`RingFreeFunc`]), so a clone never frees what the original owns.
+#[derive(Debug, Default)]
+pub enum RingValue {
+    /// An item without a value.
+    #[default]
+    Nothing,
+    /// A number stored as an integer (`ITEM_NUMBERFLAG_INT`).
+    Int(c_int),
+    /// A number stored as a double.
+    Double(f64),
+    /// A string. Ring strings are binary-safe, so this is a byte buffer.
+    String(Vec<u8>),
+    /// A list.
+    List(Vec<RingValue>),
+    /// An object: its class name and attributes (without `self`).
+    Object {
+        class: String,
+        attributes: Vec<(String, RingValue)>,
+    },
+    /// A C pointer list (`[pointer, type, status]`), or a raw pointer item when `type_name` is empty.
+    ///
+    /// `free_func` hands the pointee to Ring's garbage collector the first time the value is
+    /// stored in Ring. Values read from Ring always have `None`, since the pointer's owner is
+    /// unknown.
+    CPointer {
+        type_name: String,
+        ptr: *mut c_void,
+        free_func: Option<RingFreeFunc>,
+    },
+    /// A C function pointer.
+    FuncPointer(extern "C" fn(*mut c_void)),
+}
+
+/// The function Ring's garbage collector calls to free the pointee of a
+/// [`RingValue::CPointer`].
+///
+/// Ownership moves to Ring once: the first time the value is stored in a list or returned to
+/// Ring, the function is taken out, so storing the same value again (or a clone of it, which
+/// never has one) hands Ring a pointer it does not free.
+#[derive(Debug)]
+pub struct RingFreeFunc(Cell<Option<extern "C" fn(*mut c_void, *mut c_void)>>);
+
+impl RingFreeFunc {
+    /// Wrap `func`, which frees the pointer of the value this is attached to.
+    ///
+    /// # Safety
+    ///
+    /// The value's pointer must be owned by that value alone and valid to pass to `func`, which
+    /// Ring calls with it at most once.
+    pub unsafe fn new(func: extern "C" fn(*mut c_void, *mut c_void)) -> Self {
+        RingFreeFunc(Cell::new(Some(func)))
+    }
+
+    /// Whether Ring has not taken ownership of the pointer yet.
+    pub fn is_owned(&self) -> bool {
+        self.0.get().is_some()
+    }
+
+    /// Take the function out, handing ownership of the pointer to whoever calls it.
+    fn take(&self) -> Option<extern "C" fn(*mut c_void, *mut c_void)> {
+        self.0.take()
+    }
+}
+
+impl RingValue {
+    /// Copy a whole Ring list. C pointer lists and objects are recognized and converted to
+    /// [`RingValue::CPointer`] and [`RingValue::Object`].
+    ///
+    /// A list that contains itself through a reference, and anything nested more than
+    /// [`RING_VALUE_MAX_DEPTH`] lists deep, is copied as [`RingValue::Nothing`] at that point.
+    pub fn from_list(list: RingList) -> RingValue {
+        Self::copy_list(list, &mut Vec::new())
+    }
+
+    /// Copy one item of a Ring list (1-based index).
+    pub fn from_list_item(list: RingList, index: c_uint) -> RingValue {
+        Self::copy_list_item(list, index, &mut Vec::new())
+    }
+
+    /// Copy a Ring item.
+    ///
+    /// Pointer items the VM uses as references (a variable, a list item or a sub-list, as found
+    /// on the VM stack) are followed; other pointers become a raw [`RingValue::CPointer`].
+    /// Cycles and deep nesting are cut off as in [`RingValue::from_list`].
+    ///
+    /// # Safety
+    ///
+    /// `item` must be null or point to a valid item.
+    pub unsafe fn from_item(item: *mut Item) -> RingValue {
+        unsafe { Self::copy_item(item, &mut Vec::new()) }
+    }
+
+    /// Copy `list`, with `path` holding the lists being copied around it.
+    fn copy_list(list: RingList, path: &mut Vec<RingList>) -> RingValue {
+        if list.is_null() || path.len() >= RING_VALUE_MAX_DEPTH || path.contains(&list) {
+            return RingValue::Nothing;
+        }
+        if ring_list_iscpointerlist(list) {
+            return RingValue::CPointer {
+                type_name: ring_list_getstring_str(list, RING_CPOINTER_TYPE),
+                ptr: ring_list_getpointer(list, RING_CPOINTER_POINTER),
+                free_func: None,
+            };
+        }
+        path.push(list);
+        let value = if ring_list_isobject(list) {
+            Self::copy_object(list, path)
+        } else {
+            let size = ring_list_getsize(list);
+            RingValue::List(
+                (1..=size)
+                    .map(|i| Self::copy_list_item(list, i, path))
+                    .collect(),
+            )
+        };
+        path.pop();
+        value
+    }
+
+    fn copy_list_item(list: RingList, index: c_uint, path: &mut Vec<RingList>) -> RingValue {
+        unsafe { Self::copy_item(ring_list_getitem(list, index), path) }
+    }
+
+    unsafe fn copy_item(item: *mut Item, path: &mut Vec<RingList>) -> RingValue {
+        if item.is_null() {
+            return RingValue::Nothing;
+        }
+        unsafe {
+            match (*item).nType() {
+                ITEMTYPE_STRING => {
+                    let string = (*item).data.pString;
+                    if string.is_null() || (*string).cStr.is_null() {
+                        RingValue::String(Vec::new())
+                    } else {
+                        RingValue::String(
+                            std::slice::from_raw_parts(
+                                (*string).cStr as *const u8,
+                                (*string).nSize as usize,
+                            )
+                            .to_vec(),
+                        )
+                    }
+                }
+                ITEMTYPE_NUMBER => match (*item).nNumberFlag() {
+                    ITEM_NUMBERFLAG_INT => RingValue::Int((*item).data.iNumber),
+                    ITEM_NUMBERFLAG_DOUBLE => RingValue::Double((*item).data.dNumber),
+                    _ => RingValue::Double(ffi::ring_item_getnumber(item)),
+                },
+                ITEMTYPE_POINTER => {
+                    let ptr = (*item).data.pPointer;
+                    match (*item).nObjectType() {
+                        _ if ptr.is_null() => RingValue::Nothing,
+                        RING_OBJTYPE_VARIABLE => {
+                            Self::copy_list_item(ptr as RingList, RING_VAR_VALUE, path)
+                        }
+                        RING_OBJTYPE_LISTITEM => Self::copy_item(ptr as *mut Item, path),
+                        RING_OBJTYPE_SUBLIST => Self::copy_list(ptr as RingList, path),
+                        _ => RingValue::CPointer {
+                            type_name: String::new(),
+                            ptr,
+                            free_func: None,
+                        },
+                    }
+                }
+                ITEMTYPE_LIST => Self::copy_list((*item).data.pList, path),
+                ITEMTYPE_FUNCPOINTER => match (*item).data.pFunc {
+                    Some(func) => RingValue::FuncPointer(func),
+                    None => RingValue::Nothing,
+                },
+                _ => RingValue::Nothing,
+            }
+        }
+    }
+
+    fn copy_object(list: RingList, path: &mut Vec<RingList>) -> RingValue {
+        let class_list = ring_list_getpointer(list, RING_OBJECT_CLASSPTR) as RingList;
+        let class = if class_list.is_null() {
+            String::new()
+        } else {
+            ring_list_getstring_str(class_list, 1)
+        };
+
+        let data = ring_list_getlist(list, RING_OBJECT_OBJECTDATA);
+        let mut attributes = Vec::new();
+        for i in 1..=ring_list_getsize(data) {
+            let var = ring_list_getlist(data, i);
+            let name = ring_list_getstring_str(var, RING_VAR_NAME);
+            if name == RING_OBJECT_SELF {
+                continue;
+            }
+            attributes.push((name, Self::copy_list_item(var, RING_VAR_VALUE, path)));
+        }
+        RingValue::Object { class, attributes }
+    }
+
+    /// Create a new Ring list holding this value. The caller owns the list and must delete it
+    /// with [`ring_list_delete`](crate::ring_list_delete).
+    ///
+    /// A [`RingValue::List`] becomes a list of its items and a [`RingValue::CPointer`] becomes a C
+    /// pointer list, so both round-trip through [`RingValue::from_list`]. An object becomes a
+    /// list of `[name, value]` pairs because only the VM can instantiate a class. Any other
+    /// value becomes a one-item list.
+    pub fn to_list(&self) -> RingList {
+        let list = ring_list_new(0);
+        self.fill_list(list);
+        list
+    }
+
+    /// Append the contents of this value to an existing list (see [`RingValue::to_list`]).
+    pub fn fill_list(&self, list: RingList) {
+        match self {
+            RingValue::List(items) => {
+                for item in items {
+                    item.add_to_list(list);
+                }
+            }
+            RingValue::Object { attributes, .. } => {
+                for (name, value) in attributes {
+                    let pair = ring_list_newlist(list);
+                    ring_list_addstring2(pair, name.as_bytes());
+                    value.add_to_list(pair);
+                }
+            }
+            RingValue::CPointer {
+                type_name,
+                ptr,
+                free_func,
+            } if !type_name.is_empty() => {
+                let ctype = CString::new(type_name.as_str()).unwrap_or_default();
+                ring_list_addpointer(list, *ptr);
+                ring_list_addstring2(list, ctype.as_bytes());
+                ring_list_addint(list, crate::RING_CPOINTERSTATUS_NOTCOPIED);
+                set_free_func(list, free_func);
+            }
+            other => other.add_to_list(list),
+        }
+    }
+
+    /// Append this value to `list` as a single new item.
+    pub fn add_to_list(&self, list: RingList) {
+        match self {
+            RingValue::Nothing => ring_list_newitem(list),
+            RingValue::Int(n) => ring_list_addint(list, *n),
+            RingValue::Double(n) => ring_list_adddouble(list, *n),
+            RingValue::String(bytes) => ring_list_addstring2(list, bytes),
+            RingValue::List(_) | RingValue::Object { .. } => {
+                self.fill_list(ring_list_newlist(list));
+            }
+            RingValue::CPointer {
+                type_name,
+                ptr,
+                free_func,
+            } => {
+                if type_name.is_empty() {
+                    ring_list_addpointer(list, *ptr);
+                } else {
+                    let ctype = CString::new(type_name.as_str()).unwrap_or_default();
+                    ring_list_addcpointer(list, *ptr, ctype.as_bytes_with_nul());
+                    set_free_func(ring_list_getlist(list, ring_list_getsize(list)), free_func);
+                }
+            }
+            RingValue::FuncPointer(func) => ring_list_addfuncpointer(list, *func),
+        }
+    }
+
+    /// Store this value in a variable list (as returned by
+    /// [`ring_state_newvar`](crate::ring_state_newvar)), replacing its value and type.
+    ///
+    /// Lists, C pointers and objects are stored as lists (see [`RingValue::to_list`]), and
+    /// [`RingValue::Nothing`] as an empty string, which is Ring's `NULL`.
+    pub fn set_variable(&self, var: RingList) {
+        match self {
+            RingValue::Nothing => {
+                ring_list_setint(var, RING_VAR_TYPE, RING_VM_STRING);
+                ring_list_setstring2(var, RING_VAR_VALUE, b"");
+            }
+            RingValue::Int(n) => {
+                ring_list_setint(var, RING_VAR_TYPE, RING_VM_NUMBER);
+                ring_list_setdouble(var, RING_VAR_VALUE, *n as f64);
+            }
+            RingValue::Double(n) => {
+                ring_list_setint(var, RING_VAR_TYPE, RING_VM_NUMBER);
+                ring_list_setdouble(var, RING_VAR_VALUE, *n);
+            }
+            RingValue::String(bytes) => {
+                ring_list_setint(var, RING_VAR_TYPE, RING_VM_STRING);
+                ring_list_setstring2(var, RING_VAR_VALUE, bytes);
+            }
+            RingValue::FuncPointer(func) => {
+                ring_list_setint(var, RING_VAR_TYPE, RING_VM_POINTER);
+                ring_list_setfuncpointer(var, RING_VAR_VALUE, *func);
+            }
+            other => {
+                ring_list_setint(var, RING_VAR_TYPE, RING_VM_LIST);
+                ring_list_setlist(var, RING_VAR_VALUE);
+                other.fill_list(ring_list_getlist(var, RING_VAR_VALUE));
+            }
+        }
+    }
+
+    pub fn is_nothing(&self) -> bool {
+        matches!(self, RingValue::Nothing)
+    }
+
+    /// The value as a number, if it is one.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            RingValue::Int(n) => Some(*n as f64),
+            RingValue::Double(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// The value as UTF-8 text, if it is a valid UTF-8 string.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            RingValue::String(bytes) => std::str::from_utf8(bytes).ok(),
+            _ => None,
+        }
+    }
+
+    /// The raw bytes of a string value.
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            RingValue::String(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    /// The items of a list value.
+    pub fn as_list(&self) -> Option<&[RingValue]> {
+        match self {
+            RingValue::List(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    /// Name of the Ring type this value maps to.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            RingValue::Nothing => "nothing",
+            RingValue::Int(_) | RingValue::Double(_) => "number",
+            RingValue::String(_) => "string",
+            RingValue::List(_) => "list",
+            RingValue::Object { .. } => "object",
+            RingValue::CPointer { .. } => "pointer",
+            RingValue::FuncPointer(_) => "function pointer",
+        }
+    }
+}
+
+impl Clone for RingValue {
+    fn clone(&self) -> Self {
+        match self {
+            RingValue::Nothing => RingValue::Nothing,
+            RingValue::Int(n) => RingValue::Int(*n),
+            RingValue::Double(n) => RingValue::Double(*n),
+            RingValue::String(bytes) => RingValue::String(bytes.clone()),
+            RingValue::List(items) => RingValue::List(items.clone()),
+            RingValue::Object { class, attributes } => RingValue::Object {
+                class: class.clone(),
+                attributes: attributes.clone(),
+            },
+            RingValue::CPointer { type_name, ptr, .. } => RingValue::CPointer {
+                type_name: type_name.clone(),
+                ptr: *ptr,
+                free_func: None,
+            },
+            RingValue::FuncPointer(func) => RingValue::FuncPointer(*func),
+        }
+    }
+}
+
+impl PartialEq for RingValue {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (RingValue::Nothing, RingValue::Nothing) => true,
+            (RingValue::Int(a), RingValue::Int(b)) => a == b,
+            (RingValue::Double(a), RingValue::Double(b)) => a == b,
+            (RingValue::String(a), RingValue::String(b)) => a == b,
+            (RingValue::List(a), RingValue::List(b)) => a == b,
+            (
+                RingValue::Object {
+                    class: a,
+                    attributes: x,
+                },
+                RingValue::Object {
+                    class: b,
+                    attributes: y,
+                },
+            ) => a == b && x == y,
+            (
+                RingValue::CPointer {
+                    type_name: a,
+                    ptr: x,
+                    ..
+                },
+                RingValue::CPointer {
+                    type_name: b,
+                    ptr: y,
+                    ..
+                },
+            ) => a == b && x == y,
+            (RingValue::FuncPointer(a), RingValue::FuncPointer(b)) => std::ptr::fn_addr_eq(*a, *b),
+            _ => false,
+        }
+    }
+}
+
+impl From<f64> for RingValue {
+    fn from(n: f64) -> Self {
+        RingValue::Double(n)
+    }
+}
+
+impl From<c_int> for RingValue {
+    fn from(n: c_int) -> Self {
+        RingValue::Int(n)
+    }
+}
+
+impl From<bool> for RingValue {
+    fn from(b: bool) -> Self {
+        RingValue::Int(b as c_int)
+    }
+}
+
+impl From<&str> for RingValue {
+    fn from(s: &str) -> Self {
+        RingValue::String(s.as_bytes().to_vec())
+    }
+}
+
+impl From<String> for RingValue {
+    fn from(s: String) -> Self {
+        RingValue::String(s.into_bytes())
+    }
+}
+
+impl From<&[u8]> for RingValue {
+    fn from(bytes: &[u8]) -> Self {
+        RingValue::String(bytes.to_vec())
+    }
+}
+
+impl From<Vec<RingValue>> for RingValue {
+    fn from(items: Vec<RingValue>) -> Self {
+        RingValue::List(items)
+    }
+}
+
+/// Read parameter `n` of the current extension call as a [`RingValue`].
+pub fn ring_api_getvalue(p: *mut c_void, n: c_int) -> RingValue {
+    if ring_api_isnumber(p, n) {
+        RingValue::Double(ring_api_getnumber(p, n))
+    } else if ring_api_isstring(p, n) {
+        RingValue::String(ring_api_getstring_vec(p, n))
+    } else if ring_api_islist(p, n) || ring_api_iscpointer(p, n) {
+        RingValue::from_list(ring_api_getlist(p, n))
+    } else {
+        RingValue::Nothing
+    }
+}
+
+/// Give the pointer of C pointer list `list` to Ring's garbage collector, if it is still owned.
+fn set_free_func(list: RingList, free_func: &Option<RingFreeFunc>) {
+    let item = ring_list_getitem(list, RING_CPOINTER_POINTER);
+    if item.is_null() {
+        return;
+    }
+    if let Some(func) = free_func.as_ref().and_then(RingFreeFunc::take) {
+        unsafe { (*item).pGCFreeFunc = Some(func) };
+    }
+}
+
+/// Return a [`RingValue`] from the current extension call.
+pub fn ring_api_retvalue(p: *mut c_void, value: &RingValue) {
+    match value {
+        RingValue::Nothing => ring_api_retstring2(p, b""),
+        RingValue::Int(n) => ring_api_retnumber(p, *n as f64),
+        RingValue::Double(n) => ring_api_retnumber(p, *n),
+        RingValue::String(bytes) => ring_api_retstring2(p, bytes),
+        RingValue::CPointer {
+            type_name,
+            ptr,
+            free_func,
+        } if !type_name.is_empty() => {
+            let ctype = CString::new(type_name.as_str()).unwrap_or_default();
+            let free_func = free_func.as_ref().and_then(RingFreeFunc::take);
+            ring_api_retcpointer2(p, *ptr, ctype.as_bytes_with_nul(), free_func);
+        }
+        other => {
+            let list = ring_api_newlist(p);
+            other.fill_list(list);
+            ring_api_retlist(p, list);
+        }
+    }
+}
@@ -1,6 +1,10 @@
 use crate::ffi_types::{CString, c_char, c_int, c_uint, c_void};
 
-use crate::ffi::{self, ITEM_NUMBERFLAG_DOUBLE, ITEMTYPE_STRING, RING_FALSE, RingVM};
+use crate::ffi::{self, Item, RING_FALSE, RingList, RingVM};
+use crate::{
+    FromRing, IntoRing, RING_OBJTYPE_SUBLIST, RingError, RingValue, ring_list_delete,
+    ring_list_new, ring_list_newlist,
+};
 
 #[inline]
 pub fn ring_vm_callfunction(vm: RingVM, func_name: &[u8]) {
@@ -123,36 +127,236 @@ pub fn ring_vm_set_funcsp(vm: RingVM, funcsp: c_uint) {
 #[inline]
 pub unsafe fn ring_vm_stack_push_cvalue(vm: RingVM, s: &[u8]) {
     unsafe {
-        let sp = (*vm).nSP as usize;
         (*vm).nSP += 1;
-        let item = &mut (*vm).aStack[sp];
-        item.flags = ITEMTYPE_STRING;
-        ffi::ring_item_setstring2(item, s.as_ptr() as *const c_char, s.len() as c_uint);
+        ffi::ring_itemarray_setstring2(
+            (*vm).aStack.as_mut_ptr(),
+            (*vm).nSP,
+            s.as_ptr() as *const c_char,
+            s.len() as c_uint,
+        );
     }
 }
 
 #[inline]
 pub unsafe fn ring_vm_stack_push_number(vm: RingVM, num: f64) {
     unsafe {
-        let sp = (*vm).nSP as usize;
         (*vm).nSP += 1;
-        let item = &mut (*vm).aStack[sp];
-        item.flags = (ITEM_NUMBERFLAG_DOUBLE << 3) | ffi::ITEMTYPE_NUMBER;
-        item.data.dNumber = num;
+        ffi::ring_itemarray_setdouble((*vm).aStack.as_mut_ptr(), (*vm).nSP, num);
     }
 }
 
 #[inline]
 pub unsafe fn ring_vm_stack_push_int(vm: RingVM, num: c_int) {
     unsafe {
-        let sp = (*vm).nSP as usize;
         (*vm).nSP += 1;
-        let item = &mut (*vm).aStack[sp];
-        item.flags = (ffi::ITEM_NUMBERFLAG_INT << 3) | ffi::ITEMTYPE_NUMBER;
-        item.data.iNumber = num;
+        ffi::ring_itemarray_setint((*vm).aStack.as_mut_ptr(), (*vm).nSP, num);
     }
 }
 
+/// Push a list onto the VM stack the way a C function's returned list is pushed, as a pointer to
+/// `list`. The list must outlive the instruction or call that takes it.
+#[inline]
+pub unsafe fn ring_vm_stack_push_list(vm: RingVM, list: RingList) {
+    unsafe {
+        (*vm).nSP += 1;
+        ffi::ring_itemarray_setpointer((*vm).aStack.as_mut_ptr(), (*vm).nSP, list as *mut c_void);
+        (*ring_vm_stack_active(vm)).set_nObjectType(RING_OBJTYPE_SUBLIST);
+    }
+}
+
+/// The item on top of the VM stack (`aStack[nSP]`).
+#[inline]
+pub unsafe fn ring_vm_stack_active(vm: RingVM) -> *mut Item {
+    unsafe { &mut (*vm).aStack[(*vm).nSP as usize] }
+}
+
+// Calling Ring functions from Rust
+
+/// A value that can be passed as an argument to [`ring_vm_call`] and
+/// [`Ring::call`](crate::Ring::call). Every cloneable [`IntoRing`] type is one.
+pub trait CallArg {
+    /// Convert the argument to the value passed to Ring.
+    fn to_value(&self) -> RingValue;
+}
+
+impl<T: IntoRing + Clone> CallArg for T {
+    fn to_value(&self) -> RingValue {
+        self.clone().into_ring()
+    }
+}
+
+/// The argument list of [`ring_vm_call`]: `()`, a tuple of [`CallArg`]s, or a slice/`Vec` of one
+/// [`CallArg`] type.
+pub trait CallArgs {
+    /// Every argument, first argument first.
+    fn to_values(&self) -> Vec<RingValue>;
+}
+
+impl CallArgs for () {
+    fn to_values(&self) -> Vec<RingValue> {
+        Vec::new()
+    }
+}
+
+impl<T: CallArg> CallArgs for [T] {
+    fn to_values(&self) -> Vec<RingValue> {
+        self.iter().map(CallArg::to_value).collect()
+    }
+}
+
+impl<T: CallArg, const N: usize> CallArgs for [T; N] {
+    fn to_values(&self) -> Vec<RingValue> {
+        self[..].to_values()
+    }
+}
+
+impl<T: CallArg> CallArgs for Vec<T> {
+    fn to_values(&self) -> Vec<RingValue> {
+        self[..].to_values()
+    }
+}
+
+impl<T: CallArgs + ?Sized> CallArgs for &T {
+    fn to_values(&self) -> Vec<RingValue> {
+        (**self).to_values()
+    }
+}
+
+macro_rules! impl_call_args_tuple {
+    ($($name:ident),+) => {
+        impl<$($name: CallArg),+> CallArgs for ($($name,)+) {
+            #[allow(non_snake_case)]
+            fn to_values(&self) -> Vec<RingValue> {
+                let ($($name,)+) = self;
+                vec![$($name.to_value()),+]
+            }
+        }
+    };
+}
+
+impl_call_args_tuple!(A);
+impl_call_args_tuple!(A, B);
+impl_call_args_tuple!(A, B, C);
+impl_call_args_tuple!(A, B, C, D);
+impl_call_args_tuple!(A, B, C, D, E);
+impl_call_args_tuple!(A, B, C, D, E, F);
+impl_call_args_tuple!(A, B, C, D, E, F, G);
+impl_call_args_tuple!(A, B, C, D, E, F, G, H);
+
+/// Call a Ring (or registered C) function with arguments and convert its return value.
+///
+/// The function is loaded with `ring_vm_loadfunc2`, the arguments are pushed onto the VM stack,
+/// and `ring_vm_call2` starts the call. The VM then fetches instructions until the call returns.
+/// `nSP` and `nFuncSP` are restored afterwards, so this can be used from embedding code and
+/// from inside a C function callback.
+///
+/// Numbers and strings are pushed directly and [`RingValue::Nothing`] as an empty string
+/// (Ring's `NULL`). Lists, objects and C pointers are built in a temporary list, as by
+/// [`RingValue::to_list`], that the function receives like a list returned by a C function and
+/// that is deleted once the call returns. A function pointer is a [`RingError::TypeMismatch`].
+/// Arguments that would not fit on the VM's stack are a [`RingError::StackOverflow`], returned
+/// before anything is pushed.
+///
+/// Runtime errors inside the called function follow the VM's normal error handling; use
+/// [`Ring::call`](crate::Ring::call) if the script must not be able to terminate the host. If
+/// an error is caught by a `try` outside the call, the stack is left as the `catch` set it and a
+/// [`RingError::Runtime`] is returned.
+///
+/// ```rust,ignore
+/// let sum: f64 = ring_vm_call(vm, "add", (1, 2))?;
+/// let greeting: String = ring_vm_call(vm, "greet", ("World",))?;
+/// let total: f64 = ring_vm_call(vm, "sum", (vec![1, 2, 3],))?;
+/// ```
+pub fn ring_vm_call<A, R>(vm: RingVM, func_name: &str, args: A) -> Result<R, RingError>
+where
+    A: CallArgs,
+    R: FromRing,
+{
+    ring_vm_call_with(vm, func_name, args, ring_vm_fetch)
+}
+
+/// [`ring_vm_call`] running each instruction of the call with `fetch`, which advances the VM
+/// through [`ring_vm_fetch`] and may check limits first.
+pub(crate) fn ring_vm_call_with<A, R>(
+    vm: RingVM,
+    func_name: &str,
+    args: A,
+    mut fetch: impl FnMut(RingVM),
+) -> Result<R, RingError>
+where
+    A: CallArgs,
+    R: FromRing,
+{
+    let cname = CString::new(func_name.to_lowercase())
+        .map_err(|_| RingError::UndefinedFunction(func_name.to_string()))?;
+    let args = args.to_values();
+    if let Some(arg) = args
+        .iter()
+        .find(|arg| matches!(arg, RingValue::FuncPointer(_)))
+    {
+        return Err(RingError::TypeMismatch {
+            expected: "number, string, list, object or C pointer",
+            found: arg.type_name(),
+        });
+    }
+
+    let sp = ring_vm_get_sp(vm);
+    // The arguments go to aStack[sp + 1..] and the call takes one aFuncCall frame
+    if sp as usize + args.len() >= ffi::RING_VM_STACK_SIZE
+        || ring_vm_funccallscount(vm) as usize + 1 >= ffi::RING_VM_STACK_SIZE
+    {
+        return Err(RingError::StackOverflow);
+    }
+    let funcsp = ring_vm_get_funcsp(vm);
+    let depth = ring_vm_funccallscount(vm);
+
+    if unsafe { ffi::ring_vm_loadfunc2(vm, cname.as_ptr(), RING_FALSE) } == 0 {
+        ring_vm_set_sp(vm, sp);
+        ring_vm_set_funcsp(vm, funcsp);
+        return Err(RingError::UndefinedFunction(func_name.to_string()));
+    }
+
+    // Holds the list arguments until the call returns
+    let lists = ring_list_new(0);
+    for arg in &args {
+        match arg {
+            RingValue::Nothing => unsafe { ring_vm_stack_push_cvalue(vm, b"") },
+            RingValue::String(bytes) => unsafe { ring_vm_stack_push_cvalue(vm, bytes) },
+            RingValue::Int(n) => unsafe { ring_vm_stack_push_number(vm, *n as f64) },
+            RingValue::Double(n) => unsafe { ring_vm_stack_push_number(vm, *n) },
+            other => {
+                let list = ring_list_newlist(lists);
+                other.fill_list(list);
+                unsafe { ring_vm_stack_push_list(vm, list) }
+            }
+        }
+    }
+    ring_vm_call2(vm);
+    while ring_vm_funccallscount(vm) > depth {
+        fetch(vm);
+    }
+
+    if ring_vm_funccallscount(vm) < depth {
+        ring_list_delete(lists);
+        return Err(RingError::Runtime {
+            message: format!("The call to {} was stopped by an error", func_name),
+            file: None,
+            line: unsafe { (*vm).nLineNumber },
+            call_stack: Vec::new(),
+        });
+    }
+    let value = if ring_vm_get_sp(vm) > sp {
+        unsafe { RingValue::from_item(ring_vm_stack_active(vm)) }
+    } else {
+        RingValue::Nothing
+    };
+
+    ring_vm_set_sp(vm, sp);
+    ring_vm_set_funcsp(vm, funcsp);
+    ring_list_delete(lists);
+    R::from_ring(value)
+}
+
 // VM execution and utilities
 
 #[inline]